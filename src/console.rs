@@ -0,0 +1,318 @@
+//!
+//! A drop-down console, to control the program using text commands
+//! HanishKVC, 2022
+//!
+//! The commands entered are mapped to the same ProgramEvents as generated
+//! by the key shortcuts, so that they are handled by a common logic.
+//!
+//! Commands which dont belong to the console, but which are valid
+//! monitor/trainer commands, are sent to the playdata source as is.
+//!
+
+use sdl2::event::Event;
+use sdl2::keyboard::Keycode;
+use sdl2::pixels::Color;
+
+use crate::entities;
+use crate::keys::ProgramEvent;
+use crate::proc::actions;
+use crate::playdata::rcss::RCCmd;
+use crate::sdlx::SdlX;
+
+
+const CONSOLE_RECT: (f32, f32, f32, f32) = (0.0, 0.0, 1.0, 0.40);
+const CONSOLE_VIEWLINES: usize = 12;
+const CONSOLE_OUT_MAX: usize = 256;
+const CONSOLE_HISTORY_MAX: usize = 64;
+const CONSOLE_PROMPT: &str = "> ";
+/// The number of possession sequences listed, if not specified
+const SEQ_LIST_DEFAULT: usize = 10;
+
+const CONSOLE_HELP: [&str; 15] = [
+    "seek <time> | seek +N | seek -N",
+    "fps <fps>",
+    "pause",
+    "select <l|r><unum> | select none",
+    "export json <path/file.json>",
+    "seq list [count] | seq <index|+N|-N|none>",
+    "offsides",
+    "periods",
+    "setpieces",
+    "send <monitor/trainer command>, ex: send (change_mode play_on)",
+    "show <stamina|actions|ball|cards|viewdir|msgs|msglog|possession|shots|offside|setpieces|goalies|help>",
+    "history | clear | help | quit",
+    "Esc/`: close, Up/Down: history, PageUp/PageDown: scroll",
+    "monitor/trainer commands can also be entered directly",
+    "ex: change_mode drop_ball | move ball 0 0",
+];
+
+
+#[derive(Debug)]
+pub struct Console {
+    /// Whether the console is shown and inturn gets the key events.
+    pub show: bool,
+    /// The command being entered currently.
+    input: String,
+    /// The commands entered previously, oldest first.
+    history: Vec<String>,
+    /// Position in history, when user is moving thro it.
+    histpos: usize,
+    /// The commands and their results, oldest first.
+    out: Vec<String>,
+    /// How many lines back from the latest, the view is scrolled to.
+    scroll: usize,
+}
+
+impl Console {
+
+    pub fn new() -> Console {
+        Console {
+            show: false,
+            input: String::new(),
+            history: Vec::new(),
+            histpos: 0,
+            out: Vec::new(),
+            scroll: 0,
+        }
+    }
+
+    pub fn toggle(&mut self) {
+        self.show = !self.show;
+        self.input.clear();
+    }
+
+    /// Add a message to the console output.
+    pub fn add_output(&mut self, msg: &str) {
+        for line in msg.lines() {
+            self.out.push(line.to_string());
+        }
+        if self.out.len() > CONSOLE_OUT_MAX {
+            self.out.drain(0..self.out.len()-CONSOLE_OUT_MAX);
+        }
+        self.scroll = 0;
+    }
+
+    fn history_add(&mut self, scmd: &str) {
+        if self.history.last().map(|s| s.as_str()) != Some(scmd) {
+            self.history.push(scmd.to_string());
+        }
+        if self.history.len() > CONSOLE_HISTORY_MAX {
+            self.history.remove(0);
+        }
+        self.histpos = self.history.len();
+    }
+
+    /// Move back (-ve) or forward (+ve) thro the history.
+    fn history_move(&mut self, delta: isize) {
+        let histpos = (self.histpos as isize + delta).max(0).min(self.history.len() as isize);
+        self.histpos = histpos as usize;
+        if self.histpos < self.history.len() {
+            self.input = self.history[self.histpos].clone();
+        } else {
+            self.input.clear();
+        }
+    }
+
+    /// Run the command entered by the user.
+    /// Commands handled within the console return NeedMore.
+    fn run(&mut self, scmd: &str) -> ProgramEvent {
+        self.add_output(&format!("{}{}", CONSOLE_PROMPT, scmd));
+        if scmd.len() == 0 {
+            return ProgramEvent::NeedMore;
+        }
+        self.history_add(scmd);
+        match scmd {
+            "help" => {
+                for line in CONSOLE_HELP {
+                    self.add_output(line);
+                }
+                return ProgramEvent::NeedMore;
+            },
+            "history" => {
+                let hist = self.history.join("\n");
+                self.add_output(&hist);
+                return ProgramEvent::NeedMore;
+            },
+            "clear" => {
+                self.out.clear();
+                return ProgramEvent::NeedMore;
+            },
+            _ => (),
+        }
+        match parse(scmd) {
+            Ok(pev) => return pev,
+            Err(err) => {
+                self.add_output(&format!("ERRR:{}", err));
+                return ProgramEvent::NeedMore;
+            }
+        }
+    }
+
+    /// Handle the sdl events, when the console is shown.
+    pub fn handle_event(&mut self, ev: Event) -> ProgramEvent {
+        match ev {
+            Event::Quit { timestamp: _ } => return ProgramEvent::Quit,
+            Event::TextInput { timestamp: _, window_id: _, text } => {
+                // Skip the char wrt the key used to toggle the console
+                if text != "`" {
+                    self.input.push_str(&text);
+                }
+            },
+            Event::KeyDown { timestamp: _, window_id: _, keycode, scancode: _, keymod: _, repeat: _ } => {
+                match keycode {
+                    Some(Keycode::Return) | Some(Keycode::KpEnter) => {
+                        let scmd = self.input.trim().to_string();
+                        self.input.clear();
+                        return self.run(&scmd);
+                    },
+                    Some(Keycode::Escape) | Some(Keycode::Backquote) => self.toggle(),
+                    Some(Keycode::Backspace) => {
+                        self.input.pop();
+                    },
+                    Some(Keycode::Up) => self.history_move(-1),
+                    Some(Keycode::Down) => self.history_move(1),
+                    Some(Keycode::PageUp) => self.scroll_by(-4),
+                    Some(Keycode::PageDown) => self.scroll_by(4),
+                    _ => (),
+                }
+            },
+            _ => (),
+        }
+        return ProgramEvent::NeedMore;
+    }
+
+    /// Scroll the view back (-ve) or forward (+ve) wrt the latest output.
+    fn scroll_by(&mut self, delta: isize) {
+        let maxscroll = self.out.len().saturating_sub(CONSOLE_VIEWLINES) as isize;
+        let scroll = (self.scroll as isize - delta).max(0).min(maxscroll);
+        self.scroll = scroll as usize;
+    }
+
+    /// Draw the console, if enabled.
+    pub fn draw(&self, sx: &mut SdlX) {
+        if !self.show {
+            return;
+        }
+        let iend = self.out.len() - self.scroll;
+        let istart = iend.saturating_sub(CONSOLE_VIEWLINES);
+        let mut vlines = Vec::new();
+        vlines.push("Console (help for commands)");
+        for i in istart..iend {
+            vlines.push(self.out[i].as_str());
+        }
+        for _i in (iend-istart)..CONSOLE_VIEWLINES {
+            vlines.push("");
+        }
+        let sinput = format!("{}{}_", CONSOLE_PROMPT, self.input);
+        vlines.push(&sinput);
+        sx.n_msgbox(CONSOLE_RECT, vlines, Color::BLACK);
+    }
+
+}
+
+
+/// Parse a player id of the form <l|r><unum>, ex: l7
+pub fn parse_playerid(spid: &str) -> Result<(char, String), String> {
+    let side = spid.chars().next().unwrap_or(' ');
+    if (side != entities::SIDE_L) && (side != entities::SIDE_R) {
+        return Err(format!("select:Side should be l or r, not [{}]", spid));
+    }
+    let pid = &spid[1..];
+    if !entities::LPLAYERS.contains(&pid) {
+        return Err(format!("select:Unknown player [{}]", spid));
+    }
+    Ok((side, pid.to_string()))
+}
+
+/// Map the given console command to the corresponding program event.
+pub fn parse(scmd: &str) -> Result<ProgramEvent, String> {
+    let toks: Vec<&str> = scmd.split_whitespace().collect();
+    if toks.len() == 0 {
+        return Err("Empty command".to_string());
+    }
+    let arg = |i: usize| -> Result<&str, String> {
+        if i >= toks.len() {
+            return Err(format!("{}:Missing arg {}", toks[0], i));
+        }
+        Ok(toks[i])
+    };
+    match toks[0] {
+        "seek" => {
+            let sarg = arg(1)?;
+            if sarg.starts_with('+') || sarg.starts_with('-') {
+                let delta = sarg.trim_start_matches('+').parse::<isize>().map_err(|_| format!("seek:Bad delta [{}]", sarg))?;
+                return Ok(ProgramEvent::Seek(delta));
+            }
+            let time = sarg.parse::<usize>().map_err(|_| format!("seek:Bad time [{}]", sarg))?;
+            Ok(ProgramEvent::SeekTo(time))
+        },
+        "fps" => {
+            let sarg = arg(1)?;
+            let fps = sarg.parse::<f32>().map_err(|_| format!("fps:Bad fps [{}]", sarg))?;
+            if fps <= 0.0 {
+                return Err(format!("fps:Should be above 0, not [{}]", sarg));
+            }
+            Ok(ProgramEvent::SetFPS(fps))
+        },
+        "pause" => Ok(ProgramEvent::Pause),
+        "offsides" => Ok(ProgramEvent::ListOffsides),
+        "periods" => Ok(ProgramEvent::ListPeriods),
+        "setpieces" => Ok(ProgramEvent::ListSetPieces),
+        "seq" => {
+            let sarg = arg(1)?;
+            if sarg == "list" {
+                let cnt = match toks.get(2) {
+                    Some(scnt) => scnt.parse::<usize>().map_err(|_| format!("seq:Bad count [{}]", scnt))?,
+                    None => SEQ_LIST_DEFAULT,
+                };
+                return Ok(ProgramEvent::ListSequences(cnt));
+            }
+            Ok(ProgramEvent::HighlightSequence(actions::SeqSelect::parse(sarg).map_err(|err| format!("seq:{}", err))?))
+        },
+        "select" => {
+            let sarg = arg(1)?;
+            if sarg == "none" {
+                return Ok(ProgramEvent::SelectPlayer(None));
+            }
+            Ok(ProgramEvent::SelectPlayer(Some(parse_playerid(sarg)?)))
+        },
+        "export" => {
+            let sformat = arg(1)?;
+            let fname = arg(2)?;
+            if sformat != "json" {
+                return Err(format!("export:Unsupported format [{}]", sformat));
+            }
+            Ok(ProgramEvent::ExportJson(fname.to_string()))
+        },
+        "send" => {
+            let srccmd = scmd.trim_start()["send".len()..].trim();
+            RCCmd::parse(srccmd)?;
+            Ok(ProgramEvent::SendCommand(srccmd.to_string()))
+        },
+        "show" => {
+            match arg(1)? {
+                "stamina" => Ok(ProgramEvent::ToggleShowStamina),
+                "actions" => Ok(ProgramEvent::ToggleShowActions),
+                "ball" => Ok(ProgramEvent::ToggleShowBall),
+                "cards" => Ok(ProgramEvent::ToggleShowCards),
+                "viewdir" => Ok(ProgramEvent::ToggleShowPlayerViewDir),
+                "msgs" => Ok(ProgramEvent::ToggleShowMessages),
+                "msglog" => Ok(ProgramEvent::ToggleShowMessageLog),
+                "possession" => Ok(ProgramEvent::ToggleShowPossession),
+                "shots" => Ok(ProgramEvent::ToggleShowShots),
+                "offside" => Ok(ProgramEvent::ToggleShowOffside),
+                "setpieces" => Ok(ProgramEvent::ToggleShowSetPieces),
+                "goalies" => Ok(ProgramEvent::ToggleShowGoalies),
+                "help" => Ok(ProgramEvent::ToggleShowHelp),
+                sarg => Err(format!("show:Unknown [{}]", sarg)),
+            }
+        },
+        "quit" => Ok(ProgramEvent::Quit),
+        _ => {
+            if RCCmd::parse(scmd).is_ok() {
+                return Ok(ProgramEvent::SendCommand(scmd.to_string()));
+            }
+            Err(format!("Unknown command [{}], try help", toks[0]))
+        }
+    }
+}
@@ -28,6 +28,8 @@ pub struct GEntity<'a> {
     npos: (f32, f32),
     /// width, height in screen space dimensions
     width_height: (u32, u32),
+    /// width, height at creation, wrt which any scaling is applied
+    basewh: (u32, u32),
     nw: f32,
     nh: f32,
     /// Radius in screen space dimensions
@@ -40,8 +42,10 @@ pub struct GEntity<'a> {
     pub colorsel: u8,
     /// Should the entity be moved back into screen, if it goes out
     onscreen: bool,
-    /// A cache of the Id string, as a SDL surface
-    ids: Surface<'a>,
+    /// A cache of the Id string, as a SDL surface.
+    /// None if no ttf font is available, in which case the
+    /// id is drawn using the builtin pixel font.
+    ids: Option<Surface<'a>>,
     /// Any motion vector that should be used to move entity,
     /// when next frame is called.
     mov: (f32, f32),
@@ -82,12 +86,13 @@ pub struct GEntity<'a> {
 impl<'a> GEntity<'a> {
 
     /// Create a new instance of the Graphical Entity
-    pub fn new(id: &str, npos: (f32, f32), width_height: (u32, u32), color: Color, font: &'a Font) -> GEntity<'a> {
-        let ts = sdlx::text_surface(font, id, Color::WHITE);
+    pub fn new(id: &str, npos: (f32, f32), width_height: (u32, u32), color: Color, font: Option<&'a Font>) -> GEntity<'a> {
+        let ts = font.map(|font| sdlx::text_surface(font, id, Color::WHITE));
         GEntity {
             _id: id.to_string(),
             npos: npos,
             width_height,
+            basewh: width_height,
             radius: ((width_height.0 + width_height.1)/2) as i16,
             color: color,
             fcolor: -1.0,
@@ -118,6 +123,17 @@ impl<'a> GEntity<'a> {
         self.color = color;
     }
 
+    /// Scale the size of the entity wrt its size at creation.
+    /// The cached id image is stretched to the new size, when drawn.
+    pub fn scale(&mut self, scale: f32) {
+        let w = ((self.basewh.0 as f32 * scale).round() as u32).max(1);
+        let h = ((self.basewh.1 as f32 * scale).round() as u32).max(1);
+        self.width_height = (w, h);
+        self.radius = ((w + h)/2) as i16;
+        self.hw = (w/2) as i32;
+        self.hh = (h/2) as i32;
+    }
+
     /// Ensure that the gentity remains within the 0.0-1.0 normal space,
     /// by wrapping it around to the other end, if required.
     ///
@@ -141,9 +157,14 @@ impl<'a> GEntity<'a> {
     }
 
     /// Convert the gentity's position into screen space from normal space
-    pub fn ipos(&self) -> (i32, i32) {
-        let (prgw, prgh) = sdlx::get_prg_resolution();
-        ((self.npos.0 * prgw as f32).round() as i32, (self.npos.1 * prgh as f32).round() as i32)
+    pub fn ipos(&self, sx: &SdlX) -> (i32, i32) {
+        let (spx, spy) = sx.n2s.d2o(self.npos);
+        (spx.round() as i32, spy.round() as i32)
+    }
+
+    /// The gentity's position in normal 0.0-1.0 space
+    pub fn npos(&self) -> (f32, f32) {
+        self.npos
     }
 
     /// Set absolute position of the gentity in normal 0.0-1.0 space
@@ -177,8 +198,8 @@ impl<'a> GEntity<'a> {
     }
 
     fn update_base_graphicelements(&mut self, sx: &mut SdlX) {
-        self.nw = sx.n2s.o2dx(self.width_height.0 as f32);
-        self.nh = sx.n2s.o2dy(self.width_height.1 as f32);
+        self.nw = sx.n2s.o2d_dx(self.width_height.0 as f32);
+        self.nh = sx.n2s.o2d_dy(self.width_height.1 as f32);
         self.nhw = self.nw/2.0;
         self.nhh = self.nh/2.0;
         self.nhlw = self.nh*0.2;
@@ -255,20 +276,24 @@ impl<'a> GEntity<'a> {
         }
         sx.wc.set_draw_color(color);
         sx.wc.set_blend_mode(BlendMode::Blend);
-        let ipos = self.ipos();
+        let ipos = self.ipos(sx);
         if cfg!(feature="gentity_circle") {
             sx.wc.filled_circle(ipos.0 as i16, ipos.1 as i16, self.radius, self.color).unwrap();
         } else {
             sx.ns_fill_rect_mid(self.npos.0, self.npos.1, self.width_height.0, self.width_height.1);
         }
-        let tx = self.ids.as_texture(&sx.wctc).unwrap();
-        sx.wc.copy(&tx, None, Some(Rect::new(ipos.0-self.hw, ipos.1-self.hh, self.width_height.0, self.width_height.1))).unwrap();
+        if let Some(ids) = &self.ids {
+            let tx = ids.as_texture(&sx.wctc).unwrap();
+            sx.wc.copy(&tx, None, Some(Rect::new(ipos.0-self.hw, ipos.1-self.hh, self.width_height.0, self.width_height.1))).unwrap();
+        } else {
+            let hsw = (self._id.len() * 4) as i32;
+            sx.wc.string((ipos.0-hsw) as i16, (ipos.1-4) as i16, &self._id, Color::WHITE).unwrap();
+        }
         if self.arc_nradius > 0.0 {
             let rad = (self.radius as f32 * self.arc_nradius).round() as i16;
             let edeg = (self.arc_nangle * 359.0).round() as i16;
             sx.ns_arc(self.npos.0, self.npos.1, rad, 0, edeg, 4, self.arc_color);
         }
-        drop(tx);
         self.draw_outerlines(sx);
         self.draw_gextras(sx);
     }
@@ -372,6 +397,9 @@ pub enum GEDrawPrimitive {
     #[allow(dead_code)]
     /// RemainingFramesCnt, Line type (Top,Bottom,Left,Right), RelativePositionWrtCorrespondingBoundry, Color
     NLine{ remfc: isize, linetype: GELineType, radratio: f32, color: Color },
+    /// RemainingFramesCnt, Message, Color
+    /// The message is shown like a speech bubble above the gentity.
+    NMsg{ remfc: isize, msg: String, color: Color },
 }
 
 impl GEDrawPrimitive {
@@ -395,6 +423,14 @@ impl GEDrawPrimitive {
                     bremove = true;
                 }
             },
+            GEDrawPrimitive::NMsg{ remfc, msg: _, color: _ } => {
+                if *remfc > 0 {
+                    *remfc -= 1;
+                    if *remfc == 0 {
+                        bremove = true;
+                    }
+                }
+            },
         }
         return bremove;
     }
@@ -426,6 +462,10 @@ impl<'a> GEntity<'a> {
                 GEDrawPrimitive::NLine { remfc: _, linetype, radratio, color } => {
                     self.draw_geline(sx, linetype.clone(), *radratio, *color);
                 },
+                GEDrawPrimitive::NMsg { remfc: _, msg, color } => {
+                    let (nx,ny) = self.npos;
+                    sx.n_msg_mid(nx, ny - self.nh*1.5, msg, *color);
+                },
             }
             let ge = &mut self.gextras[i];
             if ge.life_decrement_need_removal() {
@@ -7,7 +7,9 @@ use sdl2::pixels::Color;
 use sdl2::ttf::Font;
 
 use crate::sdlx::{SdlX, XRect};
-use crate::playdata::{PlayUpdate, GameState};
+use crate::playdata::PlayUpdate;
+use crate::playdata::rcss::PlayMode;
+use crate::playdata::rcss::FieldGeom;
 use crate::proc::actions::{ActionsInfo, ActionData, AIAction};
 
 pub const SIDE_L: char = 'l';
@@ -27,18 +29,21 @@ pub const SCREEN_COLOR_BG: Color = Color::RGB(20, 200, 20);
 
 pub const FRAMES_PER_SEC: usize = 24;
 
-pub const PITCH_RECT: XRect = ((0.03,0.04), (0.97,0.96));
+/// The rcss pitch (105x68) within the rcss space (110x74) mapped to the screen
+pub const PITCH_RECT: XRect = ((0.0227,0.0405), (0.9773,0.9595));
 
 pub const MSG_SCORE_POS: (f32,f32)      = (0.01,0.01);
 pub const MSG_STIME_POS: (f32,f32)      = (0.50,0.01);
 pub const MSG_FPS_POS: (f32,f32)        = (0.80,0.01);
 pub const MSG_GAME_POS: (f32,f32)       = (0.01,0.98);
 pub const MSG_UNKNOWN_POS: (f32,f32)    = (0.50,0.98);
+pub const MSG_STATUS_POS: (f32,f32)     = (0.80,0.98);
 pub const MSG_TIMED_POS: (f32, f32)     = (0.01,0.08);
 pub const MSG_LBALLPOSS_POS: (f32, f32) = (0.44,0.01);
 pub const MSG_RBALLPOSS_POS: (f32, f32) = (0.55,0.01);
 
 const MSG_TIMED_NUMFRAMES: isize = 40;
+const MSGLOG_VIEWLINES: usize = 16;
 
 pub const LPLAYERS: [&str; 11] = [ "1", "2", "3", "4", "5", "6", "7", "8", "9", "10", "11" ];
 pub const RPLAYERS: [&str; 11] = [ "1", "2", "3", "4", "5", "6", "7", "8", "9", "10", "11" ];
@@ -59,10 +64,19 @@ pub mod team;
 pub mod objects;
 use objects::Ball;
 use objects::FixedPosMessage;
+use objects::MessageLog;
 pub mod simobjs;
 use simobjs::VirtBall;
 
 
+/// What the view (camera) follows, if any
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Follow {
+    None,
+    Ball,
+    SelectedPlayer,
+}
+
 #[derive(Debug)]
 /// Manage the entities in the playground.
 pub(crate) struct PGEntities<'a> {
@@ -80,6 +94,8 @@ pub(crate) struct PGEntities<'a> {
     rteam: team::Team<'a>,
     /// The pitch boundry within the screen, in normalised 0.0-1.0 space.
     pitch: XRect,
+    /// The field geometry, used to draw the pitch markings to scale.
+    pub field: FieldGeom,
     /// If extra pitch markers should be shown or not.
     pub showxtrapitchmarkers: bool,
     /// Info from Data
@@ -91,6 +107,12 @@ pub(crate) struct PGEntities<'a> {
     virtballd: Option<VirtBall>,
     /// Timed Msg
     pub timedmsg: FixedPosMessage,
+    /// Log of communication messages, including player says.
+    pub msglog: MessageLog,
+    /// What the view should follow, if any
+    pub follow: Follow,
+    /// Whether to show the offside lines or not
+    pub showoffside: bool,
 }
 
 impl<'a> PGEntities<'a> {
@@ -106,7 +128,8 @@ impl<'a> PGEntities<'a> {
     /// * score: Give the current score, if any.
     /// * stime: Provide any time related info wrt the game.
     /// * game: show any game related messages.
-    pub fn new(pitch: XRect, lplayers: &Vec<&str>, rplayers: &Vec<&str>, fps: f32, font: &'a Font) -> PGEntities<'a> {
+    /// * status: show the status of the playdata source, like connection status.
+    pub fn new(pitch: XRect, lplayers: &Vec<&str>, rplayers: &Vec<&str>, fps: f32, font: Option<&'a Font>) -> PGEntities<'a> {
         let mut vfpmsgs = Vec::new();
         let scoremsg = FixedPosMessage::new("score", MSG_SCORE_POS, false, -1);
         vfpmsgs.push(scoremsg);
@@ -117,6 +140,8 @@ impl<'a> PGEntities<'a> {
         vfpmsgs.push(gamemsg);
         let unknownmsg = FixedPosMessage::new("unknown", MSG_UNKNOWN_POS, false, -1);
         vfpmsgs.push(unknownmsg);
+        let statusmsg = FixedPosMessage::new("status", MSG_STATUS_POS, false, -1);
+        vfpmsgs.push(statusmsg);
         let mut timedmsg = FixedPosMessage::new("timedmsg", MSG_TIMED_POS, true, MSG_TIMED_NUMFRAMES);
         timedmsg.update_direct("");
         PGEntities {
@@ -129,9 +154,13 @@ impl<'a> PGEntities<'a> {
             lteam: team::Team::new(SIDE_L_TEAM, Color::RED, lplayers, font),
             rteam: team::Team::new(SIDE_R_TEAM, Color::BLUE, rplayers, font),
             pitch: pitch,
+            field: FieldGeom::new(),
             showxtrapitchmarkers: true,
             actionsinfo: ActionsInfo::new(lplayers, rplayers),
             timedmsg: timedmsg,
+            msglog: MessageLog::new(MSGLOG_VIEWLINES),
+            follow: Follow::None,
+            showoffside: false,
         }
     }
 
@@ -156,22 +185,32 @@ impl<'a> PGEntities<'a> {
     ///   * inframes - specifies as to in how many frames the object should
     ///     be moved to the new location being specified.
     ///
-    /// Handle Game State info (Currently Goal)
+    /// Handle Game State info (Currently Goal), and track ball possession
     ///
     pub fn update(&mut self, pu: PlayUpdate, babsolute: bool, inframes: f32) {
+        if self.field.update_from_params(&pu.params) {
+            eprintln!("INFO:PGND:PGEntities:FieldGeom:{:?}", self.field);
+            self.actionsinfo.shots.field = self.field.clone();
+            self.actionsinfo.goalies.field = self.field.clone();
+        }
+        self.actionsinfo.possession.update_from_params(&pu.params);
         for fpmsg in &mut self.vfpmsgs {
             fpmsg.update(&pu.msgs);
         }
+        for (from, msg) in &pu.commsgs {
+            self.msglog.add(format!("{}:{}:{}", pu.timecounter, from, msg));
+        }
         if self.virtballd.is_some() {
             let virtball = self.virtballd.as_mut().unwrap();
             let bpos = virtball.next_record(pu.timecounter);
             self.virtballg.update(bpos, babsolute, inframes);
         }
         self.ball.update(pu.ball, babsolute, inframes);
-        self.lteam.update(pu.timecounter, pu.lteamcoded, babsolute, inframes, &mut self.actionsinfo);
-        self.rteam.update(pu.timecounter, pu.rteamcoded, babsolute, inframes, &mut self.actionsinfo);
+        self.lteam.update(pu.timecounter, pu.lteamcoded, babsolute, inframes, &mut self.actionsinfo, &mut self.msglog);
+        self.rteam.update(pu.timecounter, pu.rteamcoded, babsolute, inframes, &mut self.actionsinfo, &mut self.msglog);
+        self.actionsinfo.handle_cycle(pu.timecounter, &pu.state, pu.ball);
         match pu.state {
-            GameState::Goal(side)=> {
+            Some(PlayMode::Goal(side)) => {
                 self.actionsinfo.handle_action(ActionData::new(pu.timecounter, side, XPLAYERID_UNKNOWN.to_string(), pu.ball, AIAction::Goal))
             },
             _ => {
@@ -191,7 +230,62 @@ impl<'a> PGEntities<'a> {
         self.rteam.next_frame();
     }
 
+    /// Map a position in the field (meters, centered at the center spot)
+    /// to the normalised screen space, based on the pitch rect.
+    fn field2n(&self, fx: f32, fy: f32) -> (f32, f32) {
+        let ((nx1,ny1),(nx2,ny2)) = self.pitch;
+        let nx = nx1 + (fx/self.field.pitch_length + 0.5)*(nx2-nx1);
+        let ny = ny1 + (fy/self.field.pitch_width + 0.5)*(ny2-ny1);
+        (nx, ny)
+    }
+
+    /// Draw a line between the given field positions
+    fn field_line(&self, sx: &mut SdlX, f1: (f32, f32), f2: (f32, f32), color: Color) {
+        let (nx1, ny1) = self.field2n(f1.0, f1.1);
+        let (nx2, ny2) = self.field2n(f2.0, f2.1);
+        sx.nn_line(nx1, ny1, nx2, ny2, color);
+    }
+
+    /// Draw the offside line wrt each team, ie at its second-last defender,
+    /// in the color of the team.
+    fn draw_offside_lines(&self, sx: &mut SdlX) {
+        let hw = self.field.pitch_width/2.0;
+        let (lline, rline) = self.actionsinfo.offside_lines();
+        for (line, color) in [(lline, self.lteam.color()), (rline, self.rteam.color())] {
+            if let Some(x) = line {
+                self.field_line(sx, (x, -hw), (x, hw), color);
+            }
+        }
+    }
+
+    /// Draw a rect given its diagonally opposite corners, in the field
+    fn field_rect(&self, sx: &mut SdlX, f1: (f32, f32), f2: (f32, f32), color: Color) {
+        self.field_line(sx, (f1.0, f1.1), (f2.0, f1.1), color);
+        self.field_line(sx, (f2.0, f1.1), (f2.0, f2.1), color);
+        self.field_line(sx, (f2.0, f2.1), (f1.0, f2.1), color);
+        self.field_line(sx, (f1.0, f2.1), (f1.0, f1.1), color);
+    }
+
+    /// Draw a arc in the field, as a series of lines, so that it scales
+    /// the same way as the rest of the field. Angles are in degrees,
+    /// measured clockwise (wrt the screen) from the +ve x axis.
+    fn field_arc(&self, sx: &mut SdlX, center: (f32, f32), radius: f32, angles: (f32, f32), color: Color) {
+        let steps = ((angles.1 - angles.0).abs()/6.0).ceil().max(1.0) as usize;
+        let point = |i: usize| {
+            let angle = (angles.0 + (angles.1 - angles.0)*(i as f32)/(steps as f32)).to_radians();
+            (center.0 + radius*angle.cos(), center.1 + radius*angle.sin())
+        };
+        for i in 0..steps {
+            self.field_line(sx, point(i), point(i+1), color);
+        }
+    }
+
     /// Draw the pitch on the screen, along with the boundries and any markers.
+    /// The markings are drawn to scale, based on the field geometry
+    /// * the touch lines, goal lines and the halfway line
+    /// * the centre circle and spot
+    /// * the goal areas, penalty areas, penalty spots and penalty arcs
+    /// * the corner arcs and the goals
     fn draw_pitch(&self, sx: &mut SdlX) {
         //let inbtwcolor = Color::RGB(230, 230, 230);
         let inbtwcolor = Color::WHITE;
@@ -200,11 +294,32 @@ impl<'a> PGEntities<'a> {
         sx.nn_thick_line(nx1, ny1, nx1, ny2, 0.004, Color::WHITE);
         sx.nn_thick_line(nx1, ny2, nx2, ny2, 0.002, Color::WHITE);
         sx.nn_thick_line(nx2, ny1, nx2, ny2, 0.004, Color::WHITE);
-        sx.nn_line(0.50, 0.02, 0.50, 0.98, inbtwcolor); // Center vertical
-        let leftmidx = nx1-0.01;
-        sx.nn_line(leftmidx, 0.40, leftmidx, 0.60, inbtwcolor); // Left mid
-        let rightmidx = nx2+0.01;
-        sx.nn_line(rightmidx, 0.40, rightmidx, 0.60, inbtwcolor); // Right mid
+        let fd = &self.field;
+        let hl = fd.pitch_length/2.0;
+        let hw = fd.pitch_width/2.0;
+        // Halfway line, centre circle and spot
+        self.field_line(sx, (0.0, -hw), (0.0, hw), inbtwcolor);
+        self.field_arc(sx, (0.0, 0.0), fd.center_circle_r, (0.0, 360.0), inbtwcolor);
+        self.field_arc(sx, (0.0, 0.0), 0.3, (0.0, 360.0), inbtwcolor);
+        // Penalty arc, the part of the circle around the penalty spot, outside the penalty area
+        let parcangle = ((fd.penalty_area_length - fd.penalty_spot_dist)/fd.center_circle_r).min(1.0).acos().to_degrees();
+        for (gx, dir) in [(-hl, 1.0), (hl, -1.0)] {
+            // Goal area and penalty area
+            self.field_rect(sx, (gx, -fd.goal_area_width/2.0), (gx + dir*fd.goal_area_length, fd.goal_area_width/2.0), inbtwcolor);
+            self.field_rect(sx, (gx, -fd.penalty_area_width/2.0), (gx + dir*fd.penalty_area_length, fd.penalty_area_width/2.0), inbtwcolor);
+            // Penalty spot and arc
+            let spot = (gx + dir*fd.penalty_spot_dist, 0.0);
+            self.field_arc(sx, spot, 0.3, (0.0, 360.0), inbtwcolor);
+            let midangle = if dir > 0.0 { 0.0 } else { 180.0 };
+            self.field_arc(sx, spot, fd.center_circle_r, (midangle - parcangle, midangle + parcangle), inbtwcolor);
+            // Goal, behind the goal line
+            self.field_rect(sx, (gx, -fd.goal_width/2.0), (gx - dir*fd.goal_depth, fd.goal_width/2.0), inbtwcolor);
+        }
+        // Corner arcs
+        self.field_arc(sx, (-hl, -hw), fd.corner_arc_r, (0.0, 90.0), inbtwcolor);
+        self.field_arc(sx, (hl, -hw), fd.corner_arc_r, (90.0, 180.0), inbtwcolor);
+        self.field_arc(sx, (hl, hw), fd.corner_arc_r, (180.0, 270.0), inbtwcolor);
+        self.field_arc(sx, (-hl, hw), fd.corner_arc_r, (270.0, 360.0), inbtwcolor);
         // Additional markers
         if self.showxtrapitchmarkers {
             sx.nn_line(0.25, 0.48, 0.25, 0.52, inbtwcolor); // Horizontal left quarter
@@ -216,11 +331,11 @@ impl<'a> PGEntities<'a> {
     }
 
     /// Draw all the objects in the playground.
+    /// The pitch and the objects in it are drawn as seen through the view,
+    /// while the messages remain fixed on the screen.
     pub fn draw(&mut self, sx: &mut SdlX) {
+        sx.view_apply(true);
         self.draw_pitch(sx);
-        for fpmsg in &mut self.vfpmsgs {
-            fpmsg.draw(sx);
-        }
         self.lteam.draw(sx);
         self.rteam.draw(sx);
         if self.showball {
@@ -229,7 +344,16 @@ impl<'a> PGEntities<'a> {
         if self.virtballd.is_some() {
             self.virtballg.draw(sx);
         }
+        if self.showoffside {
+            self.draw_offside_lines(sx);
+        }
+        self.actionsinfo.draw_sequence(sx);
+        sx.view_apply(false);
+        for fpmsg in &mut self.vfpmsgs {
+            fpmsg.draw(sx);
+        }
         self.timedmsg.draw(sx);
+        self.msglog.draw(sx);
     }
 
 }
@@ -245,6 +369,27 @@ impl<'a> PGEntities<'a> {
         self.rteam.adjust_players(0x0e); //3
     }
 
+    /// Scale the players and balls wrt their size at creation,
+    /// so that they remain in proportion to the pitch, when resized.
+    pub fn scale_members(&mut self, scale: f32) {
+        self.lteam.scale_players(scale);
+        self.rteam.scale_players(scale);
+        self.ball.scale(scale);
+        self.virtballg.scale(scale);
+    }
+
+    /// Set the colors used for the left and right teams
+    pub fn set_team_colors(&mut self, lcolor: Color, rcolor: Color) {
+        self.lteam.set_color(lcolor);
+        self.rteam.set_color(rcolor);
+    }
+
+    /// Set the initial show state wrt the different player related info
+    pub fn set_bshows(&mut self, bstamina: bool, bactions: bool, bcards: bool, bviewdir: bool, bmsgs: bool) {
+        self.lteam.set_bshows(bstamina, bactions, bcards, bviewdir, bmsgs);
+        self.rteam.set_bshows(bstamina, bactions, bcards, bviewdir, bmsgs);
+    }
+
     pub fn toggle_bshowstamina(&mut self) {
         let lshow = self.lteam.toggle_bshowstamina();
         let rshow = self.rteam.toggle_bshowstamina();
@@ -285,6 +430,66 @@ impl<'a> PGEntities<'a> {
         }
     }
 
+    pub fn toggle_bshowmsgs(&mut self) {
+        let lshow = self.lteam.toggle_bshowmsgs();
+        let rshow = self.rteam.toggle_bshowmsgs();
+        if lshow && rshow {
+            self.timedmsg.update_direct("Messages:Show");
+        } else {
+            self.timedmsg.update_direct("Messages:Hide");
+        }
+    }
+
+    pub fn toggle_bshowoffside(&mut self) {
+        self.showoffside = !self.showoffside;
+        if self.showoffside {
+            self.timedmsg.update_direct("OffsideLines:Show");
+        } else {
+            self.timedmsg.update_direct("OffsideLines:Hide");
+        }
+    }
+
+    pub fn toggle_bshowmsglog(&mut self) {
+        self.msglog.show = !self.msglog.show;
+        if self.msglog.show {
+            self.timedmsg.update_direct("MessageLog:Show");
+        } else {
+            self.timedmsg.update_direct("MessageLog:Hide");
+        }
+    }
+
+    /// Select (highlight) the given player (side, playerid), None to clear
+    pub fn select_player(&mut self, player: &Option<(char, String)>) {
+        self.lteam.select(None);
+        self.rteam.select(None);
+        if let Some((side, pid)) = player {
+            if *side == SIDE_L {
+                self.lteam.select(Some(pid));
+            } else {
+                self.rteam.select(Some(pid));
+            }
+        }
+    }
+
+    /// Cycle thro what the view follows, ie none -> ball -> selected player
+    pub fn cycle_follow(&mut self) {
+        self.follow = match self.follow {
+            Follow::None => Follow::Ball,
+            Follow::Ball => Follow::SelectedPlayer,
+            Follow::SelectedPlayer => Follow::None,
+        };
+        self.timedmsg.update_direct(&format!("Follow:{:?}", self.follow));
+    }
+
+    /// The position (in normal space) the view should be centered on, if following something
+    pub fn follow_npos(&self) -> Option<(f32, f32)> {
+        match self.follow {
+            Follow::None => None,
+            Follow::Ball => Some(self.ball.npos()),
+            Follow::SelectedPlayer => self.lteam.selected_npos().or(self.rteam.selected_npos()),
+        }
+    }
+
     pub fn seek(&mut self, seekdelta: isize) {
         if self.virtballd.is_some() {
             self.virtballd.as_mut().unwrap().seek(seekdelta);
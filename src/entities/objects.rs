@@ -13,6 +13,8 @@ use crate::entities::gentity::GEntity;
 pub const BALL_SIZE: u32 = 6;
 pub const BALL_COLOR: Color = Color::WHITE;
 pub const MSG_COLOR: Color = Color::RED;
+const MSGLOG_RECT: (f32, f32, f32, f32) = (0.50, 0.10, 0.48, 0.50);
+const MSGLOG_LINE_MAXCHARS: usize = 56;
 
 
 #[derive(Debug)]
@@ -29,7 +31,7 @@ impl<'a> Ball<'a> {
 
     /// Create a new instance of the ball.
     /// NOTE: The position is set using update call.
-    pub fn new(font: &'a Font) -> Ball<'a> {
+    pub fn new(font: Option<&'a Font>) -> Ball<'a> {
         Ball {
             bge: GEntity::new(" ", (0.0,0.0), (BALL_SIZE, BALL_SIZE), BALL_COLOR, font)
         }
@@ -39,6 +41,10 @@ impl<'a> Ball<'a> {
         self.bge.set_color(color);
     }
 
+    pub fn scale(&mut self, scale: f32) {
+        self.bge.scale(scale);
+    }
+
     /// Update the position of the ball on the screen.
     ///
     /// babsolute
@@ -61,6 +67,11 @@ impl<'a> Ball<'a> {
         self.bge.next_frame();
     }
 
+    /// The current position of the ball on the screen, in normal space
+    pub fn npos(&self) -> (f32, f32) {
+        self.bge.npos()
+    }
+
     pub fn draw(&mut self, sx: &mut SdlX) {
         self.bge.draw(sx);
     }
@@ -159,3 +170,63 @@ impl FixedPosMessage {
     }
 
 }
+
+
+#[derive(Debug)]
+/// Maintain a log of messages, which can be shown as a scrollable panel.
+pub struct MessageLog {
+    /// The messages, oldest first
+    msgs: Vec<String>,
+    /// How many messages back from the latest, the view is scrolled to.
+    scroll: usize,
+    /// The number of messages shown at a time.
+    viewlines: usize,
+    /// Whether the panel should be shown or not.
+    pub show: bool,
+}
+
+impl MessageLog {
+
+    pub fn new(viewlines: usize) -> MessageLog {
+        MessageLog {
+            msgs: Vec::new(),
+            scroll: 0,
+            viewlines: viewlines,
+            show: false,
+        }
+    }
+
+    pub fn add(&mut self, msg: String) {
+        self.msgs.push(msg);
+        // Retain the view on the same messages, if user has scrolled back
+        if self.scroll > 0 {
+            self.scroll += 1;
+        }
+    }
+
+    /// Scroll the view back (-ve) or forward (+ve) wrt the latest message.
+    pub fn scroll(&mut self, delta: isize) {
+        let maxscroll = self.msgs.len().saturating_sub(self.viewlines) as isize;
+        let scroll = (self.scroll as isize - delta).max(0).min(maxscroll);
+        self.scroll = scroll as usize;
+    }
+
+    /// Draw the panel with the messages in the current view, if enabled.
+    pub fn draw(&mut self, sx: &mut SdlX) {
+        if !self.show {
+            return;
+        }
+        let iend = self.msgs.len() - self.scroll;
+        let istart = iend.saturating_sub(self.viewlines);
+        let shead = format!("Messages [{}-{}/{}]", istart, iend, self.msgs.len());
+        let mut vmsgs = Vec::new();
+        vmsgs.push(shead.as_str());
+        for i in istart..iend {
+            let msg = &self.msgs[i];
+            let ilen = msg.char_indices().nth(MSGLOG_LINE_MAXCHARS).map_or(msg.len(), |(i, _)| i);
+            vmsgs.push(&msg[..ilen]);
+        }
+        sx.n_msgbox(MSGLOG_RECT, vmsgs, Color::BLACK);
+    }
+
+}
@@ -12,11 +12,15 @@ use loggerk::{ldebug, log_d};
 
 use crate::entities::{ENTITY_WIDTH, ENTITY_HEIGHT};
 use crate::entities::gentity::{GEntity, GEDrawPrimitive};
+use crate::entities::objects::MessageLog;
 use crate::proc::actions::{ActionsInfo, ActionData, AIAction};
 use crate::sdlx::{SdlX, self, COLOR_INVISIBLE};
 use crate::playdata::{PlayerCodedData, self, rcss};
 
 
+/// How many frames, the speech bubble wrt a player say message, is shown.
+const SAY_BUBBLE_NUMFRAMES: isize = 30;
+
 
 #[derive(Debug)]
 pub struct Team<'a> {
@@ -29,11 +33,14 @@ pub struct Team<'a> {
     bshowcards: bool,
     bshowotheractions: bool,
     bshowplayerviewdir: bool,
+    bshowmsgs: bool,
+    /// The player selected (highlighted) by the user, if any
+    selected: Option<String>,
 }
 
 impl<'a> Team<'a> {
 
-    pub fn new(name: &str, color: Color, vplayerids: &Vec<&str>, font: &'a Font) -> Team<'a> {
+    pub fn new(name: &str, color: Color, vplayerids: &Vec<&str>, font: Option<&'a Font>) -> Team<'a> {
         let mut team = Team {
             name: name.to_string(),
             color: color,
@@ -44,6 +51,8 @@ impl<'a> Team<'a> {
             bshowcards: true,
             bshowotheractions: false,
             bshowplayerviewdir: false,
+            bshowmsgs: true,
+            selected: None,
         };
         let (prgw, prgh) = sdlx::get_prg_resolution();
         let bx = (rand::random::<u32>() % prgw) as f32;
@@ -58,7 +67,7 @@ impl<'a> Team<'a> {
         team
     }
 
-    pub fn update(&mut self, timecounter: usize, playersdata: Vec<PlayerCodedData>, babsolute: bool, inframes: f32, actionsinfo: &mut ActionsInfo) {
+    pub fn update(&mut self, timecounter: usize, playersdata: Vec<PlayerCodedData>, babsolute: bool, inframes: f32, actionsinfo: &mut ActionsInfo, msglog: &mut MessageLog) {
         let side = self.name.chars().nth(0).unwrap();
         for playerdata in playersdata {
             ldebug!(&format!("DBUG:PPGND:Team:{}:{:?}", self.name, playerdata));
@@ -66,6 +75,11 @@ impl<'a> Team<'a> {
             let mut px = 0.0;
             let mut py = 0.0;
             let mut pact = AIAction::None;
+            let mut pbody = 0.0;
+            let mut pcmds = Vec::new();
+            let mut bgoalie = false;
+            let mut bcatchfault = false;
+            let mut bkickfault = false;
             let player = self.players.get_mut(&pid).unwrap();
             for pd in playerdata.1 {
                 match pd {
@@ -80,6 +94,7 @@ impl<'a> Team<'a> {
                         }
                     },
                     playdata::PlayerData::Dir(body, neck, viewanglewidth) => {
+                        pbody = body;
                         if !self.bshowplayerviewdir {
                             continue;
                         }
@@ -142,6 +157,7 @@ impl<'a> Team<'a> {
                                     pact = AIAction::Kick;
                                     Color::BLUE
                                 } else {
+                                    bkickfault = true;
                                     Color::GRAY
                                 }
                             },
@@ -150,6 +166,7 @@ impl<'a> Team<'a> {
                                     pact = AIAction::Catch;
                                     Color::WHITE
                                 } else {
+                                    bcatchfault = true;
                                     Color::GRAY
                                 }
                             },
@@ -180,10 +197,34 @@ impl<'a> Team<'a> {
                             action_color = COLOR_INVISIBLE;
                         }
                         player.set_nxarc(1.0, 0.98, action_color);
-                    }
+                    },
+                    playdata::PlayerData::Cmd(cmd) => {
+                        if let playdata::PlayerCmd::Kick(power, dir) = &cmd {
+                            if self.bshowactions {
+                                // Intended kick direction, further away the more the power
+                                let kdir = (pbody + dir).round() as i16;
+                                let radratio = 1.2 + (power.abs()/100.0)*0.8;
+                                player.gextras_add(GEDrawPrimitive::NSArc{ remfc: 10, radratio, arcangles: (kdir-8, kdir+8), width: 2, color: Color::MAGENTA});
+                            }
+                        }
+                        if let playdata::PlayerCmd::Say(msg) = &cmd {
+                            msglog.add(format!("{}:{}{:02}:{}", timecounter, side.to_uppercase(), pid, msg));
+                            if self.bshowmsgs {
+                                player.gextras_add(GEDrawPrimitive::NMsg{ remfc: SAY_BUBBLE_NUMFRAMES, msg: msg.clone(), color: Color::BLACK });
+                            }
+                        }
+                        pcmds.push(cmd);
+                    },
+                    playdata::PlayerData::Goalie => bgoalie = true,
                 }
             }
-            actionsinfo.handle_action(ActionData::new(timecounter, side, pid, (px,py), pact));
+            if bgoalie {
+                actionsinfo.goalies.handle_goalie_flag(side, &pid);
+            }
+            if bcatchfault {
+                actionsinfo.goalies.handle_catch_fault(timecounter, side, &pid);
+            }
+            actionsinfo.handle_action(ActionData::new(timecounter, side, pid, (px,py), pact).with_cmds(pcmds).with_kickfault(bkickfault));
         }
     }
 
@@ -194,6 +235,11 @@ impl<'a> Team<'a> {
     }
 
     pub fn draw(&mut self, sx: &mut SdlX) {
+        if let Some(pid) = &self.selected {
+            if let Some(player) = self.players.get_mut(pid) {
+                player.gextras_add(GEDrawPrimitive::NSArc{ remfc: 1, radratio: 1.8, arcangles: (0, 359), width: 2, color: Color::YELLOW });
+            }
+        }
         for player in &mut self.players {
             player.1.draw(sx);
         }
@@ -209,6 +255,32 @@ impl<'a> Team<'a> {
         }
     }
 
+    /// Scale the players wrt their size at creation
+    pub fn scale_players(&mut self, scale: f32) {
+        for player in &mut self.players {
+            player.1.scale(scale);
+        }
+    }
+
+    pub fn color(&self) -> Color {
+        return self.color;
+    }
+
+    pub fn set_color(&mut self, color: Color) {
+        self.color = color;
+        for player in &mut self.players {
+            player.1.set_color(color);
+        }
+    }
+
+    pub fn set_bshows(&mut self, bstamina: bool, bactions: bool, bcards: bool, bviewdir: bool, bmsgs: bool) {
+        self.bshowstamina = bstamina;
+        self.bshowactions = bactions;
+        self.bshowcards = bcards;
+        self.bshowplayerviewdir = bviewdir;
+        self.bshowmsgs = bmsgs;
+    }
+
     pub fn toggle_bshowstamina(&mut self) -> bool {
         self.bshowstamina = !self.bshowstamina;
         self.bshowstamina
@@ -229,4 +301,20 @@ impl<'a> Team<'a> {
         self.bshowplayerviewdir
     }
 
+    /// Select (highlight) the given player, None to clear
+    pub fn select(&mut self, playerid: Option<&str>) {
+        self.selected = playerid.map(|pid| pid.to_string());
+    }
+
+    /// The current position of the selected player if any, in normal space
+    pub fn selected_npos(&self) -> Option<(f32, f32)> {
+        let pid = self.selected.as_ref()?;
+        self.players.get(pid).map(|player| player.npos())
+    }
+
+    pub fn toggle_bshowmsgs(&mut self) -> bool {
+        self.bshowmsgs = !self.bshowmsgs;
+        self.bshowmsgs
+    }
+
 }
@@ -2,13 +2,103 @@
 //! Key shortcuts
 //! HanishKVC, 2022
 //!
+//! The key sequences are mapped to the program events using key bindings.
+//! A default set of bindings is built in, which can be overridden/extended
+//! using a bindings file, which contains lines of the form
+//!
+//!   <key sequence> = <program event>[(arg)] [# description]
+//!
+//! * key sequence: one or more keys seperated by space, ex: p, s a, d A
+//!   * a lower case letter refers to the key, upper case to shift+key
+//!   * other keys use their sdl names, ex: Left, PageUp, Return, `
+//! * program event: ex: Pause, AdjustFPS(1.2), SendCommand(change_mode play_on)
+//!   * None removes any existing binding wrt the key sequence.
+//! * description: shown in the help, if not given a default one is used.
+//!
+//! A multi key sequence acts like a mode, ie after it is matched, one can
+//! press its last key or the last key of a sibling sequence directly, till
+//! a key which doesnt match is pressed.
+//!
 
 use sdl2::keyboard::{Keycode, Mod};
 
 use loggerk::{ldebug, log_d};
 
-use crate::{sdlx::SdlX, proc::actions};
+use crate::sdlx::{self, SdlX};
+use crate::proc::actions;
+use crate::playdata::rcss;
+use crate::console::{self, Console};
+
+
+const MTAG: &str = "GPPGND:Keys";
 
+/// The zoom ratio wrt each step of the mouse wheel
+const MOUSEWHEEL_ZOOM: f32 = 1.1;
+
+/// The built in key bindings
+const DEFAULT_BINDINGS: &str = "
+p = Pause
+h = ToggleShowHelp
+b = BackgroundColorChange
+Left = SeekBackward
+Right = SeekForward
+f = AdjustFPS(0.8)
+F = AdjustFPS(1.2)
+PageUp = ScrollMessageLog(-4)
+PageDown = ScrollMessageLog(4)
+Return = ToggleConsole
+F11 = ToggleFullscreen
+` = ToggleConsole
+s s = ToggleShowStamina
+s a = ToggleShowActions
+s b = ToggleShowBall
+s c = ToggleShowCards
+s d = ToggleShowPlayerViewDir
+s m = ToggleShowMessages
+s h = ToggleShowHelp
+c 0 = SendRecordCoded(0)
+c 1 = SendRecordCoded(1)
+c 2 = SendRecordCoded(2) # pause the server
+c 3 = SendRecordCoded(3) # resume the server
+c 4 = SendRecordCoded(4)
+c 5 = SendRecordCoded(5)
+c 6 = SendRecordCoded(6)
+c b = MoveBallToMouse
+c f = DispFoulAtMouse(l)
+c F = DispFoulAtMouse(r)
+d e = DumpPGEntities
+d a = DumpAIScoresSummary(T)
+d A = DumpAIScoresSummary(A)
+d d = DumpAIDistancesSummary(T)
+d D = DumpAIDistancesSummary(A)
+d c = DumpIncCardScore
+d t = DumpAITimeVsScoreSummary(T)
+d T = DumpAITimeVsScoreSummary(A)
+d m = ToggleShowMessageLog
+d p = ToggleShowPossession
+d g = ToggleShowShots
+s o = ToggleShowOffside
+d o = ListOffsides
+d h = ListPeriods
+d k = ToggleShowSetPieces
+d K = ListSetPieces
+d G = ToggleShowGoalies
+d l = ListSequences(10)
+d s = HighlightSequence(-1)
+d S = HighlightSequence(+1)
+d x = HighlightSequence(none)
+v i = Zoom(1.25)
+v o = Zoom(0.8)
+v h = Pan(0.05,0)
+v l = Pan(-0.05,0)
+v k = Pan(0,0.05)
+v j = Pan(0,-0.05)
+v f = CycleFollow
+v r = ResetView
+";
+
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum ProgramEvent {
     None,
     Pause,
@@ -19,159 +109,456 @@ pub enum ProgramEvent {
     ToggleShowStamina,
     ToggleShowCards,
     ToggleShowPlayerViewDir,
+    ToggleShowMessages,
+    ToggleShowMessageLog,
+    ToggleShowPossession,
+    ToggleShowShots,
+    ToggleShowOffside,
+    ToggleShowSetPieces,
+    ToggleShowGoalies,
+    /// List the set pieces and their success rates wrt each team and taker
+    ListSetPieces,
+    /// List the offside calls and the count of calls wrt each player
+    ListOffsides,
+    /// List the stats of both teams wrt each period of the game
+    ListPeriods,
+    ToggleConsole,
+    ScrollMessageLog(isize),
     SeekBackward,
     SeekForward,
+    /// Seek relative to the current time
+    Seek(isize),
+    /// Seek to the given game time
+    SeekTo(usize),
     AdjustFPS(f32),
+    SetFPS(f32),
+    /// Select (highlight) the given player (side, playerid), None to clear
+    SelectPlayer(Option<(char, String)>),
+    /// Export the actions info summary into the given json file
+    ExportJson(String),
+    /// List the given number of latest possession sequences
+    ListSequences(usize),
+    /// Highlight a possession sequence on the pitch
+    HighlightSequence(actions::SeqSelect),
     SendRecordCoded(isize),
+    /// A monitor/trainer command entered by the user, using the console
+    SendCommand(String),
+    MoveBallToMouse,
+    /// Free kick to the given side, at the mouse position
+    DispFoulAtMouse(char),
     DumpPGEntities,
     DumpAIScoresSummary(char),
     DumpAIDistancesSummary(char),
     DumpIncCardScore,
     DumpAITimeVsScoreSummary(char),
+    /// Zoom the view by the given ratio, about its center
+    Zoom(f32),
+    /// Zoom the view by the given ratio, about the mouse position
+    ZoomAtMouse(f32),
+    /// Pan the view by the given screen distance, in normal space
+    Pan(f32, f32),
+    /// Cycle what the view follows, ie none, ball, selected player
+    CycleFollow,
+    ResetView,
+    ToggleFullscreen,
+    /// The window was resized to the given width and height
+    WindowResized(u32, u32),
     Quit,
     NeedMore,
 }
 
-fn handle_s_cmds(keycode: Keycode) -> ProgramEvent {
-    match keycode {
-        Keycode::A => {
-            return ProgramEvent::ToggleShowActions;
-        },
-        Keycode::B => {
-            return ProgramEvent::ToggleShowBall;
-        },
-        Keycode::S => {
-            return ProgramEvent::ToggleShowStamina;
-        },
-        Keycode::C => {
-            return ProgramEvent::ToggleShowCards;
-        },
-        Keycode::D => {
-            return ProgramEvent::ToggleShowPlayerViewDir;
-        },
-        Keycode::H => {
-            return ProgramEvent::ToggleShowHelp;
-        },
-        _ => (),
+impl ProgramEvent {
+
+    /// Get the program event from its name and optional argument,
+    /// as used in the key bindings file, ex: AdjustFPS(1.2)
+    pub fn from_str(sevent: &str) -> Result<ProgramEvent, String> {
+        let sevent = sevent.trim();
+        let (name, sarg) = match sevent.split_once('(') {
+            Some((name, rest)) => {
+                match rest.strip_suffix(')') {
+                    Some(sarg) => (name.trim(), sarg.trim()),
+                    None => return Err(format!("Missing ) in [{}]", sevent)),
+                }
+            },
+            None => (sevent, ""),
+        };
+        let noarg = |pev: ProgramEvent| -> Result<ProgramEvent, String> {
+            if sarg.len() > 0 {
+                return Err(format!("{}:Doesnt take a arg", name));
+            }
+            Ok(pev)
+        };
+        let badarg = || -> String { format!("{}:Bad arg [{}]", name, sarg) };
+        let summarytype = || -> Result<char, String> {
+            match sarg {
+                "T" => Ok(actions::SUMMARY_RELATIVE_TEAM),
+                "A" => Ok(actions::SUMMARY_RELATIVE_ALL),
+                _ => Err(format!("{}:Should be T or A, not [{}]", name, sarg)),
+            }
+        };
+        match name {
+            "None" => noarg(ProgramEvent::None),
+            "Pause" => noarg(ProgramEvent::Pause),
+            "BackgroundColorChange" => noarg(ProgramEvent::BackgroundColorChange),
+            "ToggleShowHelp" => noarg(ProgramEvent::ToggleShowHelp),
+            "ToggleShowActions" => noarg(ProgramEvent::ToggleShowActions),
+            "ToggleShowBall" => noarg(ProgramEvent::ToggleShowBall),
+            "ToggleShowStamina" => noarg(ProgramEvent::ToggleShowStamina),
+            "ToggleShowCards" => noarg(ProgramEvent::ToggleShowCards),
+            "ToggleShowPlayerViewDir" => noarg(ProgramEvent::ToggleShowPlayerViewDir),
+            "ToggleShowMessages" => noarg(ProgramEvent::ToggleShowMessages),
+            "ToggleShowMessageLog" => noarg(ProgramEvent::ToggleShowMessageLog),
+            "ToggleShowPossession" => noarg(ProgramEvent::ToggleShowPossession),
+            "ToggleShowShots" => noarg(ProgramEvent::ToggleShowShots),
+            "ToggleShowOffside" => noarg(ProgramEvent::ToggleShowOffside),
+            "ToggleShowSetPieces" => noarg(ProgramEvent::ToggleShowSetPieces),
+            "ToggleShowGoalies" => noarg(ProgramEvent::ToggleShowGoalies),
+            "ListSetPieces" => noarg(ProgramEvent::ListSetPieces),
+            "ListOffsides" => noarg(ProgramEvent::ListOffsides),
+            "ListPeriods" => noarg(ProgramEvent::ListPeriods),
+            "ToggleConsole" => noarg(ProgramEvent::ToggleConsole),
+            "ScrollMessageLog" => Ok(ProgramEvent::ScrollMessageLog(sarg.parse().map_err(|_| badarg())?)),
+            "SeekBackward" => noarg(ProgramEvent::SeekBackward),
+            "SeekForward" => noarg(ProgramEvent::SeekForward),
+            "Seek" => Ok(ProgramEvent::Seek(sarg.parse().map_err(|_| badarg())?)),
+            "SeekTo" => Ok(ProgramEvent::SeekTo(sarg.parse().map_err(|_| badarg())?)),
+            "AdjustFPS" => Ok(ProgramEvent::AdjustFPS(sarg.parse().map_err(|_| badarg())?)),
+            "SetFPS" => Ok(ProgramEvent::SetFPS(sarg.parse().map_err(|_| badarg())?)),
+            "SelectPlayer" => {
+                if sarg == "none" {
+                    return Ok(ProgramEvent::SelectPlayer(None));
+                }
+                Ok(ProgramEvent::SelectPlayer(Some(console::parse_playerid(sarg)?)))
+            },
+            "ExportJson" => {
+                if sarg.len() == 0 {
+                    return Err(badarg());
+                }
+                Ok(ProgramEvent::ExportJson(sarg.to_string()))
+            },
+            "ListSequences" => Ok(ProgramEvent::ListSequences(sarg.parse().map_err(|_| badarg())?)),
+            "HighlightSequence" => Ok(ProgramEvent::HighlightSequence(actions::SeqSelect::parse(sarg)?)),
+            "SendRecordCoded" => Ok(ProgramEvent::SendRecordCoded(sarg.parse().map_err(|_| badarg())?)),
+            "SendCommand" => {
+                rcss::RCCmd::parse(sarg)?;
+                Ok(ProgramEvent::SendCommand(sarg.to_string()))
+            },
+            "MoveBallToMouse" => noarg(ProgramEvent::MoveBallToMouse),
+            "DispFoulAtMouse" => {
+                match sarg {
+                    "l" | "r" => Ok(ProgramEvent::DispFoulAtMouse(sarg.chars().next().unwrap())),
+                    _ => Err(badarg()),
+                }
+            },
+            "DumpPGEntities" => noarg(ProgramEvent::DumpPGEntities),
+            "DumpAIScoresSummary" => Ok(ProgramEvent::DumpAIScoresSummary(summarytype()?)),
+            "DumpAIDistancesSummary" => Ok(ProgramEvent::DumpAIDistancesSummary(summarytype()?)),
+            "DumpIncCardScore" => noarg(ProgramEvent::DumpIncCardScore),
+            "DumpAITimeVsScoreSummary" => Ok(ProgramEvent::DumpAITimeVsScoreSummary(summarytype()?)),
+            "Zoom" => Ok(ProgramEvent::Zoom(sarg.parse().map_err(|_| badarg())?)),
+            "ZoomAtMouse" => Ok(ProgramEvent::ZoomAtMouse(sarg.parse().map_err(|_| badarg())?)),
+            "Pan" => {
+                let (sdx, sdy) = sarg.split_once(',').ok_or_else(badarg)?;
+                let dx = sdx.trim().parse().map_err(|_| badarg())?;
+                let dy = sdy.trim().parse().map_err(|_| badarg())?;
+                Ok(ProgramEvent::Pan(dx, dy))
+            },
+            "CycleFollow" => noarg(ProgramEvent::CycleFollow),
+            "ResetView" => noarg(ProgramEvent::ResetView),
+            "ToggleFullscreen" => noarg(ProgramEvent::ToggleFullscreen),
+            "Quit" => noarg(ProgramEvent::Quit),
+            _ => Err(format!("Unknown program event [{}]", name)),
+        }
     }
-    return ProgramEvent::None;
-}
 
-fn handle_c_cmds(keycode: Keycode) -> ProgramEvent {
-    match keycode {
-        Keycode::Num0 => {
-            return ProgramEvent::SendRecordCoded(0);
-        },
-        Keycode::Num1 => {
-            return ProgramEvent::SendRecordCoded(1);
-        },
-        _ => (),
+    /// A short description of the program event, as shown in the help
+    pub fn describe(&self) -> String {
+        let summarytype = |st: &char| -> &str {
+            if *st == actions::SUMMARY_RELATIVE_ALL { "all" } else { "team" }
+        };
+        match self {
+            ProgramEvent::None | ProgramEvent::NeedMore => "nothing".to_string(),
+            ProgramEvent::Pause => "pause playback".to_string(),
+            ProgramEvent::BackgroundColorChange => "change background color".to_string(),
+            ProgramEvent::ToggleShowHelp => "hide/unhide help".to_string(),
+            ProgramEvent::ToggleShowActions => "show/hide actions".to_string(),
+            ProgramEvent::ToggleShowBall => "show/hide ball".to_string(),
+            ProgramEvent::ToggleShowStamina => "show/hide stamina".to_string(),
+            ProgramEvent::ToggleShowCards => "show/hide cards".to_string(),
+            ProgramEvent::ToggleShowPlayerViewDir => "show/hide player view dir".to_string(),
+            ProgramEvent::ToggleShowMessages => "show/hide msg bubbles".to_string(),
+            ProgramEvent::ToggleShowMessageLog => "show/hide msg log".to_string(),
+            ProgramEvent::ToggleShowPossession => "show/hide possession panel".to_string(),
+            ProgramEvent::ToggleShowShots => "show/hide shot map and xG".to_string(),
+            ProgramEvent::ToggleShowOffside => "show/hide offside lines".to_string(),
+            ProgramEvent::ToggleShowSetPieces => "show/hide set pieces".to_string(),
+            ProgramEvent::ToggleShowGoalies => "show/hide goalies panel".to_string(),
+            ProgramEvent::ListSetPieces => "list set pieces".to_string(),
+            ProgramEvent::ListOffsides => "list offside calls".to_string(),
+            ProgramEvent::ListPeriods => "list stats wrt each half".to_string(),
+            ProgramEvent::ToggleConsole => "open/close console".to_string(),
+            ProgramEvent::ScrollMessageLog(delta) => {
+                if *delta < 0 { "scroll msg log back".to_string() } else { "scroll msg log forward".to_string() }
+            },
+            ProgramEvent::SeekBackward => "seek back".to_string(),
+            ProgramEvent::SeekForward => "seek forward".to_string(),
+            ProgramEvent::Seek(delta) => format!("seek by {}", delta),
+            ProgramEvent::SeekTo(time) => format!("seek to {}", time),
+            ProgramEvent::AdjustFPS(ratio) => {
+                if *ratio < 1.0 { "reduce fps".to_string() } else { "increase fps".to_string() }
+            },
+            ProgramEvent::SetFPS(fps) => format!("set fps to {}", fps),
+            ProgramEvent::SelectPlayer(player) => {
+                match player {
+                    Some((side, pid)) => format!("select player {}{}", side, pid),
+                    None => "clear player selection".to_string(),
+                }
+            },
+            ProgramEvent::ExportJson(fname) => format!("export summary to {}", fname),
+            ProgramEvent::ListSequences(cnt) => format!("list last {} possession sequences", cnt),
+            ProgramEvent::HighlightSequence(seqsel) => {
+                match seqsel {
+                    actions::SeqSelect::None => "clear sequence highlight".to_string(),
+                    actions::SeqSelect::Index(i) => format!("highlight sequence {}", i),
+                    actions::SeqSelect::Step(delta) => {
+                        if *delta < 0 { "highlight previous sequence".to_string() } else { "highlight next sequence".to_string() }
+                    },
+                }
+            },
+            ProgramEvent::SendRecordCoded(code) => {
+                match *code {
+                    rcss::CMDCODE_DISPINIT => "RCLive init hs".to_string(),
+                    rcss::CMDCODE_DISPSTART => "RCLive kick-off".to_string(),
+                    rcss::CMDCODE_PAUSE => "RCLive pause".to_string(),
+                    rcss::CMDCODE_RESUME => "RCLive resume".to_string(),
+                    rcss::CMDCODE_DROPBALL => "RCLive drop ball".to_string(),
+                    rcss::CMDCODE_KICKOFF_L => "RCLive kick-off l".to_string(),
+                    rcss::CMDCODE_KICKOFF_R => "RCLive kick-off r".to_string(),
+                    _ => format!("RCLive send code {}", code),
+                }
+            },
+            ProgramEvent::SendCommand(scmd) => format!("RCLive {}", scmd),
+            ProgramEvent::MoveBallToMouse => "RCLive move ball to mouse".to_string(),
+            ProgramEvent::DispFoulAtMouse(side) => format!("RCLive free kick {} at mouse", side),
+            ProgramEvent::DumpPGEntities => "dump entities".to_string(),
+            ProgramEvent::DumpAIScoresSummary(st) => format!("perf bars wrt {}", summarytype(st)),
+            ProgramEvent::DumpAIDistancesSummary(st) => format!("dist bars wrt {}", summarytype(st)),
+            ProgramEvent::DumpIncCardScore => "include/exclude card score".to_string(),
+            ProgramEvent::DumpAITimeVsScoreSummary(st) => format!("time vs perf wrt {}", summarytype(st)),
+            ProgramEvent::Zoom(ratio) | ProgramEvent::ZoomAtMouse(ratio) => {
+                if *ratio < 1.0 { "zoom out".to_string() } else { "zoom in".to_string() }
+            },
+            ProgramEvent::Pan(dx, dy) => {
+                let sdir = if *dx > 0.0 { "left" } else if *dx < 0.0 { "right" } else if *dy > 0.0 { "up" } else { "down" };
+                format!("look {}", sdir)
+            },
+            ProgramEvent::CycleFollow => "follow none/ball/selected".to_string(),
+            ProgramEvent::ResetView => "reset zoom/pan".to_string(),
+            ProgramEvent::ToggleFullscreen => "fullscreen on/off".to_string(),
+            ProgramEvent::WindowResized(width, height) => format!("window resized to {}x{}", width, height),
+            ProgramEvent::Quit => "quit".to_string(),
+        }
     }
-    return ProgramEvent::None;
+
 }
 
-fn handle_d_cmds(keycode: Keycode, keymod: Mod) -> ProgramEvent {
-    match keycode {
-        Keycode::E => {
-            return ProgramEvent::DumpPGEntities;
-        },
-        Keycode::A => {
-            if keymod.contains(Mod::RSHIFTMOD) || keymod.contains(Mod::LSHIFTMOD) {
-                return ProgramEvent::DumpAIScoresSummary(actions::SUMMARY_RELATIVE_ALL);
-            } else {
-                return ProgramEvent::DumpAIScoresSummary(actions::SUMMARY_RELATIVE_TEAM);
+
+#[derive(Debug, Clone)]
+pub struct KeyBinding {
+    /// The key sequence, keys seperated by a single space
+    pub keyseq: String,
+    pub event: ProgramEvent,
+    pub desc: String,
+}
+
+/// The result of looking up a key sequence
+pub enum KBMatch {
+    Exact(ProgramEvent),
+    /// There are bindings with longer key sequences, starting with this
+    Prefix,
+    None,
+}
+
+#[derive(Debug)]
+pub struct KeyBindings {
+    vbindings: Vec<KeyBinding>,
+}
+
+impl KeyBindings {
+
+    /// Create the key bindings, with the default bindings loaded
+    pub fn new() -> KeyBindings {
+        let mut kb = KeyBindings {
+            vbindings: Vec::new(),
+        };
+        kb.load_str(DEFAULT_BINDINGS, "Default");
+        kb
+    }
+
+    /// Parse a line from the key bindings file.
+    /// Returns None for empty/comment lines, else the key sequence (normalised),
+    /// the program event and the description if any.
+    pub fn parse_line(line: &str) -> Result<Option<(String, ProgramEvent, String)>, String> {
+        let line = line.trim();
+        if (line.len() == 0) || line.starts_with('#') {
+            return Ok(None);
+        }
+        let (skeyseq, rest) = match line.split_once(" = ") {
+            Some(parts) => parts,
+            None => return Err(format!("Missing ' = ' in [{}]", line)),
+        };
+        let keyseq = skeyseq.split_whitespace().collect::<Vec<&str>>().join(" ");
+        if keyseq.len() == 0 {
+            return Err(format!("Missing key sequence in [{}]", line));
+        }
+        let (sevent, desc) = match rest.split_once('#') {
+            Some((sevent, desc)) => (sevent, desc.trim()),
+            None => (rest, ""),
+        };
+        let event = ProgramEvent::from_str(sevent)?;
+        Ok(Some((keyseq, event, desc.to_string())))
+    }
+
+    /// Add or replace the binding wrt the given key sequence.
+    /// Binding to ProgramEvent::None removes it.
+    pub fn set(&mut self, keyseq: &str, event: ProgramEvent, desc: &str) {
+        let desc = if desc.len() == 0 { event.describe() } else { desc.to_string() };
+        let pos = self.vbindings.iter().position(|kb| kb.keyseq == keyseq);
+        if event == ProgramEvent::None {
+            if let Some(pos) = pos {
+                self.vbindings.remove(pos);
             }
-        },
-        Keycode::D => {
-            if keymod.contains(Mod::RSHIFTMOD) || keymod.contains(Mod::LSHIFTMOD) {
-                return ProgramEvent::DumpAIDistancesSummary(actions::SUMMARY_RELATIVE_ALL);
-            } else {
-                return ProgramEvent::DumpAIDistancesSummary(actions::SUMMARY_RELATIVE_TEAM);
+            return;
+        }
+        let kb = KeyBinding { keyseq: keyseq.to_string(), event: event, desc: desc };
+        match pos {
+            Some(pos) => self.vbindings[pos] = kb,
+            None => self.vbindings.push(kb),
+        }
+    }
+
+    /// Load the bindings in the given string, bad lines are skipped with a warning
+    pub fn load_str(&mut self, sdata: &str, srcname: &str) {
+        for (i, line) in sdata.lines().enumerate() {
+            match Self::parse_line(line) {
+                Ok(Some((keyseq, event, desc))) => self.set(&keyseq, event, &desc),
+                Ok(None) => (),
+                Err(err) => eprintln!("WARN:{}:Load:{}:{}:{}", MTAG, srcname, i+1, err),
             }
-        },
-        Keycode::C => {
-            return ProgramEvent::DumpIncCardScore;
         }
-        Keycode::T => {
-            if keymod.contains(Mod::RSHIFTMOD) || keymod.contains(Mod::LSHIFTMOD) {
-                return ProgramEvent::DumpAITimeVsScoreSummary(actions::SUMMARY_RELATIVE_ALL);
-            } else {
-                return ProgramEvent::DumpAITimeVsScoreSummary(actions::SUMMARY_RELATIVE_TEAM);
+    }
+
+    /// Load the bindings from the given file, on top of the existing ones
+    pub fn load_file(&mut self, fname: &str) {
+        match std::fs::read_to_string(fname) {
+            Ok(sdata) => {
+                self.load_str(&sdata, fname);
+                eprintln!("INFO:{}:Loaded:{}", MTAG, fname);
+            },
+            Err(err) => eprintln!("WARN:{}:Load:{}:{}", MTAG, fname, err),
+        }
+    }
+
+    pub fn lookup(&self, keyseq: &str) -> KBMatch {
+        let sprefix = format!("{} ", keyseq);
+        let mut bprefix = false;
+        for kb in &self.vbindings {
+            if kb.keyseq == keyseq {
+                return KBMatch::Exact(kb.event.clone());
+            }
+            if kb.keyseq.starts_with(&sprefix) {
+                bprefix = true;
             }
-        },
-        Keycode::LShift | Keycode::RShift => return ProgramEvent::NeedMore,
-        _ => ldebug!(&format!("DBUG:GPPGND:Keys:DCmds:{}:{}", keycode, keymod)),
+        }
+        if bprefix {
+            return KBMatch::Prefix;
+        }
+        KBMatch::None
+    }
+
+    /// The help lines wrt the active bindings, in the order they were added.
+    pub fn help_lines(&self) -> Vec<String> {
+        let mut vlines = Vec::new();
+        for kb in &self.vbindings {
+            vlines.push(format!("{:9} {}", kb.keyseq, kb.desc));
+        }
+        vlines
+    }
+
+}
+
+
+/// Convert the pressed key into the key token used in the bindings.
+/// Modifier keys return None, as they are used along with other keys.
+fn key_token(keycode: Keycode, keymod: Mod) -> Option<String> {
+    match keycode {
+        Keycode::LShift | Keycode::RShift | Keycode::LCtrl | Keycode::RCtrl |
+        Keycode::LAlt | Keycode::RAlt | Keycode::LGui | Keycode::RGui => return None,
+        _ => (),
     }
-    return ProgramEvent::None;
+    let name = keycode.name();
+    if (name.len() == 1) && name.chars().all(|c| c.is_ascii_alphabetic()) {
+        if keymod.contains(Mod::RSHIFTMOD) || keymod.contains(Mod::LSHIFTMOD) {
+            return Some(name.to_uppercase());
+        }
+        return Some(name.to_lowercase());
+    }
+    Some(name)
 }
 
-pub fn get_programevents(sx: &mut SdlX, skey: &mut String) -> ProgramEvent {
+/// Map the key events to program events, using the key bindings.
+/// skey tracks the keys pressed till now, wrt a multi key sequence.
+pub fn get_programevents(sx: &mut SdlX, skey: &mut String, console: &mut Console, keybindings: &KeyBindings) -> ProgramEvent {
     for ev in sx.ep.poll_iter() {
-        use sdl2::event::Event;
-        if skey.len() > 0 {
-            match ev {
-                Event::Quit { timestamp: _} => return ProgramEvent::Quit,
-                Event::KeyDown { timestamp: _, window_id: _, keycode, scancode: _, keymod, repeat: _ } => {
-                    let mut pev = ProgramEvent::None;
-                    if skey == "s" {
-                        pev = handle_s_cmds(keycode.unwrap());
-                    }
-                    if skey == "c" {
-                        pev = handle_c_cmds(keycode.unwrap());
-                    }
-                    if skey == "d" {
-                        pev = handle_d_cmds(keycode.unwrap(), keymod);
-                    }
-                    if let ProgramEvent::None = pev {
-                        skey.clear();
-                    }
-                    return pev;
-                },
-                _ => return ProgramEvent::None,
-            }
+        use sdl2::event::{Event, WindowEvent};
+        if let Event::Window { win_event: WindowEvent::SizeChanged(width, height), .. } = ev {
+            return ProgramEvent::WindowResized(width as u32, height as u32);
+        }
+        if console.show {
+            return console.handle_event(ev);
         }
-        skey.clear();
         match ev {
             Event::Quit { timestamp: _ } => return ProgramEvent::Quit,
-            Event::KeyDown { timestamp: _, window_id: _, keycode, scancode: _, keymod, repeat: _ } => {
-                match keycode.unwrap() {
-                    Keycode::B => {
-                        return ProgramEvent::BackgroundColorChange;
-                    }
-                    Keycode::C => {
-                        skey.push('c');
-                        return ProgramEvent::NeedMore;
-                    }
-                    Keycode::P => {
-                        return ProgramEvent::Pause;
-                    }
-                    Keycode::S => {
-                        skey.push('s');
-                        return ProgramEvent::NeedMore;
-                    }
-                    Keycode::Left => {
-                        return ProgramEvent::SeekBackward;
-                    }
-                    Keycode::Right => {
-                        return ProgramEvent::SeekForward;
-                    }
-                    Keycode::F => {
-                        if keymod.contains(Mod::RSHIFTMOD) || keymod.contains(Mod::LSHIFTMOD) {
-                            return ProgramEvent::AdjustFPS(1.20);
-                        } else {
-                            return ProgramEvent::AdjustFPS(0.80);
+            Event::KeyDown { timestamp: _, window_id: _, keycode: Some(keycode), scancode: _, keymod, repeat: _ } => {
+                let token = match key_token(keycode, keymod) {
+                    Some(token) => token,
+                    None => return ProgramEvent::NeedMore,
+                };
+                let mut keyseq = if skey.len() > 0 { format!("{} {}", skey, token) } else { token.clone() };
+                let mut kbmatch = keybindings.lookup(&keyseq);
+                if let KBMatch::None = kbmatch {
+                    // Exit the mode, if any, and check the key directly
+                    keyseq = token;
+                    kbmatch = keybindings.lookup(&keyseq);
+                }
+                match kbmatch {
+                    KBMatch::Exact(pev) => {
+                        // Remain in the mode, if any
+                        match keyseq.rsplit_once(' ') {
+                            Some((smode, _)) => *skey = smode.to_string(),
+                            None => skey.clear(),
                         }
-                    }
-                    Keycode::H => {
-                        return ProgramEvent::ToggleShowHelp;
-                    }
-                    Keycode::D => {
-                        skey.push('d');
+                        return pev;
+                    },
+                    KBMatch::Prefix => {
+                        *skey = keyseq;
                         return ProgramEvent::NeedMore;
-                    }
-                    _ => {
-
-                    }
+                    },
+                    KBMatch::None => {
+                        ldebug!(&format!("DBUG:{}:Unbound:{}", MTAG, keyseq));
+                        skey.clear();
+                    },
+                }
+            },
+            Event::MouseWheel { y, .. } => {
+                if y != 0 {
+                    return ProgramEvent::ZoomAtMouse(MOUSEWHEEL_ZOOM.powi(y));
+                }
+            },
+            Event::MouseMotion { mousestate, xrel, yrel, .. } => {
+                if mousestate.left() {
+                    let (prgw, prgh) = sdlx::get_prg_resolution();
+                    return ProgramEvent::Pan(xrel as f32/prgw as f32, yrel as f32/prgh as f32);
                 }
             },
             _ => (),
@@ -4,11 +4,14 @@
 //!
 
 use std::time;
+use std::cell::RefCell;
 
 use proc::actions;
+use proc::shots;
+use proc::setpieces;
 use sdl2::pixels::Color;
 use sdl2::rect::Rect;
-use sdl2::ttf::Font;
+use sdl2::ttf::{Font, Sdl2TtfContext};
 
 use loggerk::{log_init, ldebug, log_d};
 use argsclsk::ArgsCmdLineSimpleManager;
@@ -17,34 +20,304 @@ mod entities;
 mod sdlx;
 mod playdata;
 use playdata::rcg::Rcg;
+use playdata::rcl::Rcl;
+use playdata::rcgrec::RcgRecorder;
+use playdata::monsrv::MonitorServer;
 use playdata::random::RandomData;
-use playdata::{PlayData, rclive};
+use playdata::{PlayData, PlayUpdate, rclive, rcss};
+use playdata::rcss::RCCmd;
 use playdata::rclive::RCLive;
 use sdlx::SdlX;
 use entities::PGEntities;
+use console::Console;
+use keys::KeyBindings;
 
 mod testlib;
 mod keys;
+mod console;
 mod proc;
 
 
 const MTAG: &str = "GPPGND:Main";
 
+/// Max lines in a column, wrt the help msgbox
+const HELP_COLLINES_MAX: usize = 24;
+
+/// The keys supported in the config file, as well as on the commandline (as --key)
+const CFG_KEYS: [&str; 33] = [
+    "mode", "src", "save_interval", "fps", "virtball", "rcl",
+    "rclversion", "rclport", "record", "monsrv", "keybindings",
+    "font", "font_size", "window_width", "window_height", "fullscreen",
+    "lteam_color", "rteam_color", "pitch_rect",
+    "show_stamina", "show_actions", "show_cards", "show_viewdir", "show_msgs", "show_ball",
+    "summary_aiscores", "summary_aidistances", "summary_aitimevsscore",
+    "xg_model", "setpiece_window", "goalies", "config", "print-config",
+];
+
+/// Where the cumulative xG plot is shown ((left,bottom),(width,height))
+const XG_PLOT_WIN: sdlx::XRect = ((0.1, 0.95), (0.8, 0.25));
+
+/// The config file, relative to the users config dir
+const CFG_FILE: &str = "gameplaypgnd-rcss2d/config";
+
 struct Cfg {
     mode: String,
     src: String,
     save_interval: usize,
     fps: f32,
     fvirtball: String,
+    frcl: String,
+    rclversion: usize,
+    rclport: u16,
+    frecord: String,
+    monsrvport: u16,
+    fkeybindings: String,
+    font: String,
+    font_size: u16,
+    /// 0 means size the window based on the display
+    window_width: u32,
+    window_height: u32,
+    fullscreen: bool,
+    lteam_color: Color,
+    rteam_color: Color,
+    pitch_rect: sdlx::XRect,
+    show_stamina: bool,
+    show_actions: bool,
+    show_cards: bool,
+    show_viewdir: bool,
+    show_msgs: bool,
+    show_ball: bool,
+    summary_aiscores: char,
+    summary_aidistances: char,
+    summary_aitimevsscore: char,
+    xg_model: shots::XGModel,
+    setpiece_window: usize,
+    /// The player ids of the left and right goalies, empty if to be identified from the game
+    goalies: (String, String),
+}
+
+fn parse_bool(svalue: &str) -> Result<bool, String> {
+    match svalue {
+        "true" | "yes" | "on" | "1" => Ok(true),
+        "false" | "no" | "off" | "0" => Ok(false),
+        _ => Err(format!("Expected true or false, got [{}]", svalue)),
+    }
+}
+
+/// Parse a comma seperated list of numbers, of the given length
+fn parse_nums<T: std::str::FromStr>(svalue: &str, cnt: usize) -> Result<Vec<T>, String> {
+    let mut vnums = Vec::new();
+    for snum in svalue.split(',') {
+        vnums.push(snum.trim().parse::<T>().map_err(|_| format!("Bad number [{}] in [{}]", snum, svalue))?);
+    }
+    if vnums.len() != cnt {
+        return Err(format!("Expected {} comma seperated numbers, got [{}]", cnt, svalue));
+    }
+    Ok(vnums)
+}
+
+fn parse_summarytype(svalue: &str) -> Result<char, String> {
+    match svalue {
+        "T" => Ok(actions::SUMMARY_RELATIVE_TEAM),
+        "A" => Ok(actions::SUMMARY_RELATIVE_ALL),
+        _ => Err(format!("Expected T or A, got [{}]", svalue)),
+    }
 }
 
 impl Cfg {
 
+    /// The built in defaults
+    fn default() -> Cfg {
+        Cfg {
+            mode: String::from("random"),
+            src: String::new(),
+            save_interval: 0,
+            fps: entities::FRAMES_PER_SEC as f32,
+            fvirtball: String::new(),
+            frcl: String::new(),
+            rclversion: rclive::MONITOR_VERSION_DEFAULT,
+            rclport: rclive::OWN_PORT_DEFAULT,
+            frecord: String::new(),
+            monsrvport: 0,
+            fkeybindings: String::new(),
+            font: sdlx::TTF_FONT.to_string(),
+            font_size: sdlx::TTF_FONT_SIZE,
+            window_width: 0,
+            window_height: 0,
+            fullscreen: false,
+            lteam_color: Color::RED,
+            rteam_color: Color::BLUE,
+            pitch_rect: entities::PITCH_RECT,
+            show_stamina: true,
+            show_actions: true,
+            show_cards: true,
+            show_viewdir: false,
+            show_msgs: true,
+            show_ball: true,
+            summary_aiscores: actions::SUMMARY_RELATIVE_TEAM,
+            summary_aidistances: actions::SUMMARY_RELATIVE_TEAM,
+            summary_aitimevsscore: actions::SUMMARY_RELATIVE_TEAM,
+            xg_model: shots::XGModel::default(),
+            setpiece_window: setpieces::WINDOW_DEFAULT,
+            goalies: (String::new(), String::new()),
+        }
+    }
+
+    /// Set the given config key to the given value
+    fn set(&mut self, key: &str, svalue: &str) -> Result<(), String> {
+        let svalue = svalue.trim();
+        let badnum = |_| format!("Bad number [{}]", svalue);
+        match key {
+            "mode" => self.mode = svalue.to_string(),
+            "src" => self.src = svalue.to_string(),
+            "save_interval" => self.save_interval = svalue.parse().map_err(badnum)?,
+            "fps" => self.fps = svalue.parse().map_err(|_| format!("Bad fps [{}]", svalue))?,
+            "virtball" => self.fvirtball = svalue.to_string(),
+            "rcl" => self.frcl = svalue.to_string(),
+            "rclversion" => self.rclversion = svalue.parse().map_err(badnum)?,
+            "rclport" => self.rclport = svalue.parse().map_err(badnum)?,
+            "record" => self.frecord = svalue.to_string(),
+            "monsrv" => self.monsrvport = svalue.parse().map_err(badnum)?,
+            "keybindings" => self.fkeybindings = svalue.to_string(),
+            "font" => self.font = svalue.to_string(),
+            "font_size" => self.font_size = svalue.parse().map_err(badnum)?,
+            "window_width" => self.window_width = svalue.parse().map_err(badnum)?,
+            "window_height" => self.window_height = svalue.parse().map_err(badnum)?,
+            "fullscreen" => self.fullscreen = parse_bool(svalue)?,
+            "lteam_color" | "rteam_color" => {
+                let vc = parse_nums::<u8>(svalue, 3)?;
+                let color = Color::RGB(vc[0], vc[1], vc[2]);
+                if key == "lteam_color" {
+                    self.lteam_color = color;
+                } else {
+                    self.rteam_color = color;
+                }
+            },
+            "pitch_rect" => {
+                let vr = parse_nums::<f32>(svalue, 4)?;
+                self.pitch_rect = ((vr[0], vr[1]), (vr[2], vr[3]));
+            },
+            "show_stamina" => self.show_stamina = parse_bool(svalue)?,
+            "show_actions" => self.show_actions = parse_bool(svalue)?,
+            "show_cards" => self.show_cards = parse_bool(svalue)?,
+            "show_viewdir" => self.show_viewdir = parse_bool(svalue)?,
+            "show_msgs" => self.show_msgs = parse_bool(svalue)?,
+            "show_ball" => self.show_ball = parse_bool(svalue)?,
+            "summary_aiscores" => self.summary_aiscores = parse_summarytype(svalue)?,
+            "summary_aidistances" => self.summary_aidistances = parse_summarytype(svalue)?,
+            "summary_aitimevsscore" => self.summary_aitimevsscore = parse_summarytype(svalue)?,
+            "xg_model" => {
+                let vc = parse_nums::<f32>(svalue, 4)?;
+                self.xg_model = shots::XGModel::new(vc[0], vc[1], vc[2], vc[3]);
+            },
+            "setpiece_window" => self.setpiece_window = svalue.parse().map_err(badnum)?,
+            "goalies" => {
+                if svalue.is_empty() {
+                    self.goalies = (String::new(), String::new());
+                } else {
+                    match svalue.split_once(',') {
+                        Some((lpid, rpid)) => self.goalies = (lpid.trim().to_string(), rpid.trim().to_string()),
+                        None => return Err(format!("Expected lpid,rpid, got [{}]", svalue)),
+                    }
+                }
+            },
+            _ => return Err(format!("Unknown config key [{}]", key)),
+        }
+        Ok(())
+    }
+
+    /// The effective config, as (key, value) pairs, in the config file format
+    fn entries(&self) -> Vec<(&str, String)> {
+        let color = |c: &Color| format!("{},{},{}", c.r, c.g, c.b);
+        let ((px1, py1), (px2, py2)) = self.pitch_rect;
+        vec![
+            ("mode", self.mode.clone()),
+            ("src", self.src.clone()),
+            ("save_interval", self.save_interval.to_string()),
+            ("fps", self.fps.to_string()),
+            ("virtball", self.fvirtball.clone()),
+            ("rcl", self.frcl.clone()),
+            ("rclversion", self.rclversion.to_string()),
+            ("rclport", self.rclport.to_string()),
+            ("record", self.frecord.clone()),
+            ("monsrv", self.monsrvport.to_string()),
+            ("keybindings", self.fkeybindings.clone()),
+            ("font", self.font.clone()),
+            ("font_size", self.font_size.to_string()),
+            ("window_width", self.window_width.to_string()),
+            ("window_height", self.window_height.to_string()),
+            ("fullscreen", self.fullscreen.to_string()),
+            ("lteam_color", color(&self.lteam_color)),
+            ("rteam_color", color(&self.rteam_color)),
+            ("pitch_rect", format!("{},{},{},{}", px1, py1, px2, py2)),
+            ("show_stamina", self.show_stamina.to_string()),
+            ("show_actions", self.show_actions.to_string()),
+            ("show_cards", self.show_cards.to_string()),
+            ("show_viewdir", self.show_viewdir.to_string()),
+            ("show_msgs", self.show_msgs.to_string()),
+            ("show_ball", self.show_ball.to_string()),
+            ("summary_aiscores", self.summary_aiscores.to_string()),
+            ("summary_aidistances", self.summary_aidistances.to_string()),
+            ("summary_aitimevsscore", self.summary_aitimevsscore.to_string()),
+            ("xg_model", format!("{},{},{},{}", self.xg_model.intercept, self.xg_model.coef_dist, self.xg_model.coef_angle, self.xg_model.coef_defenders)),
+            ("setpiece_window", self.setpiece_window.to_string()),
+            ("goalies", if self.goalies.0.is_empty() && self.goalies.1.is_empty() { String::new() } else { format!("{},{}", self.goalies.0, self.goalies.1) }),
+        ]
+    }
+
+    /// The config file in the users config dir, ie
+    /// $XDG_CONFIG_HOME/CFG_FILE or else $HOME/.config/CFG_FILE
+    fn user_config_file() -> Option<String> {
+        if let Ok(dir) = std::env::var("XDG_CONFIG_HOME") {
+            if dir.len() > 0 {
+                return Some(format!("{}/{}", dir, CFG_FILE));
+            }
+        }
+        if let Ok(dir) = std::env::var("HOME") {
+            return Some(format!("{}/.config/{}", dir, CFG_FILE));
+        }
+        None
+    }
+
+    /// Load the config file, containing lines of the form key = value.
+    /// Empty lines and lines starting with # are skipped.
+    /// bmissingok: dont warn, if the file doesnt exist.
+    fn load_file(&mut self, fname: &str, bmissingok: bool) {
+        let sdata = match std::fs::read_to_string(fname) {
+            Ok(sdata) => sdata,
+            Err(err) => {
+                if !bmissingok || (err.kind() != std::io::ErrorKind::NotFound) {
+                    eprintln!("WARN:{}:Cfg:Load:{}:{}", MTAG, fname, err);
+                }
+                return;
+            }
+        };
+        for (i, line) in sdata.lines().enumerate() {
+            let line = line.trim();
+            if (line.len() == 0) || line.starts_with('#') {
+                continue;
+            }
+            let res = match line.split_once('=') {
+                Some((key, svalue)) => self.set(key.trim(), svalue),
+                None => Err(format!("Missing = in [{}]", line)),
+            };
+            if let Err(err) = res {
+                eprintln!("WARN:{}:Cfg:Load:{}:{}:{}", MTAG, fname, i+1, err);
+            }
+        }
+        eprintln!("INFO:{}:Cfg:Loaded:{}", MTAG, fname);
+    }
+
+    ///
+    /// Configure the program, in the order
+    /// * the built in defaults
+    /// * the config file in the users config dir, or the one specified using --config
+    /// * the commandline args
     ///
-    /// Parse commandline args to configure the program
+    /// All the config keys can be specified on the commandline as --<key> <value>
     ///
     /// --mode random
-    /// --mode rclive [--src <the network addr>]
+    /// --mode rclive [--src <the network addr>] [--rclversion <3|4|5>] [--rclport <local port>]
     /// --mode rcg --src <path/file>
     ///
     /// --save_interval <0 or above> # 0 disable saving playback screen
@@ -53,53 +326,87 @@ impl Cfg {
     ///
     /// --virtball <path/virtball.csv>
     ///
+    /// --rcl <path/file.rcl> # player commands log, used along with --mode rcg
+    ///
+    /// --record <path/file.rcg> # record the session, used along with --mode rclive
+    ///
+    /// --monsrv <port> # rebroadcast the playback to monitor clients, 0 disables
+    ///
+    /// --keybindings <path/file> # override/extend the default key bindings
+    ///
+    /// --config <path/file> # use the given config file
+    ///
+    /// --print-config # print the effective config and exit
+    ///
     fn load() -> Cfg {
+        let mut cfg = Cfg::default();
 
-        let mut cfg = Cfg {
-            mode: String::from("random"),
-            src: String::new(),
-            save_interval: 0,
-            fps: entities::FRAMES_PER_SEC as f32,
-            fvirtball: String::new(),
-        };
-
+        let vcli = RefCell::new(Vec::new());
+        let mut vhandlers: Vec<_> = CFG_KEYS.iter().map(|key| {
+            let vcli = &vcli;
+            move |iarg: usize, args: &Vec<String>| -> usize {
+                if *key == "print-config" {
+                    vcli.borrow_mut().push((key.to_string(), String::new()));
+                    return 0;
+                }
+                if iarg+1 >= args.len() {
+                    eprintln!("ERRR:{}:Cfg:Missing value for --{}", MTAG, key);
+                    std::process::exit(2);
+                }
+                vcli.borrow_mut().push((key.to_string(), args[iarg+1].to_string()));
+                return 1;
+            }
+        }).collect();
         let mut ca = ArgsCmdLineSimpleManager::new();
+        for (key, handler) in CFG_KEYS.iter().zip(vhandlers.iter_mut()) {
+            ca.add_handler(&format!("--{}", key), handler);
+        }
+        ca.process_args();
+        drop(ca);
+        let vcli = vcli.take();
 
-        let mut handle_mode = |iarg: usize, args: &Vec<String>| -> usize {
-            cfg.mode = args[iarg+1].to_string();
-            return 1;
-        };
-        ca.add_handler("--mode", &mut handle_mode);
-
-        let mut handle_src = |iarg: usize, args: &Vec<String>| -> usize {
-            cfg.src = args[iarg+1].to_string();
-            return 1;
-        };
-        ca.add_handler("--src", &mut handle_src);
-
-        let mut handle_saveinterval = |iarg: usize, args: &Vec<String>| -> usize {
-            cfg.save_interval = args[iarg+1].parse().unwrap();
-            return 1;
-        };
-        ca.add_handler("--save_interval", &mut handle_saveinterval);
-
-        let mut handle_saveinterval = |iarg: usize, args: &Vec<String>| -> usize {
-            cfg.fps = args[iarg+1].parse().unwrap();
-            return 1;
-        };
-        ca.add_handler("--fps", &mut handle_saveinterval);
-
-        let mut handle_virtball = |iarg: usize, args: &Vec<String>| -> usize {
-            cfg.fvirtball = args[iarg+1].to_string();
-            return 1;
-        };
-        ca.add_handler("--virtball", &mut handle_virtball);
+        let fconfig = vcli.iter().rev().find(|(key, _)| key == "config").map(|(_, fname)| fname.clone());
+        match fconfig {
+            Some(fname) => cfg.load_file(&fname, false),
+            None => {
+                if let Some(fname) = Self::user_config_file() {
+                    cfg.load_file(&fname, true);
+                }
+            }
+        }
 
-        ca.process_args();
+        let mut bprint = false;
+        for (key, svalue) in vcli {
+            match key.as_str() {
+                "config" => (),
+                "print-config" => bprint = true,
+                _ => {
+                    if let Err(err) = cfg.set(&key, &svalue) {
+                        eprintln!("ERRR:{}:Cfg:--{}:{}", MTAG, key, err);
+                        std::process::exit(2);
+                    }
+                }
+            }
+        }
+        if bprint {
+            cfg.print();
+            std::process::exit(0);
+        }
 
         cfg
     }
 
+    /// Print the effective config, in the config file format
+    fn print(&self) {
+        println!("# gameplaypgnd-rcss2d effective config");
+        if let Some(fname) = Self::user_config_file() {
+            println!("# user config file: {}", fname);
+        }
+        for (key, svalue) in self.entries() {
+            println!("{} = {}", key, svalue);
+        }
+    }
+
 }
 
 
@@ -124,6 +431,8 @@ struct Gui<'a> {
     pgentities: PGEntities<'a>,
     /// Playdata source
     pdata: Box<dyn PlayData>,
+    /// Rebroadcast the playback to monitor clients, if enabled
+    monsrv: Option<MonitorServer>,
     /// Show ActionsInfo Scores summary
     showaiscores: bool,
     /// ActionsInfo Scores summary type
@@ -138,10 +447,22 @@ struct Gui<'a> {
     inc_cardscore: bool,
     /// ActionsInfo TimeVsScore summary show
     showaitimevsscore: bool,
+    /// Show the ball possession panel
+    showpossession: bool,
+    /// Show the shot map and cumulative xG plot
+    showshots: bool,
+    /// Show the set pieces panel and markers
+    showsetpieces: bool,
+    /// Show the goalies panel
+    showgoalies: bool,
     /// ActionsInfo TimeVsScore summary type
     aitimevsscore_summarytype: char,
     /// Game time counter
     timecounter: usize,
+    /// The drop-down console
+    console: Console,
+    /// The key sequence to program event bindings
+    keybindings: KeyBindings,
 }
 
 impl<'a> Gui<'a> {
@@ -167,12 +488,33 @@ impl<'a> Gui<'a> {
 
 impl<'a> Gui<'a> {
 
-    fn new(cfg: &Cfg, font: &'a Font) -> Gui<'a> {
+    fn new(cfg: &Cfg, font: Option<&'a Font>) -> Gui<'a> {
         // PGEntities
-        let mut pgentities = entities::PGEntities::new(entities::PITCH_RECT, &entities::LPLAYERS.to_vec(), &entities::RPLAYERS.to_vec(), cfg.fps, font);
+        let mut pgentities = entities::PGEntities::new(cfg.pitch_rect, &entities::LPLAYERS.to_vec(), &entities::RPLAYERS.to_vec(), cfg.fps, font);
         pgentities.adjust_members(&cfg.fvirtball);
+        pgentities.set_team_colors(cfg.lteam_color, cfg.rteam_color);
+        pgentities.set_bshows(cfg.show_stamina, cfg.show_actions, cfg.show_cards, cfg.show_viewdir, cfg.show_msgs);
+        pgentities.showball = cfg.show_ball;
+        pgentities.actionsinfo.shots.xgmodel = cfg.xg_model.clone();
+        pgentities.actionsinfo.setpieces.window = cfg.setpiece_window;
+        for (side, pid) in [(entities::SIDE_L, &cfg.goalies.0), (entities::SIDE_R, &cfg.goalies.1)] {
+            if !pid.is_empty() {
+                pgentities.actionsinfo.goalies.set_goalie(side, pid);
+            }
+        }
         // Playdata source
         let (pdata, showhelp) = pdata_source(cfg, pgentities.fps());
+        // Monitor server
+        let mut monsrv = None;
+        if cfg.monsrvport > 0 {
+            match MonitorServer::new(cfg.monsrvport) {
+                Ok(srv) => {
+                    eprintln!("INFO:{}:MonSrv:Listening on port {}", MTAG, srv.port());
+                    monsrv = Some(srv);
+                },
+                Err(err) => eprintln!("WARN:{}:MonSrv:{}, running without it", MTAG, err),
+            }
+        }
 
         let ctime = time::Instant::now();
         let mut gui = Gui {
@@ -186,16 +528,26 @@ impl<'a> Gui<'a> {
             curframetime: ctime,
             pgentities: pgentities,
             pdata: pdata,
+            monsrv: monsrv,
             showaiscores: false,
-            aiscores_summarytype: actions::SUMMARY_RELATIVE_TEAM,
+            aiscores_summarytype: cfg.summary_aiscores,
             showaidistances: false,
-            aidistances_summarytype: actions::SUMMARY_RELATIVE_TEAM,
+            aidistances_summarytype: cfg.summary_aidistances,
             saved_virtball_csv: false,
             inc_cardscore: true,
             showaitimevsscore: false,
-            aitimevsscore_summarytype: actions::SUMMARY_RELATIVE_TEAM,
+            showpossession: false,
+            showshots: false,
+            showsetpieces: false,
+            showgoalies: false,
+            aitimevsscore_summarytype: cfg.summary_aitimevsscore,
             timecounter: 0,
+            console: Console::new(),
+            keybindings: KeyBindings::new(),
         };
+        if cfg.fkeybindings.len() > 0 {
+            gui.keybindings.load_file(&cfg.fkeybindings);
+        }
         // sync up fps to spr
         gui.sync_up_fps_to_spr();
         return gui;
@@ -242,34 +594,91 @@ impl<'a> Gui<'a> {
         self.pgentities.seek(seekdelta);
     }
 
+    fn seek_to(&mut self, time: usize) {
+        self.pdata.seek_to(time);
+        self.pgentities.seek(time as isize - self.timecounter as isize);
+    }
+
     fn update_timecounter(&mut self, timecounter: usize) {
         if timecounter > 0 {
             self.timecounter = timecounter;
         }
     }
 
+    /// Let the user know about the result of a action, including in the
+    /// console, if it is being shown.
+    fn report(&mut self, msg: &str) {
+        self.pgentities.timedmsg.update_direct(msg);
+        if self.console.show {
+            self.console.add_output(msg);
+        }
+    }
+
+    /// Send the given command to the playdata source, ie to the server
+    /// in case of rclive.
+    fn send_command(&mut self, cmd: &RCCmd) {
+        self.pdata.send_record(format!("{}\x00", cmd).as_bytes());
+        self.report(&format!("Sent:{}", cmd));
+    }
+
+    /// Export the actions info summary into the given json file
+    fn export_json(&mut self, fname: &str) {
+        let jsummary = self.pgentities.actionsinfo.summary_json(self.inc_cardscore);
+        match std::fs::write(fname, jsummary.to_string()) {
+            Ok(_) => self.report(&format!("Exported:{}", fname)),
+            Err(err) => self.report(&format!("ERRR:Export:{}:{}", fname, err)),
+        }
+    }
+
+    /// Rebroadcast the play update to any monitor clients
+    fn rebroadcast(&mut self, pu: &PlayUpdate) {
+        if let Some(monsrv) = self.monsrv.as_mut() {
+            monsrv.send_playupdate(pu);
+        }
+    }
+
+    /// Accept or drop monitor clients, independent of any play updates
+    fn monsrv_poll(&mut self) {
+        if let Some(monsrv) = self.monsrv.as_mut() {
+            monsrv.poll();
+        }
+    }
+
 }
 
-fn show_help(sx: &mut SdlX) {
-    let shelp = "** Help **\n\
-    \n\
-    larrow: seek back\n\
-    rarrow: seek forward\n\
-    f/F:    change fps\n\
-    p:      pause playback\n\
-    ss:     show/hide stamina\n\
-    sa:     show/hide actions\n\
-    sb:     show/hide ball\n\
-    c1:     RCLive kick-off\n\
-    c0:     RCLive init hs\n\
-    h:      hide/unhide help\n\
-    \n\
-    gameplaypgnd-rcss2d --mode rclive [--src nwaddr]\n\
-    gameplaypgnd-rcss2d --mode rcg --src <path/file.rcg>\n\
-    ...                      Save Nature Save Earth";
-
-    let vhelp: Vec<&str> = shelp.split('\n').collect();
-    sx.n_msgbox((0.3,0.2, 0.4,0.6), vhelp, Color::BLUE);
+/// The mouse position wrt the playground, in normal space, taking the view into account
+fn mouse_pos_view(sx: &SdlX) -> (f32, f32) {
+    let ms = sx.ep.mouse_state();
+    let spos = sx.n2s.o2d((ms.x() as f32, ms.y() as f32));
+    return sx.view.s2v(spos);
+}
+
+/// Get the current mouse position, in Robocups pitch space
+fn mouse_pos_rcss(sx: &SdlX) -> (f32, f32) {
+    let npos = mouse_pos_view(sx);
+    let n2r = sdlx::XSpaces::new(((0.0,0.0), (1.0,1.0)), rcss::RCSS_RECT);
+    return n2r.d2o(npos);
+}
+
+/// Show the help, generated from the active key bindings.
+/// If there are more bindings than fit in one, two columns are used.
+fn show_help(sx: &mut SdlX, keybindings: &KeyBindings) {
+    let mut vlines = keybindings.help_lines();
+    vlines.push("".to_string());
+    vlines.push("gameplaypgnd-rcss2d --mode rclive [--src nwaddr] [--rclversion 3|4|5]".to_string());
+    vlines.push("gameplaypgnd-rcss2d --mode rcg --src <path/file.rcg>".to_string());
+    vlines.push("...                      Save Nature Save Earth".to_string());
+
+    let ncols = if vlines.len() > HELP_COLLINES_MAX { 2 } else { 1 };
+    let icollines = (vlines.len() + ncols - 1)/ncols;
+    let ncolwidth = 0.9/ncols as f32;
+    let mut nx = if ncols == 1 { 0.25 } else { 0.05 };
+    for vcol in vlines.chunks(icollines) {
+        let mut vhelp = vec!["** Help **", ""];
+        vhelp.extend(vcol.iter().map(|s| s.as_str()));
+        sx.n_msgbox((nx,0.05, if ncols == 1 { 0.5 } else { ncolwidth },0.9), vhelp, Color::BLUE);
+        nx += ncolwidth;
+    }
 
 }
 
@@ -280,6 +689,28 @@ fn test_me(font: &Font) {
     testlib::sdlx_plots();
 }
 
+///
+/// Load the ttf font to use, trying in order
+/// * the font specified in the config (--font)
+/// * the monospace fonts found on the system
+///
+/// If no font can be loaded, None is returned and the program
+/// falls back to the builtin pixel font of sdl2-gfx.
+///
+fn load_font<'a>(ttfx: &'a Sdl2TtfContext, cfg: &Cfg) -> Option<Font<'a, 'static>> {
+    for sfont in sdlx::find_ttf_fonts(&cfg.font) {
+        match ttfx.load_font(&sfont, cfg.font_size) {
+            Ok(font) => {
+                eprintln!("INFO:{}:Font:Using:{}", MTAG, sfont);
+                return Some(font);
+            },
+            Err(err) => eprintln!("WARN:{}:Font:Loading[{}]:{}", MTAG, sfont, err),
+        }
+    }
+    eprintln!("WARN:{}:Font:No ttf font found, using builtin pixel font", MTAG);
+    None
+}
+
 fn identify() {
     println!("INFO:{}:GamePlay Playground", MTAG);
     if cfg!(feature = "inbetween_frames") {
@@ -295,7 +726,11 @@ fn identify() {
 /// * mode:rclive: connect to a running rcssserver
 ///   * if src specified, use has nw address of server to connect to
 ///   * else use a default nw address specified in the program
+///   * use the monitor protocol version specified using --rclversion
+///   * bind to the local port specified using --rclport (0 for ephemeral)
+///   * if record specified, save the session into the given rcg file
 /// * mode:rcg: playback the rcg file specified using --src arg to prg.
+///   * if rcl specified, use it has the companion player commands log.
 /// * mode:default: start the random playdata source
 ///
 /// Return the playdata source and whether help msgbox should be shown
@@ -308,10 +743,16 @@ fn pdata_source(cfg: &Cfg, fps: f32) -> (Box<dyn PlayData>, bool) {
         } else {
             nwaddr = rclive::NWADDR_DEFAULT;
         }
-        let pdrcl = RCLive::new(nwaddr);
+        let mut pdrcl = RCLive::new(nwaddr, cfg.rclversion, cfg.rclport);
+        if cfg.frecord.len() > 0 {
+            pdrcl.set_recorder(RcgRecorder::new(&cfg.frecord));
+        }
         return (Box::new(pdrcl), false);
     } else if cfg.mode == "rcg" {
-        let pdrcg = Rcg::new(&cfg.src, fps);
+        let mut pdrcg = Rcg::new(&cfg.src, fps);
+        if cfg.frcl.len() > 0 {
+            pdrcg.set_rcl(Rcl::new(&cfg.frcl));
+        }
         return (Box::new(pdrcg), false);
     } else {
         let pdrandom = RandomData::new(1.0/24.0, 11, 11);
@@ -324,21 +765,29 @@ fn main() {
     log_init();
     identify();
 
+    let cfg = Cfg::load();
+
     // SDL related setup
-    let ttfx = sdl2::ttf::init().unwrap();
-    let font = ttfx.load_font(sdlx::TTF_FONT, sdlx::TTF_FONT_SIZE);
-    if font.is_err() {
-        let err = font.err().unwrap();
-        eprintln!("ERRR:{}:Loading font[{}], install it or update font in sdlx.rs:{}", MTAG, sdlx::TTF_FONT, err);
-        std::process::exit(10);
+    let ttfx = sdl2::ttf::init();
+    if let Err(err) = &ttfx {
+        eprintln!("WARN:{}:TTF:Init failed, using builtin pixel font:{}", MTAG, err);
+    }
+    let ttfx = ttfx.ok();
+    let font = ttfx.as_ref().and_then(|ttfx| load_font(ttfx, &cfg));
+    let bfitdisplay = (cfg.window_width == 0) || (cfg.window_height == 0);
+    let (winw, winh) = if bfitdisplay {
+        (entities::BASE_SCREEN_WIDTH, entities::BASE_SCREEN_HEIGHT)
+    } else {
+        (cfg.window_width, cfg.window_height)
+    };
+    let mut sx = sdlx::SdlX::init_plus("GamePlayPGND", winw, winh, bfitdisplay, false);
+    if cfg.fullscreen {
+        sx.toggle_fullscreen();
     }
-    let font = font.unwrap();
-    let mut sx = sdlx::SdlX::init_plus("GamePlayPGND", entities::BASE_SCREEN_WIDTH, entities::BASE_SCREEN_HEIGHT, false);
-    let (prgw, prgh) = sdlx::get_prg_resolution();
 
-    let cfg = Cfg::load();
     // Get the gui program related entity
-    let mut gui = Gui::new(&cfg, &font);
+    let mut gui = Gui::new(&cfg, font.as_ref());
+    gui.pgentities.scale_members(sx.fontscale);
 
     // The main loop of the program starts now
     let mut dcolor = 20;
@@ -352,9 +801,8 @@ fn main() {
 
         // handle any pending/queued program events
         'eventloop: loop {
-            let prgev= keys::get_programevents(&mut sx, &mut skey);
+            let prgev= keys::get_programevents(&mut sx, &mut skey, &mut gui.console, &gui.keybindings);
             match prgev {
-                keys::ProgramEvent::None => break 'eventloop,
                 keys::ProgramEvent::Pause => {
                     gui.pause = !gui.pause;
                     if gui.pause {
@@ -365,6 +813,7 @@ fn main() {
                 },
                 keys::ProgramEvent::BackgroundColorChange => dcolor = dcolor.wrapping_add(20),
                 keys::ProgramEvent::ToggleShowHelp => gui.showhelp = !gui.showhelp,
+                keys::ProgramEvent::ToggleConsole => gui.console.toggle(),
                 keys::ProgramEvent::ToggleShowBall => {
                     gui.pgentities.showball = !gui.pgentities.showball;
                     if gui.pgentities.showball {
@@ -377,12 +826,84 @@ fn main() {
                 keys::ProgramEvent::ToggleShowStamina => gui.pgentities.toggle_bshowstamina(),
                 keys::ProgramEvent::ToggleShowCards => gui.pgentities.toggle_bshowcards(),
                 keys::ProgramEvent::ToggleShowPlayerViewDir => gui.pgentities.toggle_bshowplayerviewdir(),
+                keys::ProgramEvent::ToggleShowMessages => gui.pgentities.toggle_bshowmsgs(),
+                keys::ProgramEvent::ToggleShowMessageLog => gui.pgentities.toggle_bshowmsglog(),
+                keys::ProgramEvent::ToggleShowOffside => gui.pgentities.toggle_bshowoffside(),
+                keys::ProgramEvent::ToggleShowPossession => gui.showpossession = !gui.showpossession,
+                keys::ProgramEvent::ToggleShowShots => {
+                    gui.showshots = !gui.showshots;
+                    if gui.showshots {
+                        let ssummary = gui.pgentities.actionsinfo.summary_shots();
+                        gui.report(&ssummary);
+                    }
+                },
+                keys::ProgramEvent::ToggleShowSetPieces => gui.showsetpieces = !gui.showsetpieces,
+                keys::ProgramEvent::ToggleShowGoalies => gui.showgoalies = !gui.showgoalies,
+                keys::ProgramEvent::ScrollMessageLog(delta) => gui.pgentities.msglog.scroll(delta),
                 keys::ProgramEvent::SeekBackward => gui.seek(-50),
                 keys::ProgramEvent::SeekForward => gui.seek(50),
+                keys::ProgramEvent::Seek(delta) => gui.seek(delta),
+                keys::ProgramEvent::SeekTo(time) => gui.seek_to(time),
                 keys::ProgramEvent::AdjustFPS(ratio) => {
                     gui.fps_adjust(ratio);
                 },
+                keys::ProgramEvent::SetFPS(fps) => {
+                    gui.fps_adjust(fps/gui.pgentities.fps());
+                    gui.report(&format!("Fps:{}", gui.pgentities.fps()));
+                },
+                keys::ProgramEvent::SelectPlayer(player) => {
+                    gui.pgentities.select_player(&player);
+                    match player {
+                        Some((side, pid)) => {
+                            let ssummary = gui.pgentities.actionsinfo.summary_player(side, &pid, gui.inc_cardscore);
+                            gui.report(&ssummary);
+                        },
+                        None => gui.report("Select:None"),
+                    }
+                },
+                keys::ProgramEvent::ExportJson(fname) => gui.export_json(&fname),
+                keys::ProgramEvent::ListSequences(cnt) => {
+                    let ssummary = gui.pgentities.actionsinfo.summary_sequences(cnt);
+                    eprintln!("INFO:{}:{}", MTAG, ssummary);
+                    gui.console.add_output(&ssummary);
+                },
+                keys::ProgramEvent::ListOffsides => {
+                    let ssummary = gui.pgentities.actionsinfo.summary_offsides();
+                    eprintln!("INFO:{}:{}", MTAG, ssummary);
+                    gui.console.add_output(&ssummary);
+                },
+                keys::ProgramEvent::ListSetPieces => {
+                    let ssummary = gui.pgentities.actionsinfo.summary_setpieces();
+                    eprintln!("INFO:{}:{}", MTAG, ssummary);
+                    gui.console.add_output(&ssummary);
+                },
+                keys::ProgramEvent::ListPeriods => {
+                    let ssummary = gui.pgentities.actionsinfo.summary_periods();
+                    eprintln!("INFO:{}:{}", MTAG, ssummary);
+                    gui.console.add_output(&ssummary);
+                },
+                keys::ProgramEvent::HighlightSequence(seqsel) => {
+                    let smsg = match gui.pgentities.actionsinfo.highlight_sequence(seqsel) {
+                        Some(seq) => format!("Seq:{}", seq),
+                        None => "Seq:None".to_string(),
+                    };
+                    gui.report(&smsg);
+                },
                 keys::ProgramEvent::SendRecordCoded(code) => gui.pdata.send_record_coded(code),
+                keys::ProgramEvent::SendCommand(scmd) => {
+                    match RCCmd::parse(&scmd) {
+                        Ok(cmd) => gui.send_command(&cmd),
+                        Err(err) => gui.report(&format!("ERRR:Cmd:{}", err)),
+                    }
+                },
+                keys::ProgramEvent::MoveBallToMouse => {
+                    let pos = mouse_pos_rcss(&sx);
+                    gui.send_command(&RCCmd::MoveBall(pos.0, pos.1));
+                },
+                keys::ProgramEvent::DispFoulAtMouse(side) => {
+                    let pos = mouse_pos_rcss(&sx);
+                    gui.send_command(&RCCmd::DispFoul(pos.0, pos.1, side));
+                },
                 keys::ProgramEvent::DumpPGEntities => eprintln!("DBUG:{}:Entities:{:#?}", MTAG, gui.pgentities),
                 keys::ProgramEvent::DumpAIScoresSummary(summarytype) => {
                     gui.pgentities.actionsinfo.summary(gui.inc_cardscore);
@@ -420,11 +941,39 @@ fn main() {
                         gui.pgentities.timedmsg.update_direct(&format!("TimeVsPerf:{}", st));
                     }
                 },
+                keys::ProgramEvent::Zoom(ratio) => {
+                    let center = sx.view.center;
+                    sx.view.zoom_by(ratio, center);
+                },
+                keys::ProgramEvent::ZoomAtMouse(ratio) => {
+                    let npos = mouse_pos_view(&sx);
+                    sx.view.zoom_by(ratio, npos);
+                },
+                keys::ProgramEvent::Pan(dx, dy) => sx.view.pan(dx, dy),
+                keys::ProgramEvent::CycleFollow => gui.pgentities.cycle_follow(),
+                keys::ProgramEvent::ResetView => {
+                    sx.view = sdlx::XView::new();
+                    gui.pgentities.follow = entities::Follow::None;
+                },
+                keys::ProgramEvent::ToggleFullscreen => {
+                    let bfullscreen = sx.toggle_fullscreen();
+                    gui.pgentities.scale_members(sx.fontscale);
+                    gui.report(&format!("Fullscreen:{}", if bfullscreen { "On" } else { "Off" }));
+                },
+                keys::ProgramEvent::WindowResized(width, height) => {
+                    ldebug!(&format!("INFO:{}:Window:Resized:{}x{}", MTAG, width, height));
+                    sx.resize(width, height);
+                    gui.pgentities.scale_members(sx.fontscale);
+                },
                 keys::ProgramEvent::Quit => break 'mainloop,
                 keys::ProgramEvent::NeedMore => (),
+                keys::ProgramEvent::None => break 'eventloop,
             }
         }
 
+        // Monitor clients can connect or leave, even when paused or done
+        gui.monsrv_poll();
+
         // Update the entities
         if !gui.pause {
             if !gui.pdata.bdone() {
@@ -432,6 +981,7 @@ fn main() {
                     if gui.pdata.next_frame_is_record_ready() {
                         let pu = gui.pdata.next_record();
                         ldebug!(&format!("DBUG:{}:{:?}", MTAG, pu));
+                        gui.rebroadcast(&pu);
                         gui.update_timecounter(pu.timecounter);
                         gui.pgentities.update(pu, false, gui.pdata.seconds_per_record() * gui.pgentities.fps());
                         //eprintln!("DBUG:GPPGND:Main:{}:Update called", _frame);
@@ -442,6 +992,7 @@ fn main() {
                     //eprintln!("DBUG:GPPGND:Main:{}:NextFrame called", _frame);
                 } else {
                     let pu = gui.pdata.next_record();
+                    gui.rebroadcast(&pu);
                     gui.update_timecounter(pu.timecounter);
                     gui.pgentities.update(pu, true, 0.0);
                 }
@@ -454,9 +1005,12 @@ fn main() {
         }
 
         // Draw entities
+        if let Some(npos) = gui.pgentities.follow_npos() {
+            sx.view.center_on(npos);
+        }
         gui.pgentities.draw(&mut sx);
         if gui.showhelp {
-            show_help(&mut sx);
+            show_help(&mut sx, &gui.keybindings);
         }
 
         // Draw info
@@ -469,13 +1023,33 @@ fn main() {
         if gui.showaitimevsscore {
             gui.pgentities.actionsinfo.summary_tvs(&mut sx, gui.timecounter, &actions::SummaryPlayerType::ScoreCumulative, ((0.1,0.9),(0.8,0.8)), gui.aitimevsscore_summarytype);
         }
+        if gui.showshots {
+            sx.view_apply(true);
+            gui.pgentities.actionsinfo.draw_shotmap(&mut sx);
+            sx.view_apply(false);
+            gui.pgentities.actionsinfo.summary_xg_sdl(&mut sx, gui.timecounter, XG_PLOT_WIN);
+        }
+        if gui.showpossession {
+            gui.pgentities.actionsinfo.summary_possession_sdl(&mut sx);
+        }
+        if gui.showsetpieces {
+            sx.view_apply(true);
+            gui.pgentities.actionsinfo.draw_setpieces(&mut sx);
+            sx.view_apply(false);
+            gui.pgentities.actionsinfo.summary_setpieces_sdl(&mut sx);
+        }
+        if gui.showgoalies {
+            gui.pgentities.actionsinfo.summary_goalies_sdl(&mut sx);
+        }
         gui.pgentities.actionsinfo.show_ballpossession(&mut sx);
+        gui.console.draw(&mut sx);
 
         // Present screen update to user
         sx.wc.present();
 
         // Save raw screen data
         if (cfg.save_interval > 0) && ((gui.frame % cfg.save_interval) == 0) {
+            let (prgw, prgh) = sdlx::get_prg_resolution();
             let imgdata = sx.wc.read_pixels(Some(Rect::new(0,0,prgw,prgh)), sdl2::pixels::PixelFormatEnum::RGB24).unwrap();
             std::fs::write(&format!("/tmp/gppgnd{:04}.rgb", gui.frame), imgdata).unwrap();
         }
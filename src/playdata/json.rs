@@ -0,0 +1,302 @@
+//!
+//! A minimal json parser, to help decode the json based messages
+//! like those sent by rcssserver wrt monitor protocol version 5.
+//! HanishKVC, 2022
+//!
+//! Objects retain the order of their members, and unknown members
+//! are retained as is, so that users can pick what they need and
+//! ignore the rest.
+//!
+
+use std::iter::Peekable;
+use std::str::Chars;
+
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum JValue {
+    Null,
+    Bool(bool),
+    Num(f64),
+    Str(String),
+    Arr(Vec<JValue>),
+    /// Vector of (Key, Value), in the order seen.
+    Obj(Vec<(String, JValue)>),
+}
+
+impl JValue {
+
+    /// Get the value of the given member, if this is a object.
+    pub fn get(&self, key: &str) -> Option<&JValue> {
+        if let JValue::Obj(members) = self {
+            for (k, v) in members {
+                if k == key {
+                    return Some(v);
+                }
+            }
+        }
+        None
+    }
+
+    pub fn as_f32(&self) -> Option<f32> {
+        match self {
+            JValue::Num(n) => Some(*n as f32),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            JValue::Str(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_arr(&self) -> Option<&Vec<JValue>> {
+        match self {
+            JValue::Arr(a) => Some(a),
+            _ => None,
+        }
+    }
+
+    /// Get the member as f32, else return the given default.
+    pub fn get_f32(&self, key: &str, def: f32) -> f32 {
+        self.get(key).and_then(|v| v.as_f32()).unwrap_or(def)
+    }
+
+    /// Get the member as string, else return a empty string.
+    pub fn get_string(&self, key: &str) -> String {
+        self.get(key).and_then(|v| v.as_str()).unwrap_or("").to_string()
+    }
+
+    /// Convert a simple (ie non object/array) value to string,
+    /// without quoting strings. Objects and arrays are converted
+    /// back to their json representation.
+    pub fn to_simple_string(&self) -> String {
+        match self {
+            JValue::Null => "null".to_string(),
+            JValue::Bool(b) => b.to_string(),
+            JValue::Num(n) => n.to_string(),
+            JValue::Str(s) => s.to_string(),
+            _ => self.to_string(),
+        }
+    }
+
+}
+
+/// Write the given string as a json string, with the required escaping
+fn write_jstring(f: &mut std::fmt::Formatter<'_>, s: &str) -> std::fmt::Result {
+    f.write_str("\"")?;
+    for c in s.chars() {
+        match c {
+            '"' => f.write_str("\\\"")?,
+            '\\' => f.write_str("\\\\")?,
+            '\n' => f.write_str("\\n")?,
+            '\r' => f.write_str("\\r")?,
+            '\t' => f.write_str("\\t")?,
+            c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+            c => write!(f, "{}", c)?,
+        }
+    }
+    f.write_str("\"")
+}
+
+impl std::fmt::Display for JValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            JValue::Null => f.write_str("null"),
+            JValue::Bool(b) => write!(f, "{}", b),
+            JValue::Num(n) => write!(f, "{}", n),
+            JValue::Str(s) => write_jstring(f, s),
+            JValue::Arr(a) => {
+                f.write_str("[")?;
+                for i in 0..a.len() {
+                    if i > 0 {
+                        f.write_str(",")?;
+                    }
+                    write!(f, "{}", a[i])?;
+                }
+                f.write_str("]")
+            },
+            JValue::Obj(o) => {
+                f.write_str("{")?;
+                for i in 0..o.len() {
+                    if i > 0 {
+                        f.write_str(",")?;
+                    }
+                    write_jstring(f, &o[i].0)?;
+                    write!(f, ":{}", o[i].1)?;
+                }
+                f.write_str("}")
+            },
+        }
+    }
+}
+
+
+struct Parser<'a> {
+    chars: Peekable<Chars<'a>>,
+}
+
+impl<'a> Parser<'a> {
+
+    fn skip_whitespaces(&mut self) {
+        while let Some(c) = self.chars.peek() {
+            if !c.is_whitespace() {
+                break;
+            }
+            self.chars.next();
+        }
+    }
+
+    fn expect(&mut self, exp: char) -> Result<(), String> {
+        self.skip_whitespaces();
+        match self.chars.next() {
+            Some(c) if c == exp => Ok(()),
+            Some(c) => Err(format!("Expected [{}], got [{}]", exp, c)),
+            None => Err(format!("Expected [{}], got end", exp)),
+        }
+    }
+
+    fn value(&mut self) -> Result<JValue, String> {
+        self.skip_whitespaces();
+        let c = match self.chars.peek() {
+            Some(c) => *c,
+            None => return Err("Unexpected end".to_string()),
+        };
+        match c {
+            '{' => self.object(),
+            '[' => self.array(),
+            '"' => Ok(JValue::Str(self.string()?)),
+            't' | 'f' | 'n' => self.literal(),
+            _ => self.number(),
+        }
+    }
+
+    fn object(&mut self) -> Result<JValue, String> {
+        self.expect('{')?;
+        let mut members = Vec::new();
+        self.skip_whitespaces();
+        if self.chars.peek() == Some(&'}') {
+            self.chars.next();
+            return Ok(JValue::Obj(members));
+        }
+        loop {
+            self.skip_whitespaces();
+            let key = self.string()?;
+            self.expect(':')?;
+            let value = self.value()?;
+            members.push((key, value));
+            self.skip_whitespaces();
+            match self.chars.next() {
+                Some(',') => continue,
+                Some('}') => break,
+                Some(c) => return Err(format!("Object:Unexpected [{}]", c)),
+                None => return Err("Object:Unexpected end".to_string()),
+            }
+        }
+        Ok(JValue::Obj(members))
+    }
+
+    fn array(&mut self) -> Result<JValue, String> {
+        self.expect('[')?;
+        let mut values = Vec::new();
+        self.skip_whitespaces();
+        if self.chars.peek() == Some(&']') {
+            self.chars.next();
+            return Ok(JValue::Arr(values));
+        }
+        loop {
+            values.push(self.value()?);
+            self.skip_whitespaces();
+            match self.chars.next() {
+                Some(',') => continue,
+                Some(']') => break,
+                Some(c) => return Err(format!("Array:Unexpected [{}]", c)),
+                None => return Err("Array:Unexpected end".to_string()),
+            }
+        }
+        Ok(JValue::Arr(values))
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.expect('"')?;
+        let mut s = String::new();
+        loop {
+            let c = match self.chars.next() {
+                Some(c) => c,
+                None => return Err("String:Unexpected end".to_string()),
+            };
+            match c {
+                '"' => break,
+                '\\' => {
+                    let e = match self.chars.next() {
+                        Some(e) => e,
+                        None => return Err("String:Unexpected end".to_string()),
+                    };
+                    match e {
+                        'n' => s.push('\n'),
+                        't' => s.push('\t'),
+                        'r' => s.push('\r'),
+                        'b' => s.push('\x08'),
+                        'f' => s.push('\x0c'),
+                        'u' => {
+                            let hex: String = (0..4).filter_map(|_| self.chars.next()).collect();
+                            let code = u32::from_str_radix(&hex, 16).map_err(|e| format!("String:BadUnicode:{}:{}", hex, e))?;
+                            s.push(char::from_u32(code).unwrap_or('\u{FFFD}'));
+                        },
+                        _ => s.push(e),
+                    }
+                },
+                _ => s.push(c),
+            }
+        }
+        Ok(s)
+    }
+
+    fn literal(&mut self) -> Result<JValue, String> {
+        let mut word = String::new();
+        while let Some(c) = self.chars.peek() {
+            if !c.is_ascii_alphabetic() {
+                break;
+            }
+            word.push(*c);
+            self.chars.next();
+        }
+        match word.as_str() {
+            "true" => Ok(JValue::Bool(true)),
+            "false" => Ok(JValue::Bool(false)),
+            "null" => Ok(JValue::Null),
+            _ => Err(format!("Unknown literal [{}]", word)),
+        }
+    }
+
+    fn number(&mut self) -> Result<JValue, String> {
+        let mut snum = String::new();
+        while let Some(c) = self.chars.peek() {
+            if !(c.is_ascii_digit() || *c == '-' || *c == '+' || *c == '.' || *c == 'e' || *c == 'E') {
+                break;
+            }
+            snum.push(*c);
+            self.chars.next();
+        }
+        match snum.parse() {
+            Ok(n) => Ok(JValue::Num(n)),
+            Err(e) => Err(format!("Bad number [{}]:{}", snum, e)),
+        }
+    }
+
+}
+
+/// Parse the given string into a json value.
+/// Any trailing whitespace or nul chars (as found in udp msgs) are ignored.
+pub fn parse(s: &str) -> Result<JValue, String> {
+    let mut parser = Parser { chars: s.chars().peekable() };
+    let value = parser.value()?;
+    parser.skip_whitespaces();
+    for c in parser.chars {
+        if (c != '\0') && !c.is_whitespace() {
+            return Err(format!("Trailing data [{}]", c));
+        }
+    }
+    Ok(value)
+}
@@ -35,6 +35,25 @@ pub enum Action {
     Others(usize),
 }
 
+#[derive(Debug, PartialEq, Clone)]
+/// Commands sent by a player to the server, as captured in logs like the rcl file.
+pub enum PlayerCmd {
+    /// Power, Direction
+    Kick(f32, f32),
+    /// Power, Direction
+    Dash(f32, f32),
+    Turn(f32),
+    TurnNeck(f32),
+    /// PowerOrDirection, Foul
+    Tackle(f32, bool),
+    Catch(f32),
+    Move(f32, f32),
+    Say(String),
+    /// Distance, Direction; None if pointing is turned off
+    PointTo(Option<(f32, f32)>),
+    Other(String),
+}
+
 #[derive(Debug)]
 /// Player related data
 pub enum PlayerData {
@@ -45,6 +64,10 @@ pub enum PlayerData {
     Action(Action),
     /// Player Body and Neck direction, view angle width
     Dir(f32,f32,f32),
+    /// A command sent by the player, if the playdata source knows about it.
+    Cmd(PlayerCmd),
+    /// The player is the goalie of the team
+    Goalie,
 }
 
 /// Maintain a vector of player related data
@@ -53,17 +76,13 @@ pub type VPlayerData = Vec<PlayerData>;
 
 pub type PlayerCodedData = (TPlayerId, VPlayerData);
 
-#[derive(Debug)]
-pub enum GameState {
-    None,
-    PlayPaused,
-    PlayOn,
-    Goal(char),
-    Other(String),
-}
-
 pub type Messages = HashMap<String, String>;
 
+/// Parameters like those wrt server, player and player types.
+/// The key is of the form Group.Name, ex: server_param.goal_width,
+/// player_type.3.kickable_margin.
+pub type Params = HashMap<String, String>;
+
 #[derive(Debug)]
 /// Maintain possible updates wrt a playdata update.
 /// It can contain
@@ -72,10 +91,19 @@ pub type Messages = HashMap<String, String>;
 /// * position of the ball
 /// * positiono f the players.
 /// * stamina of the players
+/// * communication messages if any, beyond the player says (which are part of player data)
+/// * parameters if any, sent by the source
+/// * team names and scores, if any
 pub struct PlayUpdate {
     pub timecounter: usize,
-    pub state: GameState,
+    /// The play mode, if got wrt this update, None means no change.
+    pub state: Option<rcss::PlayMode>,
     pub msgs: Messages,
+    /// Vector of (From, Message)
+    pub commsgs: Vec<(String, String)>,
+    pub params: Params,
+    /// Vector of (Side, Name, Score) wrt the teams, if any
+    pub teams: Vec<(char, String, usize)>,
     pub ball: (f32, f32),
     pub lteamcoded: Vec<PlayerCodedData>,
     pub rteamcoded: Vec<PlayerCodedData>,
@@ -86,8 +114,11 @@ impl PlayUpdate {
     pub fn new() -> PlayUpdate {
         PlayUpdate {
             timecounter: 0,
-            state: GameState::None,
+            state: None,
             msgs: Messages::new(),
+            commsgs: Vec::new(),
+            params: Params::new(),
+            teams: Vec::new(),
             ball: (0.0,0.0),
             lteamcoded: Vec::new(),
             rteamcoded: Vec::new(),
@@ -134,6 +165,10 @@ pub trait PlayData {
     /// backward through its list of records.
     fn seek(&mut self, seekdelta: isize);
 
+    /// Request the playdata source to seek to the record wrt the
+    /// given game time (cycle), or the nearest one after it.
+    fn seek_to(&mut self, time: usize);
+
     /// Playdata source informs the main program, that there is
     /// no more data available with it.
     fn bdone(&self) -> bool;
@@ -146,7 +181,11 @@ pub trait PlayData {
 
 }
 
+pub mod json;
+pub mod monsrv;
 pub mod random;
 pub mod rcss;
 pub mod rcg;
+pub mod rcgrec;
+pub mod rcl;
 pub mod rclive;
\ No newline at end of file
@@ -0,0 +1,272 @@
+//!
+//! Act as a robocup soccer sim server, wrt monitors
+//! HanishKVC, 2022
+//!
+//! The play updates got from any playdata source (rcg playback, rclive)
+//! are rebroadcast to the monitor clients which have connected to us,
+//! using the json based monitor protocol version 5.
+//!
+//! Monitor clients send (dispinit version 5) to connect and (dispbye)
+//! to disconnect. Other monitor commands are ignored currently.
+//!
+
+use std::net::{UdpSocket, SocketAddr};
+
+use loggerk::{ldebug, log_d};
+
+use crate::entities;
+use crate::sdlx::XSpaces;
+use super::json::JValue;
+use super::{rcss, PlayUpdate, PlayerData, Params, Card, Action};
+
+
+const MTAG: &str = "GPPGND:PlayDataMonSrv";
+
+/// The only monitor protocol version served.
+const MONITOR_VERSION: usize = 5;
+
+
+pub struct MonitorServer {
+    skt: UdpSocket,
+    /// The monitor clients currently connected.
+    clients: Vec<SocketAddr>,
+    /// Help convert from normal space to Robocups pitch space.
+    n2r: XSpaces,
+    /// The last known playmode, teams and params. Playdata sources
+    /// provide these only when they change, while the show messages
+    /// sent to monitors need them always.
+    mode: String,
+    teams: Vec<(char, String, usize)>,
+    params: Params,
+}
+
+impl MonitorServer {
+
+    /// Listen for monitor clients on the given port, 0 means a ephemeral port.
+    pub fn new(port: u16) -> Result<MonitorServer, String> {
+        let skt = UdpSocket::bind(("0.0.0.0", port)).map_err(|err| format!("Failed binding to port {}:{}", port, err))?;
+        skt.set_nonblocking(true).map_err(|err| format!("Failed setting nonblocking:{}", err))?;
+        eprintln!("INFO:{}:New:{:?}", MTAG, skt);
+        Ok(MonitorServer {
+            skt: skt,
+            clients: Vec::new(),
+            n2r: XSpaces::new(((0.0,0.0), (1.0,1.0)), rcss::RCSS_RECT),
+            mode: "before_kick_off".to_string(),
+            teams: Vec::new(),
+            params: Params::new(),
+        })
+    }
+
+    /// The port on which monitor clients are listened for
+    pub fn port(&self) -> u16 {
+        return self.skt.local_addr().map_or(0, |addr| addr.port());
+    }
+
+    /// The number of monitor clients currently connected
+    pub fn clients(&self) -> usize {
+        return self.clients.len();
+    }
+
+    fn send(&self, addr: &SocketAddr, smsg: &str) {
+        if let Err(err) = self.skt.send_to(smsg.as_bytes(), addr) {
+            ldebug!(&format!("DBUG:{}:Send:{}:{}", MTAG, addr, err));
+        }
+    }
+
+    /// Send the params known till now, to the given client.
+    fn send_params(&self, addr: &SocketAddr) {
+        let mut groups: Vec<(String, Vec<(String, JValue)>)> = Vec::new();
+        let mut keys: Vec<&String> = self.params.keys().collect();
+        // server_param is sent first, like rcssserver
+        keys.sort_by_key(|key| (!key.starts_with("server_param."), key.to_string()));
+        for key in keys {
+            let (group, name) = match key.rsplit_once('.') {
+                Some(parts) => parts,
+                None => continue,
+            };
+            let value = &self.params[key];
+            let jvalue = match value.parse::<f64>() {
+                Ok(num) => JValue::Num(num),
+                Err(_) => JValue::Str(value.to_string()),
+            };
+            if (groups.len() == 0) || (groups[groups.len()-1].0 != group) {
+                groups.push((group.to_string(), Vec::new()));
+            }
+            let ilast = groups.len() - 1;
+            groups[ilast].1.push((name.to_string(), jvalue));
+        }
+        for (group, members) in groups {
+            let mut vmsg = Vec::new();
+            match group.split_once('.') {
+                Some((stype, id)) => {
+                    vmsg.push(("type".to_string(), JValue::Str(stype.to_string())));
+                    vmsg.push(("id".to_string(), JValue::Num(id.parse().unwrap_or(0.0))));
+                },
+                None => vmsg.push(("type".to_string(), JValue::Str(group.to_string()))),
+            }
+            vmsg.push(("params".to_string(), JValue::Obj(members)));
+            self.send(addr, &JValue::Obj(vmsg).to_string());
+        }
+    }
+
+    /// Handle any pending requests from the monitor clients.
+    pub fn poll(&mut self) {
+        let mut buf = [0u8; 1024];
+        loop {
+            let (ilen, addr) = match self.skt.recv_from(&mut buf) {
+                Ok(gotr) => gotr,
+                Err(err) => {
+                    if err.kind() != std::io::ErrorKind::WouldBlock {
+                        ldebug!(&format!("DBUG:{}:Poll:{}", MTAG, err));
+                    }
+                    break;
+                }
+            };
+            let smsg = String::from_utf8_lossy(&buf[0..ilen]);
+            let smsg = smsg.trim_end_matches(|c: char| c == '\0' || c.is_whitespace());
+            let toks = rcss::sexp_tokens(smsg);
+            if toks.len() == 0 {
+                continue;
+            }
+            match toks[0].as_str() {
+                "dispinit" => {
+                    if (toks.len() > 2) && (toks[2] != MONITOR_VERSION.to_string()) {
+                        eprintln!("WARN:{}:Poll:{}:Only version {} is supported, not {}", MTAG, addr, MONITOR_VERSION, toks[2]);
+                    }
+                    if !self.clients.contains(&addr) {
+                        self.clients.push(addr);
+                    }
+                    eprintln!("INFO:{}:Poll:ClientAdded:{}:Clients:{}", MTAG, addr, self.clients());
+                    self.send_params(&addr);
+                },
+                "dispbye" => {
+                    self.clients.retain(|client| *client != addr);
+                    eprintln!("INFO:{}:Poll:ClientRemoved:{}:Clients:{}", MTAG, addr, self.clients());
+                },
+                _ => {
+                    ldebug!(&format!("DBUG:{}:Poll:Ignoring:{}:{}", MTAG, addr, smsg));
+                }
+            }
+        }
+    }
+
+    /// Convert the players in the given play update into the json objects
+    /// required by the show message.
+    fn players(&self, side: char, teamcoded: &Vec<super::PlayerCodedData>, vplayers: &mut Vec<JValue>) {
+        for (pid, pd) in teamcoded {
+            let mut pos = (0.0, 0.0);
+            let mut dir = (0.0, 0.0, 90.0);
+            let mut stamina = 1.0;
+            let mut action = Action::None;
+            let mut card = Card::None;
+            let mut goalie = false;
+            for data in pd {
+                match data {
+                    PlayerData::Pos(fx, fy) => pos = self.n2r.d2o((*fx, *fy)),
+                    PlayerData::Dir(fbody, fneck, fvw) => dir = (*fbody, *fneck, *fvw),
+                    PlayerData::Stamina(fstamina) => stamina = *fstamina,
+                    PlayerData::Action(paction) => action = paction.clone(),
+                    PlayerData::Card(pcard) => card = pcard.clone(),
+                    PlayerData::Goalie => goalie = true,
+                    _ => (),
+                }
+            }
+            let unum: f64 = pid.parse().unwrap_or(0.0);
+            let mut state = rcss::state_from(&action, &card);
+            if goalie {
+                state |= rcss::STATE_GOALIE;
+            }
+            let vplayer = vec![
+                ("side".to_string(), JValue::Str(side.to_string())),
+                ("unum".to_string(), JValue::Num(unum)),
+                ("type".to_string(), JValue::Num(0.0)),
+                ("state".to_string(), JValue::Num(state as f64)),
+                ("x".to_string(), JValue::Num(pos.0 as f64)),
+                ("y".to_string(), JValue::Num(pos.1 as f64)),
+                ("vx".to_string(), JValue::Num(0.0)),
+                ("vy".to_string(), JValue::Num(0.0)),
+                ("body".to_string(), JValue::Num(dir.0 as f64)),
+                ("neck".to_string(), JValue::Num(dir.1 as f64)),
+                ("vq".to_string(), JValue::Str("h".to_string())),
+                ("vw".to_string(), JValue::Num(dir.2 as f64)),
+                ("stamina".to_string(), JValue::Num((stamina*rcss::STAMINA_BASE) as f64)),
+                ("effort".to_string(), JValue::Num(1.0)),
+                ("recovery".to_string(), JValue::Num(1.0)),
+                ("capacity".to_string(), JValue::Num(-1.0)),
+            ];
+            vplayers.push(JValue::Obj(vplayer));
+        }
+    }
+
+    /// Convert the play update into a json based show message.
+    fn show(&self, pu: &PlayUpdate) -> String {
+        let mut vteams = Vec::new();
+        for (side, name, score) in &self.teams {
+            vteams.push(JValue::Obj(vec![
+                ("side".to_string(), JValue::Str(side.to_string())),
+                ("name".to_string(), JValue::Str(name.to_string())),
+                ("score".to_string(), JValue::Num(*score as f64)),
+            ]));
+        }
+        let ball = self.n2r.d2o(pu.ball);
+        let jball = JValue::Obj(vec![
+            ("x".to_string(), JValue::Num(ball.0 as f64)),
+            ("y".to_string(), JValue::Num(ball.1 as f64)),
+            ("vx".to_string(), JValue::Num(0.0)),
+            ("vy".to_string(), JValue::Num(0.0)),
+        ]);
+        let mut vplayers = Vec::new();
+        self.players(entities::SIDE_L, &pu.lteamcoded, &mut vplayers);
+        self.players(entities::SIDE_R, &pu.rteamcoded, &mut vplayers);
+        let jshow = JValue::Obj(vec![
+            ("type".to_string(), JValue::Str("show".to_string())),
+            ("time".to_string(), JValue::Num(pu.timecounter as f64)),
+            ("mode".to_string(), JValue::Str(self.mode.clone())),
+            ("teams".to_string(), JValue::Arr(vteams)),
+            ("ball".to_string(), jball),
+            ("players".to_string(), JValue::Arr(vplayers)),
+        ]);
+        jshow.to_string()
+    }
+
+    /// Rebroadcast the given play update to all the connected clients.
+    /// Play updates without any player positions (like those only carrying
+    /// messages) update the internal state, but arent sent as show messages.
+    pub fn send_playupdate(&mut self, pu: &PlayUpdate) {
+        if let Some(mode) = &pu.state {
+            self.mode = mode.to_string();
+        }
+        if pu.teams.len() > 0 {
+            self.teams = pu.teams.clone();
+        }
+        for (k, v) in &pu.params {
+            self.params.insert(k.to_string(), v.to_string());
+        }
+        for (from, msg) in &pu.commsgs {
+            if !from.starts_with("Board") {
+                continue;
+            }
+            let board: f64 = from.trim_start_matches("Board").parse().unwrap_or(1.0);
+            let jmsg = JValue::Obj(vec![
+                ("type".to_string(), JValue::Str("msg".to_string())),
+                ("time".to_string(), JValue::Num(pu.timecounter as f64)),
+                ("board".to_string(), JValue::Num(board)),
+                ("message".to_string(), JValue::Str(msg.to_string())),
+            ]);
+            for client in &self.clients {
+                self.send(client, &jmsg.to_string());
+            }
+        }
+        if (pu.lteamcoded.len() == 0) && (pu.rteamcoded.len() == 0) {
+            return;
+        }
+        if self.clients.len() == 0 {
+            return;
+        }
+        let sshow = self.show(pu);
+        for client in &self.clients {
+            self.send(client, &sshow);
+        }
+    }
+
+}
@@ -20,6 +20,8 @@ const FRAMES_NORMAL_SPR_MULT: f32 = 2.0;
 const FRAMES_INBTW_SPR_MULT: f32 = FRAMES_NORMAL_SPR_MULT*50.0;
 
 struct Team {
+    /// The space within which the players move, in screen like units
+    space: (u32, u32),
     cnt: usize,
     pos: Vec<(f32,f32)>,
     mov: Vec<(f32, f32)>,
@@ -29,8 +31,8 @@ struct Team {
 
 impl Team {
 
-    fn new(cnt: usize) -> Team {
-        let (prgw, prgh) = sdlx::get_prg_resolution();
+    fn new(cnt: usize, space: (u32, u32)) -> Team {
+        let (prgw, prgh) = space;
         let mut pos = Vec::new();
         let mut mov = Vec::new();
         let mut chg = Vec::new();
@@ -42,6 +44,7 @@ impl Team {
             chg.push(1 + (rand::random::<usize>() % 128)*(FRAMES_NORMAL_SPR_MULT as usize));
         }
         Team {
+            space: space,
             cnt: cnt,
             pos: pos,
             mov: mov,
@@ -50,8 +53,8 @@ impl Team {
         }
     }
 
-    fn fpos_fix(mut pos: (f32, f32)) -> (f32, f32) {
-        let (prgw, prgh) = sdlx::get_prg_resolution();
+    fn fpos_fix(&self, mut pos: (f32, f32)) -> (f32, f32) {
+        let (prgw, prgh) = self.space;
         if pos.0 < 0.0 {
             pos.0 = prgw as f32;
         }
@@ -69,7 +72,7 @@ impl Team {
 
     fn pos_fix(&mut self) {
         for i in 0..self.cnt {
-            self.pos[i] = Self::fpos_fix(self.pos[i]);
+            self.pos[i] = self.fpos_fix(self.pos[i]);
         }
     }
 
@@ -123,12 +126,15 @@ impl RandomData {
 
     ///
     /// base_spr: the smallest fraction of a second, at which the logic works internally wrt movemens
+    ///
+    /// The players move within a space of the size of the screen at creation time,
+    /// which is mapped to the normal space, so that it is independent of any resizing later.
     pub fn new(base_spr: f32, lcnt: usize, rcnt: usize) -> RandomData {
         let (prgw, prgh) = sdlx::get_prg_resolution();
         let srect = ((-20.0, -20.0), (prgw as f32 + 20.0, prgh as f32 + 20.0));
         let nrect = ((0.0,0.0), (1.0,1.0));
-        let lteam = Team::new(lcnt);
-        let rteam = Team::new(rcnt);
+        let lteam = Team::new(lcnt, (prgw, prgh));
+        let rteam = Team::new(rcnt, (prgw, prgh));
         RandomData {
             base_spr,
             fpr: 0.0,
@@ -214,12 +220,17 @@ impl PlayData for RandomData {
         return;
     }
 
+    fn seek_to(&mut self, time: usize) {
+        // next_record increments it, before use
+        self.reccnt = time.saturating_sub(1);
+    }
+
     fn bdone(&self) -> bool {
         return false;
     }
 
-    fn send_record(&mut self, _buf: &[u8]) {
-        todo!()
+    fn send_record(&mut self, buf: &[u8]) {
+        eprintln!("WARN:GPPGND:PlayDataRandom:ignoring request for send record [{}]", String::from_utf8_lossy(buf));
     }
 
     fn send_record_coded(&mut self, code: isize) {
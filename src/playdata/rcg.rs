@@ -9,8 +9,8 @@ use tokensk::TStr;
 
 use crate::entities;
 use crate::playdata;
-use crate::playdata::GameState;
 use crate::playdata::rcss;
+use crate::playdata::rcl::Rcl;
 use crate::playdata::PlayUpdate;
 use crate::playdata::PlayData;
 use crate::playdata::PlayerData;
@@ -29,7 +29,9 @@ pub struct Rcg {
     secondsper_record: f32,
     secondsafter_lastrecord: f32,
     secondsperframe: f32,
-    r2d: XSpaces,
+    parser: RcgParser,
+    /// The optional companion rcl, which provides the player commands.
+    rcl: Option<Rcl>,
 }
 
 impl Rcg {
@@ -43,8 +45,6 @@ impl Rcg {
         for line in vdata {
             vline.push(line.to_string());
         }
-        let rrect = ((-55.0, -37.0), (55.0, 37.0));
-        let drect = ((0.0,0.0), (1.0,1.0));
         Rcg {
             _fname: fname.to_string(),
             _file: file,
@@ -54,15 +54,53 @@ impl Rcg {
             secondsper_record: rcss::SECONDS_PER_RECORD,
             secondsafter_lastrecord: 0.0,
             secondsperframe: 1.0/fps,
-            r2d: XSpaces::new(rrect, drect)
+            parser: RcgParser::new(),
+            rcl: None,
         }
     }
 
+    /// Attach a rcl, so that the player commands wrt each cycle
+    /// get added to the corresponding play updates.
+    pub fn set_rcl(&mut self, rcl: Rcl) {
+        self.rcl = Some(rcl);
+    }
+
 }
 
-impl Rcg {
+#[derive(Debug, PartialEq)]
+/// The type of record parsed from a rcg line.
+pub enum RcgRecord {
+    /// Empty, comment or header lines.
+    Skip,
+    Show,
+    PlayMode,
+    /// Left and Right team names
+    Team(String, String),
+    Msg,
+    Params,
+    Unknown,
+    /// A malformed record, along with what was wrong with it
+    Bad(String),
+}
+
+/// Parse the text lines of a rcg file. The same records are sent by the
+/// rcssserver to the monitors, wrt monitor protocol versions 3 and 4, so
+/// this is shared by the Rcg and RCLive playdata sources.
+pub struct RcgParser {
+    r2d: XSpaces,
+}
 
-    fn handle_ball(&mut self, vdata: &Vec<String>, pu: &mut PlayUpdate) {
+impl RcgParser {
+
+    pub fn new() -> RcgParser {
+        let rrect = rcss::RCSS_RECT;
+        let drect = ((0.0,0.0), (1.0,1.0));
+        RcgParser {
+            r2d: XSpaces::new(rrect, drect),
+        }
+    }
+
+    fn handle_ball(&self, vdata: &Vec<String>, pu: &mut PlayUpdate) {
         let fxin: f32 = vdata[1].parse().unwrap();
         let fyin: f32 = vdata[2].parse().unwrap();
         let fx = self.r2d.d2ox(fxin);
@@ -73,7 +111,7 @@ impl Rcg {
         pu.ball = (fx, fy);
     }
 
-    fn handle_player(&mut self, vdata: &Vec<String>, pu: &mut PlayUpdate) {
+    fn handle_player(&self, vdata: &Vec<String>, pu: &mut PlayUpdate) {
         let mut pd = VPlayerData::new();
         // Handle team and player id
         let mut tstr = TStr::from_str(&vdata[0], true);
@@ -93,6 +131,9 @@ impl Rcg {
         }
         pd.push(PlayerData::Card(card));
         pd.push(PlayerData::Action(action));
+        if rcss::is_goalie(state) {
+            pd.push(PlayerData::Goalie);
+        }
         // Handle position
         let fxin: f32 = vdata[3].parse().unwrap();
         let fyin: f32 = vdata[4].parse().unwrap();
@@ -133,6 +174,145 @@ impl Rcg {
         }
     }
 
+    /// Handle (msg time board "message") records, by adding them to the
+    /// communication messages in the play update.
+    /// The team graphics related messages are skipped.
+    fn handle_msg(&self, line: &str, pu: &mut PlayUpdate) {
+        let toks = rcss::sexp_tokens(line);
+        if toks.len() < 4 {
+            ldebug!(&format!("DBUG:{}:Msg:Unknown:{}", MTAG, line));
+            return;
+        }
+        let msg = toks[3..].join(" ");
+        let msg = msg.trim_matches('"');
+        if msg.starts_with("(team_graphic") {
+            return;
+        }
+        pu.commsgs.push((format!("Board{}", toks[2]), msg.to_string()));
+    }
+
+    /// Handle (server_param (name value)...), (player_param ...) and
+    /// (player_type (id N) ...) records, by adding them to the params
+    /// in the play update, in the same way as wrt RCLive json messages.
+    fn handle_params(&self, line: &str, pu: &mut PlayUpdate) -> Result<(), String> {
+        let toks = rcss::sexp_tokens(line);
+        if toks.len() == 0 {
+            return Err("Params:Empty".to_string());
+        }
+        let mut vparams = Vec::new();
+        for tok in toks.iter().skip(1) {
+            let ptoks = rcss::sexp_tokens(tok);
+            if ptoks.len() < 2 {
+                continue;
+            }
+            vparams.push((ptoks[0].clone(), ptoks[1..].join(" ").trim_matches('"').to_string()));
+        }
+        let mut group = toks[0].clone();
+        for (k, v) in &vparams {
+            if (group == "player_type") && (k == "id") {
+                group = format!("{}.{}", group, v);
+            }
+        }
+        for (k, v) in vparams {
+            pu.params.insert(format!("{}.{}", group, k), v);
+        }
+        Ok(())
+    }
+
+    /// The time wrt the given line, if it is a show record
+    pub fn show_time(line: &str) -> Option<usize> {
+        let line = line.trim_start();
+        if !line.starts_with("(show ") {
+            return None;
+        }
+        return line["(show ".len()..].split(' ').next()?.parse().ok();
+    }
+
+    /// Log and return the given malformed line as a Bad record
+    fn bad(fmtag: &str, line: &str, what: &str) -> RcgRecord {
+        eprintln!("WARN:{}:Bad:{}:{}", fmtag, what, line);
+        return RcgRecord::Bad(what.to_string());
+    }
+
+    /// Parse the given line, updating the play update as required,
+    /// and return the type of record it was.
+    pub fn parse_line(&self, line: &str, pu: &mut PlayUpdate) -> RcgRecord {
+        let fmtag: String = format!("{}:ParseLine", MTAG);
+        let mut tstr = TStr::from_str(line, true);
+        if tstr.len() == 0 {
+            return RcgRecord::Skip;
+        }
+        if tstr.char_first().unwrap() == '#' {
+            return RcgRecord::Skip;
+        }
+        if tstr.the_str().starts_with("ULG") {
+            return RcgRecord::Skip;
+        }
+        if tstr.peel_bracket('(').is_err() {
+            print!("DBUG:{}:Skipping:NotARecord:{}\n", fmtag, line);
+            return RcgRecord::Unknown;
+        }
+        let toks = match tstr.tokens_vec(' ', true, true) {
+            Ok(toks) if toks.len() > 0 => toks,
+            _ => return Self::bad(&fmtag, line, "NoTokens"),
+        };
+        ldebug!(&format!("DBUG:{}:Toks:Top:Full:{:?}", fmtag, toks));
+        if toks[0].ends_with("_param") || (toks[0] == "player_type") {
+            if let Err(err) = self.handle_params(line, pu) {
+                return Self::bad(&fmtag, line, &err);
+            }
+            return RcgRecord::Params;
+        }
+        if toks.len() > 1 {
+            pu.msgs.insert("stime".to_string(), toks[1].to_string());
+        }
+        if toks[0].starts_with("show") {
+            pu.timecounter = match toks.get(1).map(|stime| stime.parse()) {
+                Some(Ok(time)) => time,
+                _ => return Self::bad(&fmtag, line, "Show:Time"),
+            };
+            for tok in toks {
+                if !tok.starts_with("((l") && !tok.starts_with("((r") && !tok.starts_with("((b") {
+                    continue;
+                }
+                let mut tstr = TStr::from_str(&tok, true);
+                tstr.peel_bracket('(').unwrap();
+                let vdata = tstr.tokens_vec(' ', true, true).unwrap();
+                ldebug!(&format!("DBUG:{}:Toks:Full:{:?}", fmtag, vdata));
+                if vdata[0].starts_with("(b") {
+                    self.handle_ball(&vdata, pu);
+                } else {
+                    self.handle_player(&vdata, pu);
+                }
+            }
+            return RcgRecord::Show;
+        } else if toks[0].starts_with("playmode") {
+            let smode = match toks.get(2) {
+                Some(smode) => smode,
+                None => return Self::bad(&fmtag, line, "PlayMode:Missing"),
+            };
+            pu.state = Some(rcss::PlayMode::parse(smode));
+            pu.msgs.insert("game".to_string(), line.to_string());
+            return RcgRecord::PlayMode;
+        } else if toks[0].starts_with("team") {
+            pu.msgs.insert("score".to_string(), line.to_string());
+            if toks.len() > 5 {
+                pu.teams.push((entities::SIDE_L, toks[2].clone(), toks[4].parse().unwrap_or(0)));
+                pu.teams.push((entities::SIDE_R, toks[3].clone(), toks[5].parse().unwrap_or(0)));
+            }
+            if toks.len() > 3 {
+                return RcgRecord::Team(toks[2].clone(), toks[3].clone());
+            }
+            return RcgRecord::Team(String::new(), String::new());
+        } else if toks[0].starts_with("msg") {
+            self.handle_msg(line, pu);
+            return RcgRecord::Msg;
+        }
+        pu.msgs.insert("unknown".to_string(), line.to_string());
+        print!("DBUG:{}:Skipping:{:?}\n", fmtag, toks);
+        return RcgRecord::Unknown;
+    }
+
 }
 
 impl PlayData for Rcg {
@@ -165,53 +345,20 @@ impl PlayData for Rcg {
                 self.bdone = true;
                 break;
             }
-            let mut tstr = TStr::from_str(&self.lines[self.iline as usize], true);
-            if tstr.len() == 0 {
-                continue;
-            }
-            if tstr.char_first().unwrap() == '#' {
-                continue;
-            }
-            if tstr.the_str().starts_with("ULG") {
-                continue;
-            }
-            tstr.peel_bracket('(').unwrap();
-            let toks = tstr.tokens_vec(' ', true, true).unwrap();
-            ldebug!(&format!("DBUG:{}:Toks:Top:Full:{:?}", fmtag, toks));
-            pu.msgs.insert("stime".to_string(), toks[1].to_string());
-            if toks[0].starts_with("show") {
-                pu.timecounter = toks[1].parse().unwrap();
-                for tok in toks {
-                    if !tok.starts_with("((l") && !tok.starts_with("((r") && !tok.starts_with("((b") {
-                        continue;
+            let rtype = self.parser.parse_line(&self.lines[self.iline as usize], &mut pu);
+            match rtype {
+                RcgRecord::Show => {
+                    if self.rcl.is_some() {
+                        self.rcl.as_mut().unwrap().fill_playupdate(&mut pu);
                     }
-                    let mut tstr = TStr::from_str(&tok, true);
-                    tstr.peel_bracket('(').unwrap();
-                    let vdata = tstr.tokens_vec(' ', true, true).unwrap();
-                    ldebug!(&format!("DBUG:{}:Toks:Full:{:?}", fmtag, vdata));
-                    if vdata[0].starts_with("(b") {
-                        self.handle_ball(&vdata, &mut pu);
-                    } else {
-                        self.handle_player(&vdata, &mut pu);
+                    break;
+                },
+                RcgRecord::Team(lteam, rteam) => {
+                    if self.rcl.is_some() && (lteam.len() > 0) {
+                        self.rcl.as_mut().unwrap().set_teams(&lteam, &rteam);
                     }
-                }
-                break;
-            } else if toks[0].starts_with("playmode") {
-                if toks[2] == "goal_r" {
-                    pu.state = GameState::Goal(entities::SIDE_R);
-                } else if toks[2] == "goal_l" {
-                    pu.state = GameState::Goal(entities::SIDE_L);
-                } else if toks[2] == "play_on" {
-                    pu.state = GameState::PlayOn;
-                } else {
-                    pu.state = GameState::Other(toks[2].clone());
-                }
-                pu.msgs.insert("game".to_string(), self.lines[self.iline as usize].clone());
-            } else if toks[0].starts_with("team") {
-                pu.msgs.insert("score".to_string(), self.lines[self.iline as usize].clone());
-            } else {
-                pu.msgs.insert("unknown".to_string(), self.lines[self.iline as usize].clone());
-                print!("DBUG:{}:Skipping:{:?}\n", fmtag, toks);
+                },
+                _ => (),
             }
         }
         return pu;
@@ -229,12 +376,45 @@ impl PlayData for Rcg {
         }
     }
 
+    /// Scan back from the current line, till a show record before the given
+    /// time, and then forward till the show record at or after the given time.
+    /// The records between the previous show record and it are also replayed,
+    /// so that the play mode, team, ... updates before it are not missed.
+    fn seek_to(&mut self, time: usize) {
+        if self.lines.len() == 0 {
+            return;
+        }
+        let mut i = self.iline.max(0).min(self.lines.len() as isize - 1) as usize;
+        while i > 0 {
+            match RcgParser::show_time(&self.lines[i]) {
+                Some(stime) if stime < time => break,
+                _ => i -= 1,
+            }
+        }
+        let mut iprev: isize = -1;
+        while i < self.lines.len() {
+            match RcgParser::show_time(&self.lines[i]) {
+                Some(stime) if stime >= time => break,
+                Some(_) => iprev = i as isize,
+                None => (),
+            }
+            i += 1;
+        }
+        if i >= self.lines.len() {
+            eprintln!("WARN:{}:SeekTo:{}:beyond the last record", MTAG, time);
+            self.iline = (self.lines.len() - 1) as isize;
+            return;
+        }
+        self.iline = iprev;
+        self.bdone = false;
+    }
+
     fn bdone(&self) -> bool {
         return self.bdone;
     }
 
-    fn send_record(&mut self, _buf: &[u8]) {
-        todo!()
+    fn send_record(&mut self, buf: &[u8]) {
+        eprintln!("WARN:{}:SendRecord:ignoring request for send record [{}], as playing back a log", MTAG, String::from_utf8_lossy(buf));
     }
 
     fn send_record_coded(&mut self, code: isize) {
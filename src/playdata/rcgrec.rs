@@ -0,0 +1,220 @@
+//!
+//! Record the messages got from a live robocup soccer sim server,
+//! into a rcg (version 5) text log file.
+//! HanishKVC, 2022
+//!
+//! The s-expression based monitor messages (version 3 and 4) are same as
+//! the rcg records, so they are written as is. The json based monitor
+//! messages (version 5) are converted into the corresponding rcg records.
+//!
+//! Like the rcssserver, playmode and team records are written only when
+//! they change, while show records are written wrt each message.
+//!
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+use loggerk::{ldebug, log_d};
+
+use super::json::JValue;
+
+
+const MTAG: &str = "GPPGND:PlayDataRcgRec";
+
+const RCG_HEADER: &str = "ULG5";
+
+
+pub struct RcgRecorder {
+    fname: String,
+    writer: BufWriter<File>,
+    /// The last playmode and team records, help avoid duplicates.
+    lastmode: String,
+    lastteams: String,
+}
+
+impl RcgRecorder {
+
+    pub fn new(fname: &str) -> RcgRecorder {
+        let file = File::create(fname).unwrap();
+        let mut rec = RcgRecorder {
+            fname: fname.to_string(),
+            writer: BufWriter::new(file),
+            lastmode: String::new(),
+            lastteams: String::new(),
+        };
+        rec.write_line(RCG_HEADER);
+        eprintln!("INFO:{}:New:{}", MTAG, fname);
+        rec
+    }
+
+    fn write_line(&mut self, line: &str) {
+        if let Err(err) = writeln!(self.writer, "{}", line) {
+            eprintln!("WARN:{}:Write:{}:{}", MTAG, self.fname, err);
+        }
+    }
+
+    /// Record a s-expression based monitor message (ie a rcg record).
+    /// Like the json messages, playmode and team records are written
+    /// only when they change.
+    pub fn record_line(&mut self, line: &str) {
+        if line.starts_with("(playmode ") || line.starts_with("(team ") {
+            // skip the time, when checking for change
+            let skey = line.splitn(3, ' ').nth(2).unwrap_or("").to_string();
+            if line.starts_with("(playmode ") {
+                if skey == self.lastmode {
+                    return;
+                }
+                self.lastmode = skey;
+            } else {
+                if skey == self.lastteams {
+                    return;
+                }
+                self.lastteams = skey;
+            }
+        }
+        self.write_line(line);
+        if line.starts_with("(show ") {
+            self.flush();
+        }
+    }
+
+    /// Record a json based monitor message, by converting it into the
+    /// corresponding rcg record(s). Messages which dont have a rcg
+    /// equivalent (like team_graphic) are skipped.
+    pub fn record_json(&mut self, jmsg: &JValue) {
+        let stype = jmsg.get_string("type");
+        match stype.as_str() {
+            "show" => {
+                let time = num(jmsg, "time");
+                let smode = jmsg.get_string("mode");
+                if smode.len() > 0 {
+                    self.record_line(&format!("(playmode {} {})", time, smode));
+                }
+                if let Some(steams) = teams(jmsg) {
+                    self.record_line(&format!("(team {} {})", time, steams));
+                }
+                self.record_line(&show(jmsg));
+            },
+            "server_param" | "player_param" | "player_type" => {
+                self.record_line(&params(jmsg, &stype));
+            },
+            "msg" => {
+                let smsg = format!("(msg {} {} {:?})", num(jmsg, "time"), num(jmsg, "board"), jmsg.get_string("message"));
+                self.record_line(&smsg);
+            },
+            _ => {
+                ldebug!(&format!("DBUG:{}:RecordJson:Skipping:{}", MTAG, stype));
+            }
+        }
+    }
+
+    pub fn flush(&mut self) {
+        if let Err(err) = self.writer.flush() {
+            eprintln!("WARN:{}:Flush:{}:{}", MTAG, self.fname, err);
+        }
+    }
+
+}
+
+
+/// Get the given member as a string, 0 if missing.
+fn num(jv: &JValue, key: &str) -> String {
+    match jv.get(key) {
+        Some(v) => v.to_simple_string(),
+        None => "0".to_string(),
+    }
+}
+
+/// Get the lname rname lscore rscore [lpen_score lpen_miss rpen_score rpen_miss]
+/// part of the team record.
+fn teams(jshow: &JValue) -> Option<String> {
+    let jteams = jshow.get("teams")?.as_arr()?;
+    if jteams.len() < 2 {
+        return None;
+    }
+    let (mut jl, mut jr) = (&jteams[0], &jteams[1]);
+    if jl.get_string("side") == "r" {
+        (jl, jr) = (jr, jl);
+    }
+    let name = |jt: &JValue| -> String {
+        let name = jt.get_string("name");
+        if name.len() == 0 {
+            return "null".to_string();
+        }
+        name
+    };
+    let mut steams = format!("{} {} {} {}", name(jl), name(jr), num(jl, "score"), num(jr, "score"));
+    if jl.get("pen_score").is_some() || jr.get("pen_score").is_some() {
+        steams = format!("{} {} {} {} {}", steams, num(jl, "pen_score"), num(jl, "pen_miss"), num(jr, "pen_score"), num(jr, "pen_miss"));
+        if steams.ends_with(" 0 0 0 0") {
+            steams.truncate(steams.len() - 8);
+        }
+    }
+    Some(steams)
+}
+
+/// Convert the show message into
+/// (show time ((b) x y vx vy) ((side unum) type state x y vx vy body neck (v vq vw) (s stamina effort recovery capacity) [(f side unum)] [(c counts)]) ...)
+fn show(jshow: &JValue) -> String {
+    let mut sshow = format!("(show {}", num(jshow, "time"));
+    if let Some(jball) = jshow.get("ball") {
+        sshow.push_str(&format!(" ((b) {} {} {} {})", num(jball, "x"), num(jball, "y"), num(jball, "vx"), num(jball, "vy")));
+    }
+    let empty = Vec::new();
+    let jplayers = jshow.get("players").and_then(|v| v.as_arr()).unwrap_or(&empty);
+    for jp in jplayers {
+        let state = jp.get_f32("state", 0.0) as u32;
+        sshow.push_str(&format!(" (({} {}) {} 0x{:x} {} {} {} {} {} {}",
+            jp.get_string("side"), num(jp, "unum"), num(jp, "type"), state,
+            num(jp, "x"), num(jp, "y"), num(jp, "vx"), num(jp, "vy"), num(jp, "body"), num(jp, "neck")));
+        let mut vq = jp.get_string("vq");
+        if vq.len() == 0 {
+            vq = "h".to_string();
+        }
+        sshow.push_str(&format!(" (v {} {})", vq, num(jp, "vw")));
+        // stamina can be a simple value or a object containing the values
+        let sstamina = match jp.get("stamina") {
+            Some(js) if js.get("v").is_some() => format!("{} {} {} {}", num(js, "v"), num(js, "e"), num(js, "r"), num(js, "c")),
+            _ => format!("{} {} {} {}", num(jp, "stamina"), num(jp, "effort"), num(jp, "recovery"), num(jp, "capacity")),
+        };
+        sshow.push_str(&format!(" (s {})", sstamina));
+        if let Some(jf) = jp.get("focus") {
+            if jf.get("side").is_some() {
+                sshow.push_str(&format!(" (f {} {})", jf.get_string("side"), num(jf, "unum")));
+            }
+        }
+        if let Some(JValue::Obj(counts)) = jp.get("count") {
+            let vcounts: Vec<String> = counts.iter().map(|c| c.1.to_simple_string()).collect();
+            sshow.push_str(&format!(" (c {})", vcounts.join(" ")));
+        }
+        sshow.push(')');
+    }
+    sshow.push(')');
+    sshow
+}
+
+/// Convert the params message into (server_param (name value)...) or
+/// (player_param ...) or (player_type (id N) ...). String values are quoted.
+fn params(jmsg: &JValue, stype: &str) -> String {
+    let mut sparams = format!("({}", stype);
+    let jparams = jmsg.get("params").unwrap_or(jmsg);
+    if jmsg.get("params").is_some() {
+        if let Some(id) = jmsg.get("id") {
+            sparams.push_str(&format!(" (id {})", id.to_simple_string()));
+        }
+    }
+    if let JValue::Obj(members) = jparams {
+        for (k, v) in members {
+            if (k == "type") || (k == "params") {
+                continue;
+            }
+            match v {
+                JValue::Str(s) => sparams.push_str(&format!(" ({} {:?})", k, s)),
+                JValue::Bool(b) => sparams.push_str(&format!(" ({} {})", k, if *b { 1 } else { 0 })),
+                _ => sparams.push_str(&format!(" ({} {})", k, v.to_simple_string())),
+            }
+        }
+    }
+    sparams.push(')');
+    sparams
+}
@@ -0,0 +1,170 @@
+//!
+//! Process robocup soccer simulator rcl (player commands) log file
+//! HanishKVC, 2022
+//!
+//! The rcl file contains the commands sent by the agents to the server,
+//! wrt each cycle, like
+//!
+//!   time,stime<TAB>Recv TeamName_UNum: (cmd args)(cmd args)...
+//!   time,stime<TAB>Recv TeamName_Coach: (say ...)
+//!   time,stime<TAB>(referee playmode)
+//!
+//! It is not a playdata source by itself, rather it acts as a companion
+//! to the Rcg playdata source, where the commands wrt a given cycle are
+//! attached to the corresponding players in the PlayUpdate of that cycle.
+//!
+
+use loggerk::{ldebug, log_d};
+
+use crate::playdata::{rcss, PlayUpdate, PlayerCmd, PlayerData};
+
+
+const MTAG: &str = "GPPGND:PlayDataRcl";
+
+/// The player id used wrt team coach in the rcl file.
+pub const RCL_COACHID: &str = "Coach";
+
+
+#[derive(Debug, Clone)]
+/// A command sent by a player (or coach) wrt a given cycle.
+pub struct RclEntry {
+    pub time: usize,
+    pub team: String,
+    pub playerid: String,
+    pub cmd: PlayerCmd,
+}
+
+pub struct Rcl {
+    _fname: String,
+    /// The commands in the same order as in the file (ie wrt time)
+    entries: Vec<RclEntry>,
+    /// Index of the next entry to check
+    ientry: usize,
+    /// The time wrt which entries were last requested
+    lasttime: usize,
+    /// Team names wrt left and right sides, as got from the rcg.
+    lteam: String,
+    rteam: String,
+}
+
+impl Rcl {
+
+    pub fn new(fname: &str) -> Rcl {
+        let sdata = std::fs::read_to_string(fname).unwrap();
+        let mut entries = Vec::new();
+        for line in sdata.lines() {
+            entries.append(&mut Self::parse_line(line));
+        }
+        eprintln!("INFO:{}:New:{}:Entries:{}", MTAG, fname, entries.len());
+        Rcl {
+            _fname: fname.to_string(),
+            entries: entries,
+            ientry: 0,
+            lasttime: 0,
+            lteam: String::new(),
+            rteam: String::new(),
+        }
+    }
+
+    /// Parse a rcl line into the commands it contains.
+    /// Lines which dont relate to player/coach commands (like referee) are skipped.
+    pub fn parse_line(line: &str) -> Vec<RclEntry> {
+        let mut ventries = Vec::new();
+        let line = line.trim();
+        let (stimes, sdata) = match line.split_once('\t') {
+            Some(parts) => parts,
+            None => return ventries,
+        };
+        let stime = stimes.split(',').next().unwrap();
+        let time: usize = match stime.trim().parse() {
+            Ok(time) => time,
+            Err(_) => return ventries,
+        };
+        let sdata = match sdata.strip_prefix("Recv ") {
+            Some(sdata) => sdata,
+            None => return ventries,
+        };
+        let (sfrom, scmds) = match sdata.split_once(": ") {
+            Some(parts) => parts,
+            None => return ventries,
+        };
+        let (steam, splayer) = match sfrom.rsplit_once('_') {
+            Some(parts) => parts,
+            None => {
+                ldebug!(&format!("DBUG:{}:ParseLine:UnknownSender:{}", MTAG, sfrom));
+                return ventries;
+            }
+        };
+        for block in rcss::sexp_blocks(scmds) {
+            ventries.push(RclEntry {
+                time: time,
+                team: steam.to_string(),
+                playerid: splayer.to_string(),
+                cmd: rcss::handle_playercmd(&block),
+            });
+        }
+        ventries
+    }
+
+    /// Set the team names wrt the left and right sides.
+    pub fn set_teams(&mut self, lteam: &str, rteam: &str) {
+        self.lteam = lteam.to_string();
+        self.rteam = rteam.to_string();
+    }
+
+    /// Return the entries wrt the given time.
+    ///
+    /// Entries are returned only once, so if the same time is requested again
+    /// (like during stoppage), nothing more is returned. Entries older than the
+    /// given time are skipped (like after a seek forward). If a older time is
+    /// requested (like after a seek backward), search again from the begining.
+    pub fn next_entries(&mut self, time: usize) -> Vec<RclEntry> {
+        if time < self.lasttime {
+            self.ientry = self.entries.partition_point(|entry| entry.time < time);
+        }
+        self.lasttime = time;
+        while (self.ientry < self.entries.len()) && (self.entries[self.ientry].time < time) {
+            self.ientry += 1;
+        }
+        let mut ventries = Vec::new();
+        while (self.ientry < self.entries.len()) && (self.entries[self.ientry].time == time) {
+            ventries.push(self.entries[self.ientry].clone());
+            self.ientry += 1;
+        }
+        ventries
+    }
+
+    /// Attach the player commands wrt the time of the playupdate, to the
+    /// corresponding players in the playupdate.
+    pub fn fill_playupdate(&mut self, pu: &mut PlayUpdate) {
+        for entry in self.next_entries(pu.timecounter) {
+            if entry.playerid == RCL_COACHID {
+                if let PlayerCmd::Say(msg) = entry.cmd {
+                    pu.commsgs.push((format!("{}:{}", entry.team, RCL_COACHID), msg));
+                } else {
+                    ldebug!(&format!("DBUG:{}:FillPU:Coach:{}:{:?}", MTAG, entry.team, entry.cmd));
+                }
+                continue;
+            }
+            let teamcoded;
+            if entry.team == self.lteam {
+                teamcoded = &mut pu.lteamcoded;
+            } else if entry.team == self.rteam {
+                teamcoded = &mut pu.rteamcoded;
+            } else {
+                ldebug!(&format!("DBUG:{}:FillPU:UnknownTeam:{}:{}-{}", MTAG, entry.team, self.lteam, self.rteam));
+                continue;
+            }
+            // Only players with a position in the playupdate are updated,
+            // so that the rest of the flow doesnt see a player without position.
+            let player = teamcoded.iter_mut().find(|pcd| pcd.0 == entry.playerid);
+            match player {
+                Some(player) => player.1.push(PlayerData::Cmd(entry.cmd)),
+                None => {
+                    ldebug!(&format!("DBUG:{}:FillPU:PlayerNotInUpdate:{}:{}", MTAG, entry.team, entry.playerid));
+                }
+            }
+        }
+    }
+
+}
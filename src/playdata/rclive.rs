@@ -6,21 +6,31 @@
 use std::net::UdpSocket;
 use std::time;
 
-use tokensk::TStrX;
 use loggerk::{ldebug,log_d};
 
 use crate::sdlx::XSpaces;
 
 use crate::{playdata, entities};
-use super::{rcss, GameState};
+use super::{rcss, json};
+use super::json::JValue;
+use super::rcg::{RcgParser, RcgRecord};
+use super::rcgrec::RcgRecorder;
 use super::{PlayData, PlayUpdate, PlayerData};
 
 
 const MTAG: &str = "GPPGND:PlayDataRCLive";
 
 pub const NWADDR_DEFAULT: &str = "0.0.0.0:6000";
-const OWN_ADDRESS: &str = "0.0.0.0:6600";
+/// The local port used by default. 0 means a ephemeral port.
+pub const OWN_PORT_DEFAULT: u16 = 6600;
 const READ_TIMEOUT_MS: u64 = 500;
+/// How often the initial handshake is resent, till the server replies.
+const HANDSHAKE_RETRY_MS: u128 = 1000;
+/// If nothing is heard from the server for this long, assume it is gone.
+const SERVER_TIMEOUT_MS: u128 = 3000;
+/// The monitor protocol version used by default.
+/// Version 5 is json based, while versions 3 and 4 are s-expression based.
+pub const MONITOR_VERSION_DEFAULT: usize = 5;
 
 
 /// Help act as a simple monitor client for RoboCup Sim
@@ -28,109 +38,195 @@ pub struct RCLive {
     skt: UdpSocket,
     /// The robocup server address to communicate to.
     srvraddr: String,
-    /// Help tokenise recieved data.
-    tstrx: TStrX,
+    /// The server address specified by the user, used for handshake.
+    srvraddr_init: String,
     /// Help convert from Robocups pitch space to normal space.
     r2n: XSpaces,
+    /// The monitor protocol version requested from the server.
+    version: usize,
+    /// Help parse the s-expression based messages (version 3 and 4).
+    rcgparser: RcgParser,
     /// Track whether the server addr has been updated to
     /// the one over which server sent data to the monitor.
     /// ie after the initial handshake.
     bsrvraddr_updated: bool,
+    /// When the initial handshake was last sent and how many times.
+    inittime: time::Instant,
+    initcnt: usize,
+    /// When the last message was got from the server.
+    recvtime: time::Instant,
+    /// The connection status, as shown to the user.
+    status: String,
     /// Time wrt last message seen from server
     stime: String,
+    /// Time wrt last show message, helps detect server restarts.
+    lasttime: usize,
     /// Team name and Score
     ateam: String,
     bteam: String,
+    /// The optional recorder, to save the session into a rcg file.
+    recorder: Option<RcgRecorder>,
 }
 
 impl RCLive {
 
-    pub fn new(addr: &str) -> RCLive {
-        let skt = UdpSocket::bind(OWN_ADDRESS).unwrap();
+    /// Create a monitor client, which binds to the given local port
+    /// (0 for a ephemeral port) and sends the initial handshake to the
+    /// server at the given address. If the given port cant be bound to,
+    /// a ephemeral port is used instead.
+    pub fn new(addr: &str, version: usize, port: u16) -> RCLive {
+        let mut version = version;
+        if (version < 3) || (version > 5) {
+            eprintln!("WARN:{}:New:Unsupported monitor version {}, using {}", MTAG, version, MONITOR_VERSION_DEFAULT);
+            version = MONITOR_VERSION_DEFAULT;
+        }
+        let skt = match UdpSocket::bind(("0.0.0.0", port)) {
+            Ok(skt) => skt,
+            Err(err) => {
+                eprintln!("WARN:{}:New:Failed binding to port {}:{}, using a ephemeral port", MTAG, port, err);
+                UdpSocket::bind(("0.0.0.0", 0)).unwrap()
+            }
+        };
         skt.set_read_timeout(Some(time::Duration::from_millis(READ_TIMEOUT_MS))).unwrap();
-        let sinit = "(dispinit version 5)\r\n";
-        skt.send_to(sinit.as_bytes(), addr).unwrap();
+        let sinit = Self::dispinit(version);
+        if let Err(err) = skt.send_to(sinit.as_bytes(), addr) {
+            eprintln!("WARN:{}:New:Failed sending handshake to {}:{}", MTAG, addr, err);
+        }
         eprintln!("DBUG:{}:New:{:?}", MTAG, skt);
-        let rrect = ((-55.0, -37.0), (55.0, 37.0));
+        let rrect = rcss::RCSS_RECT;
         let nrect = ((0.0,0.0), (1.0,1.0));
-        let mut tstrx = TStrX::new();
-        tstrx.flags.string_canbe_asubpart = true;
-        tstrx.flags.blocktok_dlimuser_endreqd = false;
-        tstrx.delims.bracket = ('{','}');
-        tstrx.delims.obracket = Some(('[',']'));
-        tstrx.delims.string = '"';
         RCLive {
             skt: skt,
             srvraddr: addr.to_string(),
-            tstrx: tstrx,
+            srvraddr_init: addr.to_string(),
             r2n: XSpaces::new(rrect, nrect),
+            version: version,
+            rcgparser: RcgParser::new(),
             bsrvraddr_updated: false,
+            inittime: time::Instant::now(),
+            initcnt: 1,
+            recvtime: time::Instant::now(),
+            status: String::new(),
             stime: String::new(),
+            lasttime: 0,
             ateam: String::new(),
             bteam: String::new(),
+            recorder: None,
+        }
+    }
+
+    /// The server address currently used for communicating.
+    #[allow(dead_code)]
+    pub fn srvraddr(&self) -> &str {
+        &self.srvraddr
+    }
+
+    /// Attach a recorder, so that the messages got from the server
+    /// get saved into a rcg file.
+    pub fn set_recorder(&mut self, recorder: RcgRecorder) {
+        self.recorder = Some(recorder);
+    }
+
+    /// The initial handshake wrt the given monitor protocol version.
+    fn dispinit(version: usize) -> String {
+        format!("(dispinit version {})\r\n", version)
+    }
+
+    /// Update the connection status, if it has changed.
+    /// The status is passed to the user thro the status message.
+    fn set_status(&mut self, status: &str, pu: &mut PlayUpdate) {
+        if self.status == status {
+            return;
+        }
+        self.status = status.to_string();
+        eprintln!("INFO:{}:Status:{}", MTAG, status);
+        pu.msgs.insert("status".to_string(), format!("RCLive:{}", status));
+    }
+
+    /// Resend the initial handshake periodically, till the server replies.
+    fn handshake_retry(&mut self, pu: &mut PlayUpdate) {
+        if self.bsrvraddr_updated {
+            return;
+        }
+        if self.inittime.elapsed().as_millis() < HANDSHAKE_RETRY_MS {
+            return;
+        }
+        self.inittime = time::Instant::now();
+        self.initcnt += 1;
+        let sinit = Self::dispinit(self.version);
+        if let Err(err) = self.skt.send_to(sinit.as_bytes(), &self.srvraddr) {
+            ldebug!(&format!("DBUG:{}:HandshakeRetry:{}:{}", MTAG, self.srvraddr, err));
+        }
+        let status = format!("Connecting:{}:{}", self.srvraddr, self.initcnt);
+        self.set_status(&status, pu);
+    }
+
+    /// If the server has gone quiet, switch back to the user specified
+    /// server address and restart the handshake, so that a restarted
+    /// server can be connected to.
+    fn check_timeout(&mut self, pu: &mut PlayUpdate) {
+        if !self.bsrvraddr_updated {
+            return;
         }
+        if self.recvtime.elapsed().as_millis() < SERVER_TIMEOUT_MS {
+            return;
+        }
+        self.bsrvraddr_updated = false;
+        self.srvraddr = self.srvraddr_init.clone();
+        self.initcnt = 0;
+        self.set_status("NoData", pu);
+    }
+
+    /// If time goes backwards, the server would have been restarted.
+    fn check_restart(&mut self, time: usize, pu: &mut PlayUpdate) {
+        if time < self.lasttime {
+            eprintln!("INFO:{}:ServerRestarted:Time:{}->{}", MTAG, self.lasttime, time);
+            self.ateam.clear();
+            self.bteam.clear();
+            self.status = "Restarted".to_string();
+            pu.msgs.insert("status".to_string(), "RCLive:Connected:ServerRestarted".to_string());
+        }
+        self.lasttime = time;
     }
 
 }
 
 impl RCLive {
 
-    fn handle_time(&mut self, tok: &str, pu: &mut PlayUpdate) {
-        let (_,d) = tok.split_once(':').unwrap();
-        pu.timecounter = d.parse().unwrap();
-        pu.msgs.insert("stime".to_string(), d.to_string());
-        self.stime = d.to_string();
+    fn handle_time(&mut self, jshow: &JValue, pu: &mut PlayUpdate) {
+        let time = jshow.get_f32("time", 0.0) as usize;
+        pu.timecounter = time;
+        pu.msgs.insert("stime".to_string(), time.to_string());
+        self.stime = time.to_string();
     }
 
-    fn handle_mode(&mut self, tok: &str, pu: &mut PlayUpdate) {
-        let (_t,d) = tok.split_once(':').unwrap();
-        let d = d.strip_prefix('"').unwrap().strip_suffix('"').unwrap();
+    fn handle_mode(&mut self, jshow: &JValue, pu: &mut PlayUpdate) {
+        let d = match jshow.get("mode").and_then(|v| v.as_str()) {
+            Some(d) => d,
+            None => return,
+        };
         pu.msgs.insert("game".to_string(), format!("{}:{}", self.stime, d));
-        if d == "goal_r" {
-            pu.state = GameState::Goal(entities::SIDE_R);
-        } else if d == "goal_l" {
-            pu.state = GameState::Goal(entities::SIDE_L);
-        } else if d == "play_on" {
-            pu.state = GameState::PlayOn;
-        } else {
-            pu.state = GameState::Other(d.to_string());
-        }
+        pu.state = Some(rcss::PlayMode::parse(d));
     }
 
-    fn handle_teams(&mut self, tok: &str, pu: &mut PlayUpdate) {
-        let (_t,d) = tok.split_once(':').unwrap();
-        let mut tstr = self.tstrx.from_str(d, true);
-        tstr.peel_bracket('[').unwrap();
-        let teams = tstr.tokens_vec(',', true, false).unwrap();
+    fn handle_teams(&mut self, jshow: &JValue, pu: &mut PlayUpdate) {
+        let teams = match jshow.get("teams").and_then(|v| v.as_arr()) {
+            Some(teams) => teams,
+            None => return,
+        };
         for team in teams {
-            if team.trim().len() == 0 {
-                continue;
-            }
-            let mut tstr = self.tstrx.from_str(&team, true);
-            tstr.peel_bracket('{').unwrap();
-            let toks = tstr.tokens_vec(',', true, false).unwrap();
-            let mut side = '?';
-            let mut name = String::new();
-            let mut score = String::new();
-            for tok in toks {
-                if tok.starts_with("\"side\"") {
-                    let (_,d) = tok.split_once(':').unwrap();
-                    side = d.chars().nth(1).unwrap();
-                }
-                if tok.starts_with("\"name\"") {
-                    let (_,d) = tok.split_once(':').unwrap();
-                    let mut tstr = self.tstrx.from_str(d, true);
-                    if tstr.char_first().unwrap() == '"' {
-                        tstr.peel_string('"').unwrap();
-                    }
-                    name = tstr.to_string();
-                }
-                if tok.starts_with("\"score\"") {
-                    let (_,d) = tok.split_once(':').unwrap();
-                    score = d.to_string();
-                }
+            let side = team.get_string("side").chars().next().unwrap_or('?');
+            let name = team.get_string("name");
+            let score = team.get_f32("score", 0.0);
+            let penscore = team.get_f32("pen_score", 0.0);
+            let penmiss = team.get_f32("pen_miss", 0.0);
+            let ts;
+            if (penscore > 0.0) || (penmiss > 0.0) {
+                ts = format!("{} [{}({})]", name, score, penscore);
+            } else {
+                ts = format!("{} [{}]", name, score);
             }
-            let ts = format!("{} [{}]", name, score);
+            pu.teams.push((side, name, score as usize));
             if side == entities::SIDE_L {
                 self.ateam = ts;
             } else if side == entities::SIDE_R {
@@ -140,85 +236,44 @@ impl RCLive {
         pu.msgs.insert("score".to_string(), format!("{} vs {}", self.ateam, self.bteam));
     }
 
-    fn handle_ball(&mut self, tok: &str, pu: &mut PlayUpdate) {
-        let (_b,d) = tok.split_once(':').unwrap();
-        let mut tstr = self.tstrx.from_str(d, true);
-        tstr.peel_bracket('{').unwrap();
-        let toksl2 = tstr.tokens_vec(',', true, true).unwrap();
-        let mut fx = 0.0;
-        let mut fy = 0.0;
-        for tokl2 in toksl2 {
-            let (k,v) = tokl2.split_once(':').unwrap();
-            if k == "\"x\"" {
-                fx = v.parse().unwrap();
-            }
-            if k == "\"y\"" {
-                fy = v.parse().unwrap();
-            }
-        }
+    fn handle_ball(&mut self, jshow: &JValue, pu: &mut PlayUpdate) {
+        let jball = match jshow.get("ball") {
+            Some(jball) => jball,
+            None => return,
+        };
+        let fx = jball.get_f32("x", 0.0);
+        let fy = jball.get_f32("y", 0.0);
         let (fx,fy) = self.r2n.d2o((fx,fy));
         pu.ball = (fx, fy);
     }
 
-    fn handle_players(&mut self, tok: &str, pu: &mut PlayUpdate) {
-        let (_p,d) = tok.split_once(':').unwrap();
-        let mut tstr = self.tstrx.from_str(d, true);
-        tstr.peel_bracket('[').unwrap();
-        let toks = tstr.tokens_vec(',', true, false).unwrap();
-        ldebug!(&format!("DBUG:{}:Players:Got:Toks:Players:{:#?}", MTAG, toks));
+    fn handle_players(&mut self, jshow: &JValue, pu: &mut PlayUpdate) {
+        let players = match jshow.get("players").and_then(|v| v.as_arr()) {
+            Some(players) => players,
+            None => return,
+        };
         // handle the individual players
-        for tok in toks {
-            if tok.trim().len() == 0 {
-                continue;
-            }
-            let mut tstr = self.tstrx.from_str(&tok, true);
-            tstr.peel_bracket('{').unwrap();
-            let toksl2 = tstr.tokens_vec(',', true, true).unwrap();
-            ldebug!(&format!("DBUG:{}:Players:Got:Toks:Player:{:#?}", MTAG, toksl2));
-            let mut pid = String::new();
-            let mut fx = 0.0;
-            let mut fy = 0.0;
-            let mut side = String::new();
-            let mut fstamina = 1.0f32;
-            let mut card = playdata::Card::None;
-            let mut action = playdata::Action::None;
-            let mut fbody = 0.0;
-            let mut fneck = 0.0;
-            let mut fvw = 60.0;
-            // Extract the player specific datas
-            for tokl2 in toksl2 {
-                let (k,v) = tokl2.split_once(':').unwrap();
-                if k == "\"side\"" {
-                    side = v.to_string();
-                }
-                if k == "\"unum\"" {
-                    pid = v.to_string();
-                }
-                if k == "\"x\"" {
-                    fx = v.parse().unwrap();
-                }
-                if k == "\"y\"" {
-                    fy = v.parse().unwrap();
-                }
-                if k == "\"body\"" {
-                    fbody = v.parse().unwrap();
-                }
-                if k == "\"neck\"" {
-                    fneck = v.parse().unwrap();
-                }
-                if k == "\"vw\"" {
-                    fvw = v.parse().unwrap();
-                }
-                if k == "\"stamina\"" {
-                    fstamina = v.parse().unwrap();
-                }
-                if k == "\"state\"" {
-                    let state: u32 = v.parse().unwrap();
-                    (action, card) = rcss::handle_state(state);
-                    if (action == playdata::Action::None) && (card == playdata::Card::None) {
-                        ldebug!(&format!("DBUG:{}:Players:{}-{}:{}", MTAG, side, pid, state));
-                    }
-                }
+        for jplayer in players {
+            ldebug!(&format!("DBUG:{}:Players:Got:Player:{}", MTAG, jplayer));
+            let side = jplayer.get_string("side");
+            let pid = match jplayer.get("unum") {
+                Some(unum) => unum.to_simple_string(),
+                None => continue,
+            };
+            let fx = jplayer.get_f32("x", 0.0);
+            let fy = jplayer.get_f32("y", 0.0);
+            let fbody = jplayer.get_f32("body", 0.0);
+            let fneck = jplayer.get_f32("neck", 0.0);
+            let fvw = jplayer.get_f32("vw", 60.0);
+            // stamina can be a simple value or a object containing the value (v)
+            let mut fstamina = match jplayer.get("stamina") {
+                Some(JValue::Obj(_)) => jplayer.get("stamina").unwrap().get_f32("v", rcss::STAMINA_BASE),
+                _ => jplayer.get_f32("stamina", rcss::STAMINA_BASE),
+            };
+            let state = jplayer.get_f32("state", 0.0) as u32;
+            let (action, card) = rcss::handle_state(state);
+            if (action == playdata::Action::None) && (card == playdata::Card::None) {
+                ldebug!(&format!("DBUG:{}:Players:{}-{}:{}", MTAG, side, pid, state));
             }
             let (fx,fy) = self.r2n.d2o((fx,fy));
             fstamina = (fstamina/rcss::STAMINA_BASE).min(1.0);
@@ -227,15 +282,55 @@ impl RCLive {
             pd.push(PlayerData::Stamina(fstamina));
             pd.push(PlayerData::Card(card));
             pd.push(PlayerData::Action(action));
+            if rcss::is_goalie(state) {
+                pd.push(PlayerData::Goalie);
+            }
             let (fbody, fneck) = rcss::handle_dir(fbody, fneck);
             pd.push(PlayerData::Dir(fbody, fneck, fvw));
-            if side.chars().nth(1).unwrap() == entities::SIDE_L {
+            if side.chars().next() == Some(entities::SIDE_L) {
                 pu.lteamcoded.push((pid, pd));
-            } else {
+            } else if side.chars().next() == Some(entities::SIDE_R) {
                 pu.rteamcoded.push((pid, pd));
+            } else {
+                ldebug!(&format!("DBUG:{}:Players:UnknownSide:{}-{}", MTAG, side, pid));
+            }
+        }
+
+    }
+
+    fn handle_show(&mut self, jshow: &JValue, pu: &mut PlayUpdate) {
+        self.handle_time(jshow, pu);
+        self.check_restart(pu.timecounter, pu);
+        self.handle_mode(jshow, pu);
+        self.handle_teams(jshow, pu);
+        self.handle_ball(jshow, pu);
+        self.handle_players(jshow, pu);
+    }
+
+    /// Params are either directly part of the message or
+    /// grouped within a params object. The player_type id
+    /// is made part of the group name, ie player_type.ID.
+    fn handle_params(&mut self, jmsg: &JValue, stype: &str, pu: &mut PlayUpdate) {
+        let mut group = stype.to_string();
+        if let Some(id) = jmsg.get("id") {
+            group = format!("{}.{}", stype, id.to_simple_string());
+        }
+        let jparams = jmsg.get("params").unwrap_or(jmsg);
+        if let JValue::Obj(members) = jparams {
+            for (k, v) in members {
+                if (k == "type") || (k == "params") {
+                    continue;
+                }
+                pu.params.insert(format!("{}.{}", group, k), v.to_simple_string());
             }
         }
+        ldebug!(&format!("DBUG:{}:Params:{}:{}", MTAG, group, pu.params.len()));
+    }
 
+    fn handle_msg(&mut self, jmsg: &JValue, pu: &mut PlayUpdate) {
+        let board = jmsg.get_f32("board", 0.0) as usize;
+        let msg = jmsg.get_string("message");
+        pu.commsgs.push((format!("Board{}", board), msg));
     }
 
 }
@@ -259,64 +354,84 @@ impl PlayData for RCLive {
     /// to point to the address (including port) from which the record was
     /// recieved.
     ///
+    /// Till then the initial handshake is resent periodically. If the
+    /// server goes quiet for a while, the user specified server address
+    /// is used again for the handshake, so that a restarted server can
+    /// be connected to.
+    ///
     fn next_record(&mut self) -> super::PlayUpdate {
         let fmtag = format!("{}:NextRecord", MTAG);
         let mut pu = PlayUpdate::new();
         let mut buf = [0u8; 8196];
+        self.handshake_retry(&mut pu);
         let gotr = self.skt.recv_from(&mut buf);
-        if gotr.is_err() {
-            let err = gotr.unwrap_err();
-            if err.kind() == std::io::ErrorKind::WouldBlock {
-                eprintln!("WARN:{}:No data...", fmtag);
+        let (ilen, srcaddr) = match gotr {
+            Ok(gotr) => gotr,
+            Err(err) => {
+                match err.kind() {
+                    std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut => {
+                        ldebug!(&format!("DBUG:{}:No data...", fmtag));
+                    },
+                    _ => {
+                        // like connection refused, if the server isnt running
+                        ldebug!(&format!("DBUG:{}:Error:{}", fmtag, err));
+                        let status = format!("Error:{:?}", err.kind());
+                        self.set_status(&status, &mut pu);
+                    },
+                }
+                self.check_timeout(&mut pu);
                 return pu;
-            } else {
-                panic!("ERRR:{}:Unexpected error:{}", fmtag, err);
             }
-        }
+        };
+        self.recvtime = time::Instant::now();
         if !self.bsrvraddr_updated {
-            self.srvraddr = gotr.as_ref().unwrap().1.to_string();
+            self.srvraddr = srcaddr.to_string();
             self.bsrvraddr_updated = true;
+            let status = format!("Connected:{}", self.srvraddr);
+            self.set_status(&status, &mut pu);
+        }
+        let sbuf = String::from_utf8_lossy(&buf[0..ilen]);
+        ldebug!(&format!("DBUG:{}:Got:{}:{}", fmtag, srcaddr, &sbuf));
+        if sbuf.starts_with("(") {
+            // Monitor protocol version 3 and 4 messages are same as the rcg records
+            let sline = sbuf.trim_end_matches(|c: char| c == '\0' || c.is_whitespace());
+            let rtype = self.rcgparser.parse_line(sline, &mut pu);
+            if rtype == RcgRecord::Show {
+                self.check_restart(pu.timecounter, &mut pu);
+            }
+            let brecord = !matches!(rtype, RcgRecord::Skip | RcgRecord::Unknown | RcgRecord::Bad(_));
+            if brecord && self.recorder.is_some() {
+                self.recorder.as_mut().unwrap().record_line(sline);
+            }
+            ldebug!(&format!("DBUG:{}:Got:Pu:{:?}", fmtag, pu));
+            return pu;
         }
-        let sbuf = String::from_utf8_lossy(&buf);
-        ldebug!(&format!("DBUG:{}:Got:{:?}:{}", fmtag, gotr, &sbuf));
         if !sbuf.starts_with("{") {
             eprintln!("WARN:{}:Ignoring unexpected data [{}]...", fmtag, sbuf);
             return pu;
         }
-        let mut tstr = self.tstrx.from_str(&sbuf, true);
-        tstr.peel_bracket('{').unwrap();
-        let toks = tstr.tokens_vec(',', true, true).unwrap();
-        ldebug!(&format!("DBUG:{}:Got:Toks:Full:{:#?}", fmtag, toks));
-        for tok in toks {
-            if tok.starts_with("\"type\"") {
-                let (_t,d) = tok.split_once(':').unwrap();
-                if d != "\"show\"" {
-                    eprintln!("DBUG:{}:UnhandledTypeMsg:{}", fmtag, sbuf);
-                    let ilen = sbuf.trim().len().min(32);
-                    pu.msgs.insert("unknown".to_string(), sbuf[0..ilen].to_string());
-                    return pu;
-                }
-                continue;
-            }
-            if tok.starts_with("\"time\"") {
-                self.handle_time(&tok, &mut pu);
-                continue;
-            }
-            if tok.starts_with("\"mode\"") {
-                self.handle_mode(&tok, &mut pu);
-                continue;
-            }
-            if tok.starts_with("\"teams\"") {
-                self.handle_teams(&tok, &mut pu);
-                continue;
-            }
-            if tok.starts_with("\"ball\"") {
-                self.handle_ball(&tok, &mut pu);
-                continue;
+        let jmsg = match json::parse(&sbuf) {
+            Ok(jmsg) => jmsg,
+            Err(err) => {
+                eprintln!("WARN:{}:Ignoring bad json:{}:[{}]...", fmtag, err, sbuf);
+                return pu;
             }
-            if tok.starts_with("\"players\"") {
-                self.handle_players(&tok, &mut pu);
-                continue;
+        };
+        if self.recorder.is_some() {
+            self.recorder.as_mut().unwrap().record_json(&jmsg);
+        }
+        let stype = jmsg.get_string("type");
+        match stype.as_str() {
+            "show" => self.handle_show(&jmsg, &mut pu),
+            "server_param" | "player_param" | "player_type" => self.handle_params(&jmsg, &stype, &mut pu),
+            "msg" => self.handle_msg(&jmsg, &mut pu),
+            "team_graphic" => {
+                ldebug!(&format!("DBUG:{}:Ignoring:TeamGraphic", fmtag));
+            },
+            _ => {
+                eprintln!("DBUG:{}:UnhandledTypeMsg:{}", fmtag, sbuf);
+                let ilen = sbuf.trim().len().min(32);
+                pu.msgs.insert("unknown".to_string(), sbuf.chars().take(ilen).collect());
             }
         }
         ldebug!(&format!("DBUG:{}:Got:Pu:{:?}", fmtag, pu));
@@ -327,22 +442,36 @@ impl PlayData for RCLive {
         return;
     }
 
+    fn seek_to(&mut self, _time: usize) {
+        return;
+    }
+
     fn bdone(&self) -> bool {
         return false;
     }
 
     fn send_record(&mut self, buf: &[u8]) {
-        self.skt.send_to(buf, &self.srvraddr).unwrap();
+        if let Err(err) = self.skt.send_to(buf, &self.srvraddr) {
+            eprintln!("WARN:{}:SendFailed:{:?}:To:{}:{}", MTAG, buf, self.srvraddr, err);
+            return;
+        }
         eprintln!("DBUG:{}:Sent:{:?}:To:{:?}-{:?}", MTAG, buf, self.skt, self.srvraddr);
     }
 
     fn send_record_coded(&mut self, code: isize) {
         let msg = match code {
-            0 => "(dispinit version 5)\r\n".as_bytes(),
-            1 => "(dispstart)\x00".as_bytes(),
-            _ => todo!(),
+            rcss::CMDCODE_DISPINIT => Self::dispinit(self.version),
+            _ => {
+                match rcss::RCCmd::from_code(code, self.version) {
+                    Some(cmd) => format!("{}\x00", cmd),
+                    None => {
+                        eprintln!("WARN:{}:SendRecordCoded:Unknown code [{}]", MTAG, code);
+                        return;
+                    }
+                }
+            }
         };
-        self.send_record(msg);
+        self.send_record(msg.as_bytes());
     }
 
 }
@@ -3,16 +3,100 @@
 //! HanishKVC, 2022
 //!
 
-use super::{Card, Action};
+use crate::entities;
+use super::{Card, Action, PlayerCmd, Params};
 
 /// This time is infered from live record reception,
 /// Later need to check docs/src of rcss to check, if it can change
 pub const SECONDS_PER_RECORD: f32 = 0.1;
 pub const STAMINA_BASE: f32 = 8000.0;
 
+/// The pitch space used by rcss
+pub const RCSS_RECT: ((f32,f32), (f32,f32)) = ((-55.0, -37.0), (55.0, 37.0));
+
+/// Convert a position in the normal 0.0-1.0 space to the rcss pitch space (meters)
+pub fn npos_to_rcss(npos: (f32, f32)) -> (f32, f32) {
+    let x = RCSS_RECT.0.0 + npos.0*(RCSS_RECT.1.0 - RCSS_RECT.0.0);
+    let y = RCSS_RECT.0.1 + npos.1*(RCSS_RECT.1.1 - RCSS_RECT.0.1);
+    (x, y)
+}
+
+#[allow(dead_code)]
+/// Convert a position in the rcss pitch space (meters) to the normal 0.0-1.0 space
+pub fn rcss_to_npos(pos: (f32, f32)) -> (f32, f32) {
+    let x = (pos.0 - RCSS_RECT.0.0)/(RCSS_RECT.1.0 - RCSS_RECT.0.0);
+    let y = (pos.1 - RCSS_RECT.0.1)/(RCSS_RECT.1.1 - RCSS_RECT.0.1);
+    (x, y)
+}
+
+/// The field geometry used by rcss, in meters wrt the rcss pitch space.
+/// The defaults match rcssserver, and the goal width can be updated
+/// from the server_param sent by the server / logged in the rcg file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldGeom {
+    pub pitch_length: f32,
+    pub pitch_width: f32,
+    pub goal_width: f32,
+    pub goal_depth: f32,
+    pub goal_area_length: f32,
+    pub goal_area_width: f32,
+    pub penalty_area_length: f32,
+    pub penalty_area_width: f32,
+    pub penalty_spot_dist: f32,
+    pub center_circle_r: f32,
+    pub corner_arc_r: f32,
+}
+
+impl FieldGeom {
+
+    pub fn new() -> FieldGeom {
+        FieldGeom {
+            pitch_length: 105.0,
+            pitch_width: 68.0,
+            goal_width: 14.02,
+            goal_depth: 2.44,
+            goal_area_length: 5.5,
+            goal_area_width: 18.32,
+            penalty_area_length: 16.5,
+            penalty_area_width: 40.32,
+            penalty_spot_dist: 11.0,
+            center_circle_r: 9.15,
+            corner_arc_r: 1.0,
+        }
+    }
+
+    /// Update the geometry from the params if any, in the play update.
+    /// Returns true if the geometry changed.
+    pub fn update_from_params(&mut self, params: &Params) -> bool {
+        let old = self.clone();
+        if let Some(sgw) = params.get("server_param.goal_width") {
+            match sgw.parse::<f32>() {
+                Ok(gw) if gw > 0.0 => self.goal_width = gw,
+                _ => eprintln!("WARN:PPGND:Rcss:FieldGeom:Bad goal_width [{}]", sgw),
+            }
+        }
+        *self != old
+    }
+
+}
+
+/// The scale used wrt positions in monitor commands like dispfoul, dispplayer
+pub const SHOWINFO_SCALE: f32 = 16.0;
+
+/// Codes used wrt send_record_coded, by the rcss based playdata sources
+pub const CMDCODE_DISPINIT: isize = 0;
+pub const CMDCODE_DISPSTART: isize = 1;
+pub const CMDCODE_PAUSE: isize = 2;
+pub const CMDCODE_RESUME: isize = 3;
+pub const CMDCODE_DROPBALL: isize = 4;
+pub const CMDCODE_KICKOFF_L: isize = 5;
+pub const CMDCODE_KICKOFF_R: isize = 6;
+
 /// Player states
+pub const STATE_ENABLE: u32         = 0x00001;
 pub const STATE_KICK: u32           = 0x00002;
 pub const STATE_KICK_FAULT: u32     = 0x00004;
+pub const STATE_GOALIE: u32         = 0x00008;
 pub const STATE_CATCH: u32          = 0x00010;
 pub const STATE_CATCH_FAULT: u32    = 0x00020;
 pub const STATE_BALL2PLAYER: u32    = 0x00040;
@@ -65,10 +149,514 @@ pub fn handle_state(state: u32) -> (Action, Card) {
     return (action, card);
 }
 
+/// Whether the player state marks the player has the goalie
+pub fn is_goalie(state: u32) -> bool {
+    return state & STATE_GOALIE == STATE_GOALIE;
+}
+
+/// Convert action and card back to the player state bits.
+/// The player is marked has enabled.
+pub fn state_from(action: &Action, card: &Card) -> u32 {
+    let mut state = STATE_ENABLE;
+    state |= match action {
+        Action::Kick(true) => STATE_KICK,
+        Action::Kick(false) => STATE_KICK_FAULT,
+        Action::Catch(true) => STATE_CATCH,
+        Action::Catch(false) => STATE_CATCH_FAULT,
+        Action::Tackle(true) => STATE_TACKLE,
+        Action::Tackle(false) => STATE_TACKLE_FAULT,
+        Action::Others(others) => *others as u32,
+        Action::None => 0,
+    };
+    state |= match card {
+        Card::Red => STATE_REDCARD,
+        Card::Yellow => STATE_YELLOWCARD,
+        Card::None => 0,
+    };
+    state
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// The play modes of rcssserver.
+///
+/// The side is as given in the rcss play mode name, ie
+/// * the side awarded the restart wrt kick off, kick in, free kick, corner kick,
+///   goal kick, indirect free kick and penalty kick.
+/// * the side which scored wrt goal.
+/// * the side which committed the offence wrt offside, fouls, back pass,
+///   free kick fault, catch fault and illegal defense.
+/// * the side taking the kick wrt the penalty shoot-out modes.
+/// * the side whose goalie caught the ball wrt goalie catch.
+pub enum PlayMode {
+    BeforeKickOff,
+    TimeOver,
+    PlayOn,
+    KickOff(char),
+    KickIn(char),
+    FreeKick(char),
+    CornerKick(char),
+    GoalKick(char),
+    Goal(char),
+    DropBall,
+    Offside(char),
+    PenaltyKick(char),
+    FirstHalfOver,
+    Pause,
+    HumanJudge,
+    FoulCharge(char),
+    FoulPush(char),
+    FoulMultipleAttacker(char),
+    FoulBallOut(char),
+    BackPass(char),
+    FreeKickFault(char),
+    CatchFault(char),
+    IndirectFreeKick(char),
+    PenaltySetup(char),
+    PenaltyReady(char),
+    PenaltyTaken(char),
+    PenaltyMiss(char),
+    PenaltyScore(char),
+    IllegalDefense(char),
+    PenaltyOnfield(char),
+    PenaltyFoul(char),
+    GoalieCatch(char),
+    ExtendHalf,
+    /// The referee variants of first_half_over, time_over and extend_half
+    HalfTime,
+    TimeUp,
+    TimeUpWithoutATeam,
+    TimeExtended,
+    /// Any play mode not known to the program
+    Unknown(String),
+}
+
+impl PlayMode {
+
+    /// Parse the rcss play mode name, ex: kick_off_l
+    pub fn parse(smode: &str) -> PlayMode {
+        let smode = smode.trim();
+        let (base, side) = match smode.rsplit_once('_') {
+            Some((base, "l")) => (base, Some(entities::SIDE_L)),
+            Some((base, "r")) => (base, Some(entities::SIDE_R)),
+            _ => (smode, None),
+        };
+        match (base, side) {
+            ("before_kick_off", None) => PlayMode::BeforeKickOff,
+            ("time_over", None) => PlayMode::TimeOver,
+            ("play_on", None) => PlayMode::PlayOn,
+            ("kick_off", Some(side)) => PlayMode::KickOff(side),
+            ("kick_in", Some(side)) => PlayMode::KickIn(side),
+            ("free_kick", Some(side)) => PlayMode::FreeKick(side),
+            ("corner_kick", Some(side)) => PlayMode::CornerKick(side),
+            ("goal_kick", Some(side)) => PlayMode::GoalKick(side),
+            ("goal", Some(side)) => PlayMode::Goal(side),
+            ("drop_ball", None) => PlayMode::DropBall,
+            ("offside", Some(side)) => PlayMode::Offside(side),
+            ("penalty_kick", Some(side)) => PlayMode::PenaltyKick(side),
+            ("first_half_over", None) => PlayMode::FirstHalfOver,
+            ("pause", None) => PlayMode::Pause,
+            ("human_judge", None) => PlayMode::HumanJudge,
+            ("foul_charge", Some(side)) => PlayMode::FoulCharge(side),
+            ("foul_push", Some(side)) => PlayMode::FoulPush(side),
+            ("foul_multiple_attack", Some(side)) => PlayMode::FoulMultipleAttacker(side),
+            ("foul_ballout", Some(side)) => PlayMode::FoulBallOut(side),
+            ("back_pass", Some(side)) => PlayMode::BackPass(side),
+            ("free_kick_fault", Some(side)) => PlayMode::FreeKickFault(side),
+            ("catch_fault", Some(side)) => PlayMode::CatchFault(side),
+            ("indirect_free_kick", Some(side)) => PlayMode::IndirectFreeKick(side),
+            ("penalty_setup", Some(side)) => PlayMode::PenaltySetup(side),
+            ("penalty_ready", Some(side)) => PlayMode::PenaltyReady(side),
+            ("penalty_taken", Some(side)) => PlayMode::PenaltyTaken(side),
+            ("penalty_miss", Some(side)) => PlayMode::PenaltyMiss(side),
+            ("penalty_score", Some(side)) => PlayMode::PenaltyScore(side),
+            ("illegal_defense", Some(side)) => PlayMode::IllegalDefense(side),
+            ("penalty_onfield", Some(side)) => PlayMode::PenaltyOnfield(side),
+            ("penalty_foul", Some(side)) => PlayMode::PenaltyFoul(side),
+            ("goalie_catch_ball", Some(side)) => PlayMode::GoalieCatch(side),
+            ("extend_half", None) => PlayMode::ExtendHalf,
+            ("half_time", None) => PlayMode::HalfTime,
+            ("time_up", None) => PlayMode::TimeUp,
+            ("time_up_without_a_team", None) => PlayMode::TimeUpWithoutATeam,
+            ("time_extended", None) => PlayMode::TimeExtended,
+            _ => PlayMode::Unknown(smode.to_string()),
+        }
+    }
+
+    /// The rcss name of the play mode, without the side, along with the side if any
+    fn name_side(&self) -> (&str, Option<char>) {
+        match self {
+            PlayMode::BeforeKickOff => ("before_kick_off", None),
+            PlayMode::TimeOver => ("time_over", None),
+            PlayMode::PlayOn => ("play_on", None),
+            PlayMode::KickOff(side) => ("kick_off", Some(*side)),
+            PlayMode::KickIn(side) => ("kick_in", Some(*side)),
+            PlayMode::FreeKick(side) => ("free_kick", Some(*side)),
+            PlayMode::CornerKick(side) => ("corner_kick", Some(*side)),
+            PlayMode::GoalKick(side) => ("goal_kick", Some(*side)),
+            PlayMode::Goal(side) => ("goal", Some(*side)),
+            PlayMode::DropBall => ("drop_ball", None),
+            PlayMode::Offside(side) => ("offside", Some(*side)),
+            PlayMode::PenaltyKick(side) => ("penalty_kick", Some(*side)),
+            PlayMode::FirstHalfOver => ("first_half_over", None),
+            PlayMode::Pause => ("pause", None),
+            PlayMode::HumanJudge => ("human_judge", None),
+            PlayMode::FoulCharge(side) => ("foul_charge", Some(*side)),
+            PlayMode::FoulPush(side) => ("foul_push", Some(*side)),
+            PlayMode::FoulMultipleAttacker(side) => ("foul_multiple_attack", Some(*side)),
+            PlayMode::FoulBallOut(side) => ("foul_ballout", Some(*side)),
+            PlayMode::BackPass(side) => ("back_pass", Some(*side)),
+            PlayMode::FreeKickFault(side) => ("free_kick_fault", Some(*side)),
+            PlayMode::CatchFault(side) => ("catch_fault", Some(*side)),
+            PlayMode::IndirectFreeKick(side) => ("indirect_free_kick", Some(*side)),
+            PlayMode::PenaltySetup(side) => ("penalty_setup", Some(*side)),
+            PlayMode::PenaltyReady(side) => ("penalty_ready", Some(*side)),
+            PlayMode::PenaltyTaken(side) => ("penalty_taken", Some(*side)),
+            PlayMode::PenaltyMiss(side) => ("penalty_miss", Some(*side)),
+            PlayMode::PenaltyScore(side) => ("penalty_score", Some(*side)),
+            PlayMode::IllegalDefense(side) => ("illegal_defense", Some(*side)),
+            PlayMode::PenaltyOnfield(side) => ("penalty_onfield", Some(*side)),
+            PlayMode::PenaltyFoul(side) => ("penalty_foul", Some(*side)),
+            PlayMode::GoalieCatch(side) => ("goalie_catch_ball", Some(*side)),
+            PlayMode::ExtendHalf => ("extend_half", None),
+            PlayMode::HalfTime => ("half_time", None),
+            PlayMode::TimeUp => ("time_up", None),
+            PlayMode::TimeUpWithoutATeam => ("time_up_without_a_team", None),
+            PlayMode::TimeExtended => ("time_extended", None),
+            PlayMode::Unknown(smode) => (smode.as_str(), None),
+        }
+    }
+
+    /// The name of the play mode, without the side suffix if any
+    pub fn name(&self) -> &str {
+        return self.name_side().0;
+    }
+
+    /// The side associated with the play mode, if any
+    pub fn side(&self) -> Option<char> {
+        return self.name_side().1;
+    }
+
+    /// Whether the play mode is a restart of play with a set piece, ie
+    /// kick in, free kick, corner kick, goal kick, indirect free kick or penalty kick.
+    /// The side is the side taking the set piece.
+    pub fn set_piece(&self) -> Option<char> {
+        match self {
+            PlayMode::KickIn(side) | PlayMode::FreeKick(side) | PlayMode::CornerKick(side) |
+            PlayMode::GoalKick(side) | PlayMode::IndirectFreeKick(side) | PlayMode::PenaltyKick(side) => Some(*side),
+            _ => None,
+        }
+    }
+
+    /// Whether the play mode indicates the end of a period (half/extra time) or the game.
+    pub fn is_period_end(&self) -> bool {
+        match self {
+            PlayMode::FirstHalfOver | PlayMode::TimeOver | PlayMode::HalfTime |
+            PlayMode::TimeUp | PlayMode::TimeUpWithoutATeam => true,
+            _ => false,
+        }
+    }
+
+    /// Whether the play mode belongs to a penalty shoot-out
+    pub fn is_penalty_shootout(&self) -> bool {
+        match self {
+            PlayMode::PenaltySetup(_) | PlayMode::PenaltyReady(_) | PlayMode::PenaltyTaken(_) |
+            PlayMode::PenaltyMiss(_) | PlayMode::PenaltyScore(_) | PlayMode::PenaltyOnfield(_) |
+            PlayMode::PenaltyFoul(_) => true,
+            _ => false,
+        }
+    }
+
+}
+
+impl std::fmt::Display for PlayMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.name_side() {
+            (name, Some(side)) => f.write_str(&format!("{}_{}", name, side)),
+            (name, None) => f.write_str(name),
+        }
+    }
+}
+
 /// RCSS Direction is in degrees.
 /// +ve is clockwise from 0 degree,
 /// -ve is anticlockwise from 0 degree.
 /// RCSS and SdlX interpret arc angles in same way, so simple straight mapping.
 pub fn handle_dir(fbody: f32, fneck: f32) -> (f32,f32) {
     (fbody, fneck)
-}
\ No newline at end of file
+}
+
+/// Split the given string into its top level bracketed blocks.
+/// Ex: (turn 30)(say "hi (all)") => ["(turn 30)", "(say "hi (all)")"]
+///
+/// Brackets within double quoted strings are ignored.
+/// Anything outside the top level brackets is skipped.
+pub fn sexp_blocks(s: &str) -> Vec<String> {
+    let mut vblocks = Vec::new();
+    let mut block = String::new();
+    let mut depth = 0;
+    let mut bstring = false;
+    for c in s.chars() {
+        if depth == 0 {
+            if c == '(' {
+                depth = 1;
+                block.push(c);
+            }
+            continue;
+        }
+        block.push(c);
+        if bstring {
+            if c == '"' {
+                bstring = false;
+            }
+            continue;
+        }
+        match c {
+            '"' => bstring = true,
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    vblocks.push(block.clone());
+                    block.clear();
+                }
+            },
+            _ => (),
+        }
+    }
+    vblocks
+}
+
+/// Split the contents of a bracketed block into its top level tokens.
+/// Ex: (kick 100 (a b) "hi all") => ["kick", "100", "(a b)", "\"hi all\""]
+///
+/// Nested blocks and double quoted strings are retained as is, as single tokens.
+/// Adjacent nested blocks (without space between them) are seperate tokens.
+pub fn sexp_tokens(block: &str) -> Vec<String> {
+    let s = block.trim();
+    let s = s.strip_prefix('(').unwrap_or(s);
+    let s = s.strip_suffix(')').unwrap_or(s);
+    let mut vtoks = Vec::new();
+    let mut tok = String::new();
+    let mut depth = 0;
+    let mut bstring = false;
+    for c in s.chars() {
+        if bstring {
+            tok.push(c);
+            if c == '"' {
+                bstring = false;
+            }
+            continue;
+        }
+        match c {
+            '"' => {
+                bstring = true;
+                tok.push(c);
+            },
+            '(' => {
+                if (depth == 0) && (tok.len() > 0) {
+                    vtoks.push(tok.clone());
+                    tok.clear();
+                }
+                depth += 1;
+                tok.push(c);
+            },
+            ')' => {
+                depth -= 1;
+                tok.push(c);
+                // adjacent blocks like (a 1)(b 2) are seperate tokens
+                if depth == 0 {
+                    vtoks.push(tok.clone());
+                    tok.clear();
+                }
+            },
+            ' ' | '\t' | '\r' | '\n' if depth == 0 => {
+                if tok.len() > 0 {
+                    vtoks.push(tok.clone());
+                    tok.clear();
+                }
+            },
+            _ => tok.push(c),
+        }
+    }
+    if tok.len() > 0 {
+        vtoks.push(tok);
+    }
+    vtoks
+}
+
+/// Convert a player command block like (kick 100 -20) into PlayerCmd.
+/// Missing or unparsable optional args default to 0.
+pub fn handle_playercmd(block: &str) -> PlayerCmd {
+    let toks = sexp_tokens(block);
+    if toks.len() == 0 {
+        return PlayerCmd::Other(block.to_string());
+    }
+    let farg = |i: usize| -> f32 {
+        if i >= toks.len() {
+            return 0.0;
+        }
+        toks[i].parse().unwrap_or(0.0)
+    };
+    match toks[0].as_str() {
+        "kick" => PlayerCmd::Kick(farg(1), farg(2)),
+        "dash" => PlayerCmd::Dash(farg(1), farg(2)),
+        "turn" => PlayerCmd::Turn(farg(1)),
+        "turn_neck" => PlayerCmd::TurnNeck(farg(1)),
+        "tackle" => {
+            let bfoul = (toks.len() > 2) && (toks[2] == "on" || toks[2] == "true");
+            PlayerCmd::Tackle(farg(1), bfoul)
+        },
+        "catch" => PlayerCmd::Catch(farg(1)),
+        "move" => PlayerCmd::Move(farg(1), farg(2)),
+        "say" => {
+            let msg = toks[1..].join(" ");
+            let msg = msg.trim_matches('"');
+            PlayerCmd::Say(msg.to_string())
+        },
+        "pointto" => {
+            if (toks.len() > 1) && (toks[1] == "off") {
+                PlayerCmd::PointTo(None)
+            } else {
+                PlayerCmd::PointTo(Some((farg(1), farg(2))))
+            }
+        },
+        _ => PlayerCmd::Other(block.to_string()),
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+/// Commands which can be sent to the rcssserver by a monitor, including the
+/// trainer like commands accepted by it, when running in coach mode.
+/// Positions are in rcss pitch space and sides are l or r.
+pub enum RCCmd {
+    DispInit(usize),
+    DispStart,
+    /// x, y, side which gets the free kick
+    DispFoul(f32, f32, char),
+    /// side, unum, x, y, body direction
+    DispPlayer(char, usize, f32, f32, f32),
+    ChangeMode(String),
+    MoveBall(f32, f32),
+    /// side, unum, x, y, optional body direction
+    MovePlayer(char, usize, f32, f32, Option<f32>),
+    /// side, unum, player type
+    ChangePlayerType(char, usize, usize),
+    Recover,
+}
+
+impl RCCmd {
+
+    /// Map the send_record_coded codes to the corresponding commands.
+    ///
+    /// Pause changes the mode to pause (where time doesnt advance) and
+    /// resume changes it back to play_on.
+    pub fn from_code(code: isize, version: usize) -> Option<RCCmd> {
+        match code {
+            CMDCODE_DISPINIT => Some(RCCmd::DispInit(version)),
+            CMDCODE_DISPSTART => Some(RCCmd::DispStart),
+            CMDCODE_PAUSE => Some(RCCmd::ChangeMode("pause".to_string())),
+            CMDCODE_RESUME => Some(RCCmd::ChangeMode("play_on".to_string())),
+            CMDCODE_DROPBALL => Some(RCCmd::ChangeMode("drop_ball".to_string())),
+            CMDCODE_KICKOFF_L => Some(RCCmd::ChangeMode("kick_off_l".to_string())),
+            CMDCODE_KICKOFF_R => Some(RCCmd::ChangeMode("kick_off_r".to_string())),
+            _ => None,
+        }
+    }
+
+    /// Parse a command entered by the user, the outer brackets are optional.
+    /// Ex: change_mode free_kick_l
+    ///     move ball 0 0 | move (ball) 0 0
+    ///     move player l 3 -10 5 [90] | move (player l 3) -10 5 [90]
+    ///     change_player_type l 3 2
+    ///     dispfoul 10 -5 l
+    ///     dispplayer l 3 -10 5 90
+    ///     pause | resume | dispinit | dispstart | recover
+    pub fn parse(scmd: &str) -> Result<RCCmd, String> {
+        let scmd = scmd.trim();
+        let mut vtoks = Vec::new();
+        for tok in sexp_tokens(&format!("({})", scmd.trim_start_matches('(').trim_end_matches(')'))) {
+            if tok.starts_with('(') {
+                vtoks.extend(sexp_tokens(&tok));
+            } else {
+                vtoks.push(tok);
+            }
+        }
+        if vtoks.len() == 0 {
+            return Err("Empty command".to_string());
+        }
+        let toks: Vec<&str> = vtoks.iter().map(|tok| tok.as_str()).collect();
+        let fnum = |i: usize| -> Result<f32, String> {
+            if i >= toks.len() {
+                return Err(format!("{}:Missing arg {}", toks[0], i));
+            }
+            toks[i].parse().map_err(|_| format!("{}:Bad number [{}]", toks[0], toks[i]))
+        };
+        let unum = |i: usize| -> Result<usize, String> {
+            fnum(i).map(|n| n as usize)
+        };
+        let side = |i: usize| -> Result<char, String> {
+            if (i < toks.len()) && ((toks[i] == "l") || (toks[i] == "r")) {
+                return Ok(toks[i].chars().next().unwrap());
+            }
+            Err(format!("{}:Side should be l or r", toks[0]))
+        };
+        match toks[0] {
+            "dispinit" => Ok(RCCmd::DispInit(unum(2).unwrap_or(5))),
+            "dispstart" => Ok(RCCmd::DispStart),
+            "dispfoul" => Ok(RCCmd::DispFoul(fnum(1)?, fnum(2)?, side(3)?)),
+            "dispplayer" => Ok(RCCmd::DispPlayer(side(1)?, unum(2)?, fnum(3)?, fnum(4)?, fnum(5)?)),
+            "change_mode" => {
+                if toks.len() < 2 {
+                    return Err("change_mode:Missing playmode".to_string());
+                }
+                Ok(RCCmd::ChangeMode(toks[1].to_string()))
+            },
+            "move" => {
+                if (toks.len() > 1) && (toks[1] == "ball") {
+                    return Ok(RCCmd::MoveBall(fnum(2)?, fnum(3)?));
+                }
+                if (toks.len() > 1) && (toks[1] == "player") {
+                    return Ok(RCCmd::MovePlayer(side(2)?, unum(3)?, fnum(4)?, fnum(5)?, fnum(6).ok()));
+                }
+                Err("move:Should be ball or player".to_string())
+            },
+            "change_player_type" => Ok(RCCmd::ChangePlayerType(side(1)?, unum(2)?, unum(3)?)),
+            "pause" => Ok(RCCmd::from_code(CMDCODE_PAUSE, 5).unwrap()),
+            "resume" => Ok(RCCmd::from_code(CMDCODE_RESUME, 5).unwrap()),
+            "recover" => Ok(RCCmd::Recover),
+            _ => Err(format!("Unknown command [{}]", toks[0])),
+        }
+    }
+
+}
+
+/// Side as used by the monitor commands
+fn side_num(side: &char) -> i32 {
+    if *side == entities::SIDE_L { 1 } else { -1 }
+}
+
+/// Position as used by the monitor commands
+fn showinfo_scaled(f: &f32) -> i32 {
+    (f * SHOWINFO_SCALE).round() as i32
+}
+
+impl std::fmt::Display for RCCmd {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RCCmd::DispInit(version) => write!(f, "(dispinit version {})", version),
+            RCCmd::DispStart => write!(f, "(dispstart)"),
+            RCCmd::DispFoul(x, y, side) => write!(f, "(dispfoul {} {} {})", showinfo_scaled(x), showinfo_scaled(y), side_num(side)),
+            RCCmd::DispPlayer(side, unum, x, y, dir) => write!(f, "(dispplayer {} {} {} {} {})", side_num(side), unum, showinfo_scaled(x), showinfo_scaled(y), dir.round() as i32),
+            RCCmd::ChangeMode(mode) => write!(f, "(change_mode {})", mode),
+            RCCmd::MoveBall(x, y) => write!(f, "(move (ball) {} {})", x, y),
+            RCCmd::MovePlayer(side, unum, x, y, dir) => {
+                match dir {
+                    Some(dir) => write!(f, "(move (player {} {}) {} {} {})", side, unum, x, y, dir),
+                    None => write!(f, "(move (player {} {}) {} {})", side, unum, x, y),
+                }
+            },
+            RCCmd::ChangePlayerType(side, unum, ptype) => write!(f, "(change_player_type {} {} {})", side, unum, ptype),
+            RCCmd::Recover => write!(f, "(recover)"),
+        }
+    }
+}
@@ -2,10 +2,12 @@
 //! Identify pass quality
 //! HanishKVC, 2022
 //!
+//! The chaining of actions (wrt scoring) is restarted at kick offs and
+//! penalty setups, so that the kick which restarts play isnt scored wrt
+//! the actions before it, like at half time, where the side which kicks
+//! off switches. The stats are inturn split wrt the periods of the game.
+//!
 //! TODO:
-//! * Track for halftime/etal and avoid providing -ve scoring
-//!   due to any of these leading to shifting/switching of side
-//!   wrt the kick that will follow.
 //! * Allow -ve scoring to goalie, if they allow a goal to occur
 //!   ie beyond the failed catch situations.
 //! * Account penalties beyond cards during scoring.
@@ -19,6 +21,14 @@ use sdl2::{pixels::Color, render::BlendMode};
 
 use crate::sdlx::{SdlX, XRect};
 use crate::{entities, playdata};
+use crate::playdata::json::JValue;
+use crate::playdata::rcss;
+use crate::proc::possession::{self, Possession, TeamPossession, Sequence};
+use crate::proc::shots::{Shots, Shot, ShotOutcome};
+use crate::proc::offside::{Offside, OffsideCall};
+use crate::proc::periods::Periods;
+use crate::proc::setpieces::{SetPieces, SetPiece};
+use crate::proc::goalies::{Goalies, Goalie};
 
 
 const MTAG: &str = "GPPGND:ProcActions";
@@ -47,6 +57,26 @@ pub const SUMMARY_RELATIVE_TEAM: char = 'T';
 /// Relative summary graphs wrt Best across both teams
 pub const SUMMARY_RELATIVE_ALL: char = 'A';
 
+/// Where the possession panel is shown
+const POSSESSION_RECT: (f32, f32, f32, f32) = (0.02, 0.10, 0.46, 0.40);
+
+/// Colors used when highlighting the actions of the left and right teams
+const HL_LCOLOR: Color = Color::YELLOW;
+const HL_RCOLOR: Color = Color::CYAN;
+/// Sizes used when highlighting a possession sequence
+const SEQ_LINE_WIDTH: f32 = 0.003;
+const SEQ_TOUCH_RADIUS: f32 = 0.008;
+/// The radius of the shot marker, wrt a xG of 1.0
+const SHOT_RADIUS: f32 = 0.02;
+/// Where the set pieces panel is shown
+const SETPIECES_RECT: (f32, f32, f32, f32) = (0.52, 0.10, 0.46, 0.40);
+/// The number of latest set pieces listed in the set pieces panel
+const SETPIECES_PANEL_LATEST: usize = 5;
+/// The size of the set piece marker
+const SETPIECE_RADIUS: f32 = 0.006;
+/// Where the goalies panel is shown
+const GOALIES_RECT: (f32, f32, f32, f32) = (0.27, 0.55, 0.46, 0.30);
+
 /// Filtering of player time vs score data
 //pub const PLOT_TVS_FILTER: [f32;5] = [0.1,0.2,0.4,0.2,0.1];
 pub const PLOT_TVS_FILTER: [f32;0] = [];
@@ -75,6 +105,26 @@ struct Score {
     dist: f32,
     /// Card issued if any
     card: playdata::Card,
+    /// The number of kick commands, as per the player commands log if any
+    kickcmds: usize,
+    /// The total power wrt the kick commands
+    kickpower: f32,
+    /// The number of dash commands, as per the player commands log if any
+    dashcmds: usize,
+    /// The number of kicks seen in the player state, which had a matching kick command
+    kicksconfirmed: usize,
+    /// The number of kicks not seen in the player state, but inferred from a kick command
+    kicksattributed: usize,
+    /// The number of times the ball was won from a kick by the other side
+    interceptions: usize,
+    /// The number of times the other side got the ball, after a kick
+    balllosses: usize,
+    /// The number of tackles, after which the ball was with the own side
+    tackleswon: usize,
+    /// The number of tackles, after which the ball was with the other side
+    tacklesfailed: usize,
+    /// The score, kicks, tackles and catchs during each period of the game
+    vperiods: Vec<(f32, usize, usize, usize)>,
 }
 
 impl Score {
@@ -91,6 +141,16 @@ impl Score {
             catchs: catchs,
             dist: dist,
             card: card,
+            kickcmds: 0,
+            kickpower: 0.0,
+            dashcmds: 0,
+            kicksconfirmed: 0,
+            kicksattributed: 0,
+            interceptions: 0,
+            balllosses: 0,
+            tackleswon: 0,
+            tacklesfailed: 0,
+            vperiods: Vec::new(),
         }
     }
 
@@ -98,8 +158,9 @@ impl Score {
         return Score::new(0.0, 0, 0, 0, 0.0, playdata::Card::None);
     }
 
-    fn pscore_update(&mut self, time: usize, pscoredelta: f32) {
+    fn pscore_update(&mut self, time: usize, period: usize, pscoredelta: f32) {
         self.pscore += pscoredelta;
+        self.period_mut(period).0 += pscoredelta;
         if self.pscore > self.hist_cumul_maxpscore {
             self.hist_cumul_maxpscore = self.pscore;
         }
@@ -110,6 +171,19 @@ impl Score {
         self.vtimepscore_cumul.push((time, self.pscore));
     }
 
+    /// The stats wrt the given period, adding periods as required
+    fn period_mut(&mut self, period: usize) -> &mut (f32, usize, usize, usize) {
+        if self.vperiods.len() <= period {
+            self.vperiods.resize(period+1, (0.0, 0, 0, 0));
+        }
+        return &mut self.vperiods[period];
+    }
+
+    /// The score (including card penalties), kicks, tackles and catchs during the given period
+    fn period(&self, period: usize) -> (f32, usize, usize, usize) {
+        return self.vperiods.get(period).cloned().unwrap_or((0.0, 0, 0, 0));
+    }
+
     fn card_score_value(card: playdata::Card) -> f32 {
         match card {
             playdata::Card::None => 0.0,
@@ -118,9 +192,9 @@ impl Score {
         }
     }
 
-    fn card_issued(&mut self, time: usize, card: playdata::Card) {
+    fn card_issued(&mut self, time: usize, period: usize, card: playdata::Card) {
         self.card = card.clone();
-        self.pscore_update(time, Self::card_score_value(card));
+        self.pscore_update(time, period, Self::card_score_value(card));
     }
 
     /// Returns the performance score as is, or after substracting latest
@@ -144,6 +218,15 @@ impl Score {
 
 type Pos = (f32, f32);
 
+#[derive(Debug, Clone, PartialEq)]
+/// The ball winning/losing related stats of a player
+enum BallStat {
+    Interception,
+    BallLoss,
+    TackleWon,
+    TackleFailed,
+}
+
 #[derive(Debug)]
 struct Player {
     id: String,
@@ -168,8 +251,8 @@ struct Teams {
     rpids: Vec<String>,
     lplayers: HashMap<String, Player>,
     rplayers: HashMap<String, Player>,
-    lballtime: f32,
-    rballtime: f32,
+    /// The period of the game, to which the scores and counts being updated belong
+    period: usize,
 }
 
 impl Teams {
@@ -180,8 +263,7 @@ impl Teams {
             rpids: Vec::new(),
             lplayers: HashMap::new(),
             rplayers: HashMap::new(),
-            lballtime: 0.0,
-            rballtime: 0.0,
+            period: 0,
         };
         for pid in lplayers {
             teams.lpids.push(pid.to_string());
@@ -214,8 +296,9 @@ impl Teams {
         } else {
             eprintln!("DBUG:{}:Players:Card:{}{:02}:{}", MTAG, side, playerid, card);
         }
+        let period = self.period;
         let player = self.get_player_mut(side, playerid);
-        player.score.card_issued(time, card);
+        player.score.card_issued(time, period, card);
     }
 
     /// Help update the performance related score of a specific player
@@ -227,8 +310,9 @@ impl Teams {
         } else {
             eprintln!("DBUG:{}:Players:Score:{}{:02}:{}", MTAG, side, playerid, pscoredelta);
         }
+        let period = self.period;
         let player = self.get_player_mut(side, playerid);
-        player.score.pscore_update(time, pscoredelta);
+        player.score.pscore_update(time, period, pscoredelta);
     }
 
     /// Help update the count wrt specified action of a specific player
@@ -237,6 +321,7 @@ impl Teams {
             ldebug!(&format!("WARN:{}:Players:CountInc:SpecialPlayerId:{}{:02}:Ignoring...", MTAG, side, playerid));
             return;
         }
+        let period = self.period;
         let player = self.get_player_mut(side, playerid);
         let stype;
         match atype {
@@ -244,20 +329,105 @@ impl Teams {
             AIAction::Kick => {
                 stype = "Kick";
                 player.score.kicks += 1;
+                player.score.period_mut(period).1 += 1;
             },
             AIAction::Catch => {
                 stype = "Catch";
                 player.score.catchs += 1;
+                player.score.period_mut(period).3 += 1;
             },
             AIAction::Tackle => {
                 stype = "Tackle";
                 player.score.tackles += 1;
+                player.score.period_mut(period).2 += 1;
             },
             AIAction::Goal => stype = "Goal",
         }
         ldebug!(&format!("DBUG:{}:CountInc:{}{:02}:{}", MTAG, side, playerid, stype));
     }
 
+    /// Recount the kicks, tackles and catchs of the players during each period,
+    /// wrt the given raw actions, ie after a seek back in time.
+    fn period_counts_recount(&mut self, rawactions: &Vec<ActionData>, periods: &Periods) {
+        for player in self.lplayers.values_mut().chain(self.rplayers.values_mut()) {
+            for pstats in player.score.vperiods.iter_mut() {
+                (pstats.1, pstats.2, pstats.3) = (0, 0, 0);
+            }
+        }
+        for actd in rawactions {
+            if actd.playerid.starts_with(entities::XPLAYERID_START) {
+                continue;
+            }
+            let period = periods.period_of(actd.time);
+            let pstats = self.get_player_mut(actd.side, &actd.playerid).score.period_mut(period);
+            match actd.action {
+                AIAction::Kick => pstats.1 += 1,
+                AIAction::Tackle => pstats.2 += 1,
+                AIAction::Catch => pstats.3 += 1,
+                _ => (),
+            }
+        }
+    }
+
+    /// Help update the ball winning/losing related stats of a specific player
+    fn ballstat_increment(&mut self, side: char, playerid: &str, stat: BallStat) {
+        if playerid.starts_with(entities::XPLAYERID_START) {
+            ldebug!(&format!("WARN:{}:Players:BallStatInc:SpecialPlayerId:{}{:02}:Ignoring...", MTAG, side, playerid));
+            return;
+        }
+        let player = self.get_player_mut(side, playerid);
+        match stat {
+            BallStat::Interception => player.score.interceptions += 1,
+            BallStat::BallLoss => player.score.balllosses += 1,
+            BallStat::TackleWon => player.score.tackleswon += 1,
+            BallStat::TackleFailed => player.score.tacklesfailed += 1,
+        }
+        ldebug!(&format!("DBUG:{}:BallStatInc:{}{:02}:{:?}", MTAG, side, playerid, stat));
+    }
+
+    /// Help update the ball stats wrt the ball being won by a player, from a kick by the other side
+    fn ballstat_turnover(&mut self, lside: char, lplayerid: &str, wside: char, wplayerid: &str) {
+        self.ballstat_increment(lside, lplayerid, BallStat::BallLoss);
+        self.ballstat_increment(wside, wplayerid, BallStat::Interception);
+    }
+
+    /// Help update the kick related counts of a specific player, wrt a kick command,
+    /// which either confirmed a kick seen in the state or was attributed as a kick
+    fn kickcmd_update(&mut self, side: char, playerid: &str, battributed: bool) {
+        if playerid.starts_with(entities::XPLAYERID_START) {
+            ldebug!(&format!("WARN:{}:Players:KickCmdUpdate:SpecialPlayerId:{}{:02}:Ignoring...", MTAG, side, playerid));
+            return;
+        }
+        let player = self.get_player_mut(side, playerid);
+        if battributed {
+            player.score.kicksattributed += 1;
+        } else {
+            player.score.kicksconfirmed += 1;
+        }
+    }
+
+    /// Help update the player commands related info of a specific player
+    fn cmds_update(&mut self, side: char, playerid: &str, cmds: &Vec<playdata::PlayerCmd>) {
+        if cmds.len() == 0 {
+            return;
+        }
+        if playerid.starts_with(entities::XPLAYERID_START) {
+            ldebug!(&format!("WARN:{}:Players:CmdsUpdate:SpecialPlayerId:{}{:02}:Ignoring...", MTAG, side, playerid));
+            return;
+        }
+        let player = self.get_player_mut(side, playerid);
+        for cmd in cmds {
+            match cmd {
+                playdata::PlayerCmd::Kick(power, _dir) => {
+                    player.score.kickcmds += 1;
+                    player.score.kickpower += power;
+                },
+                playdata::PlayerCmd::Dash(_power, _dir) => player.score.dashcmds += 1,
+                _ => (),
+            }
+        }
+    }
+
     fn dist_update_from_pos(&mut self, side: char, playerid: &str, npos: Pos) {
         if playerid.starts_with(entities::XPLAYERID_START) {
             ldebug!(&format!("WARN:{}:Players:DistUpdateFromPos:SpecialPlayerId:{}{:02}:Ignoring...", MTAG, side, playerid));
@@ -348,16 +518,18 @@ impl Teams {
         (lmax, rmax)
     }
 
-    /// If ball actions remain on the same side, give ball time to the side
-    fn balltime_update(&mut self, prevactd: &ActionData, curactd: &ActionData) {
-        if curactd.side == prevactd.side {
-            let dtime = (curactd.time - prevactd.time) as f32;
-            if curactd.side == entities::SIDE_L {
-                self.lballtime += dtime;
-            } else {
-                self.rballtime += dtime;
+    /// The known positions of all the players, as (side, playerid, pos)
+    fn positions(&self) -> Vec<(char, String, Pos)> {
+        let mut vpos = Vec::new();
+        for (side, players) in [(entities::SIDE_L, &self.lplayers), (entities::SIDE_R, &self.rplayers)] {
+            for player in players.values() {
+                if player.pos.0 == 99.0 && player.pos.1 == 99.0 {
+                    continue;
+                }
+                vpos.push((side, player.id.to_string(), player.pos));
             }
         }
+        return vpos;
     }
 
 }
@@ -416,6 +588,12 @@ pub struct ActionData {
     playerid: String,
     pub pos: (f32, f32),
     action: AIAction,
+    /// The commands sent by the player wrt this time, if known.
+    cmds: Vec<playdata::PlayerCmd>,
+    /// The intended power and direction wrt a kick, if known from the commands.
+    pub kick: Option<(f32, f32)>,
+    /// The player state indicated a failed kick.
+    kickfault: bool,
 }
 
 impl ActionData {
@@ -427,9 +605,29 @@ impl ActionData {
             playerid: playerid,
             pos: pos,
             action: action,
+            cmds: Vec::new(),
+            kick: None,
+            kickfault: false,
         }
     }
 
+    /// Attach the player commands wrt this action data, noting the kick if any.
+    pub fn with_cmds(mut self, cmds: Vec<playdata::PlayerCmd>) -> ActionData {
+        for cmd in &cmds {
+            if let playdata::PlayerCmd::Kick(power, dir) = cmd {
+                self.kick = Some((*power, *dir));
+            }
+        }
+        self.cmds = cmds;
+        self
+    }
+
+    /// Note that the player state indicated a failed kick.
+    pub fn with_kickfault(mut self, kickfault: bool) -> ActionData {
+        self.kickfault = kickfault;
+        self
+    }
+
     fn print(&self, print_aia_none: bool) {
         let mut bprint = true;
         match self.action {
@@ -447,7 +645,11 @@ impl ActionData {
 
 impl Display for ActionData {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.write_fmt(format_args!("[{}:{}{:02}:{}:({},{})]", self.time, self.side, self.playerid, self.action, self.pos.0, self.pos.1))
+        f.write_fmt(format_args!("[{}:{}{:02}:{}:({},{})", self.time, self.side, self.playerid, self.action, self.pos.0, self.pos.1))?;
+        if let Some((power, dir)) = self.kick {
+            f.write_fmt(format_args!(":KickCmd:{}:{}", power, dir))?;
+        }
+        f.write_str("]")
     }
 }
 
@@ -462,6 +664,24 @@ pub struct ActionsInfo {
     pub rawactions: Vec<ActionData>,
     /// Flag to indicate a seek was requested
     handle_deferedseek: bool,
+    /// Ball possession, tracked every cycle based on ball proximity
+    pub possession: Possession,
+    /// The possession sequence to highlight on the pitch, if any
+    pub seqhighlight: Option<usize>,
+    /// The shots and their xG
+    pub shots: Shots,
+    /// The offside calls
+    pub offside: Offside,
+    /// The current play mode, if known
+    pub playmode: Option<rcss::PlayMode>,
+    /// The periods of the game
+    pub periods: Periods,
+    /// The restarts of play, as (time, index into actions from where the chaining restarts)
+    restarts: Vec<(usize, usize)>,
+    /// The set pieces and their outcome
+    pub setpieces: SetPieces,
+    /// The goalkeeper specific stats
+    pub goalies: Goalies,
 }
 
 impl ActionsInfo {
@@ -472,6 +692,15 @@ impl ActionsInfo {
             actions: Vec::new(),
             rawactions: Vec::new(),
             handle_deferedseek: false,
+            possession: Possession::new(),
+            seqhighlight: None,
+            shots: Shots::new(),
+            offside: Offside::new(),
+            playmode: None,
+            periods: Periods::new(),
+            restarts: Vec::new(),
+            setpieces: SetPieces::new(),
+            goalies: Goalies::new(),
         }
     }
 
@@ -598,9 +827,218 @@ impl ActionsInfo {
         }
     }
 
+    /// Print the player commands related info, if any got from the playdata source.
+    fn summary_cmds_simple(&self) {
+        let mut sides = Vec::new();
+        sides.push((entities::SIDE_L, &self.teams.lpids, &self.teams.lplayers));
+        sides.push((entities::SIDE_R, &self.teams.rpids, &self.teams.rplayers));
+        for (side, pids, players) in sides {
+            for pid in pids {
+                let score = &players.get(pid).unwrap().score;
+                if (score.kickcmds == 0) && (score.dashcmds == 0) {
+                    continue;
+                }
+                let avgkickpower = if score.kickcmds > 0 { score.kickpower/score.kickcmds as f32 } else { 0.0 };
+                eprintln!("DBUG:{}:{}{:02}:Cmds:Kicks:{}:AvgKickPower:{}:Dashs:{}", MTAG, side.to_uppercase(), pid, score.kickcmds, avgkickpower, score.dashcmds);
+            }
+        }
+    }
+
+    /// Print the ball winning/losing related stats of the players, along with the team totals
+    fn summary_ballstats_simple(&self) {
+        let mut sides = Vec::new();
+        sides.push((entities::SIDE_L, &self.teams.lpids, &self.teams.lplayers));
+        sides.push((entities::SIDE_R, &self.teams.rpids, &self.teams.rplayers));
+        for (side, pids, players) in sides {
+            let mut totals = (0, 0, 0, 0);
+            for pid in pids {
+                let score = &players.get(pid).unwrap().score;
+                totals.0 += score.interceptions;
+                totals.1 += score.balllosses;
+                totals.2 += score.tackleswon;
+                totals.3 += score.tacklesfailed;
+                eprintln!("DBUG:{}:{}{:02}:BallStats:Intercepts:{}:Losses:{}:TacklesWon:{}:TacklesFailed:{}", MTAG, side.to_uppercase(), pid, score.interceptions, score.balllosses, score.tackleswon, score.tacklesfailed);
+            }
+            eprintln!("DBUG:{}:{}:BallStats:Intercepts:{}:Losses:{}:TacklesWon:{}:TacklesFailed:{}", MTAG, side.to_uppercase(), totals.0, totals.1, totals.2, totals.3);
+        }
+    }
+
     pub fn summary(&self, inc_cardscore: bool) {
         self.summary_score_asciiart(inc_cardscore);
         self.summary_score_simple(inc_cardscore);
+        self.summary_cmds_simple();
+        self.summary_ballstats_simple();
+        for sline in self.summary_periods().lines() {
+            eprintln!("INFO:{}:{}", MTAG, sline);
+        }
+        for sline in self.summary_goalies().lines() {
+            eprintln!("INFO:{}:{}", MTAG, sline);
+        }
+    }
+
+    /// A single line summary wrt the specified player
+    pub fn summary_player(&self, side: char, playerid: &str, inc_cardscore: bool) -> String {
+        let score = &self.teams.get_player(side, playerid).score;
+        let mut speriods = String::new();
+        for i in 0..self.periods.periods.len() {
+            let pstats = self.player_period_stats(side, playerid, i);
+            speriods.push_str(&format!(",{}:{:.2}", self.periods.periods[i].kind, pstats.0));
+        }
+        let avgkickpower = if score.kickcmds > 0 { score.kickpower/score.kickcmds as f32 } else { 0.0 };
+        format!("{}{:02}:Score:{:.2}:Kicks:{}:Tackles:{}:Catchs:{}:Dist:{:.2}:Card:{}:Poss:{}:Intercepts:{}:Losses:{}:TacklesWon:{}:TacklesFailed:{}:KickCmds:{}:AvgKickPower:{:.1}:DashCmds:{}:KicksConfirmed:{}:KicksAttributed:{}:Periods:{}",
+            side.to_uppercase(), playerid, score.score(inc_cardscore), score.kicks, score.tackles, score.catchs, score.dist, score.card, self.possession.player(side, playerid),
+            score.interceptions, score.balllosses, score.tackleswon, score.tacklesfailed,
+            score.kickcmds, avgkickpower, score.dashcmds, score.kicksconfirmed, score.kicksattributed, speriods.trim_start_matches(','))
+    }
+
+    /// The score (including card penalties), kicks, tackles and catchs of the
+    /// specified player, during the given period
+    fn player_period_stats(&self, side: char, playerid: &str, period: usize) -> (f32, usize, usize, usize) {
+        return self.teams.get_player(side, playerid).score.period(period);
+    }
+
+    /// The score, kicks, tackles, catchs, shots, shots on target, goals and xG
+    /// of the given side, during the given period
+    fn team_period_stats(&self, side: char, period: usize) -> (f32, usize, usize, usize, usize, usize, usize, f32) {
+        let pids = if side == entities::SIDE_L { &self.teams.lpids } else { &self.teams.rpids };
+        let (mut pscore, mut kicks, mut tackles, mut catchs) = (0.0, 0, 0, 0);
+        for pid in pids {
+            let pstats = self.player_period_stats(side, pid, period);
+            pscore += pstats.0;
+            kicks += pstats.1;
+            tackles += pstats.2;
+            catchs += pstats.3;
+        }
+        let (start, end) = self.periods.range(period);
+        let (mut shots, mut ontarget, mut goals, mut xg) = (0, 0, 0, 0.0);
+        for shot in self.shots.shots.iter().filter(|shot| (shot.side == side) && (shot.time >= start) && (shot.time < end)) {
+            shots += 1;
+            if shot.ontarget {
+                ontarget += 1;
+            }
+            if shot.outcome == ShotOutcome::Goal {
+                goals += 1;
+            }
+            xg += shot.xg;
+        }
+        return (pscore, kicks, tackles, catchs, shots, ontarget, goals, xg);
+    }
+
+    /// The stats of both the teams, split wrt the periods of the game, one line per period
+    pub fn summary_periods(&self) -> String {
+        let mut ssummary = format!("Periods:{}", self.periods.periods.len());
+        for i in 0..self.periods.periods.len() {
+            let period = &self.periods.periods[i];
+            let send = period.end.map_or(String::new(), |end| end.to_string());
+            ssummary.push_str(&format!("\n{}:{}-{}:", period.kind, period.start, send));
+            for side in [entities::SIDE_L, entities::SIDE_R] {
+                let tstats = self.team_period_stats(side, i);
+                ssummary.push_str(&format!(" {}:Score:{:.2}:Kicks:{}:Tackles:{}:Catchs:{}:Shots:{}/{}:Goals:{}:xG:{:.2}",
+                    side.to_uppercase(), tstats.0, tstats.1, tstats.2, tstats.3, tstats.4, tstats.5, tstats.6, tstats.7));
+            }
+        }
+        if self.periods.penalties.len() > 0 {
+            let (ltaken, lscored) = self.periods.penalty_score(entities::SIDE_L);
+            let (rtaken, rscored) = self.periods.penalty_score(entities::SIDE_R);
+            ssummary.push_str(&format!("\nPenalties: L:{}/{} R:{}/{}", lscored, ltaken, rscored, rtaken));
+        }
+        return ssummary;
+    }
+
+    /// The summary wrt all the players, along with the ball possession of both the teams
+    pub fn summary_json(&self, inc_cardscore: bool) -> JValue {
+        let mut jteams = Vec::new();
+        let mut sides = Vec::new();
+        sides.push((entities::SIDE_L, &self.teams.lpids, &self.teams.lplayers, &self.possession.lteam));
+        sides.push((entities::SIDE_R, &self.teams.rpids, &self.teams.rplayers, &self.possession.rteam));
+        for (side, pids, players, teamposs) in sides {
+            let mut jplayers = Vec::new();
+            for pid in pids {
+                let score = &players.get(pid).unwrap().score;
+                jplayers.push(JValue::Obj(vec![
+                    ("id".to_string(), JValue::Str(pid.to_string())),
+                    ("score".to_string(), JValue::Num(score.score(inc_cardscore) as f64)),
+                    ("kicks".to_string(), JValue::Num(score.kicks as f64)),
+                    ("tackles".to_string(), JValue::Num(score.tackles as f64)),
+                    ("catchs".to_string(), JValue::Num(score.catchs as f64)),
+                    ("dist".to_string(), JValue::Num(score.dist as f64)),
+                    ("card".to_string(), JValue::Str(score.card.to_string())),
+                    ("kickcmds".to_string(), JValue::Num(score.kickcmds as f64)),
+                    ("kickpower".to_string(), JValue::Num(score.kickpower as f64)),
+                    ("dashcmds".to_string(), JValue::Num(score.dashcmds as f64)),
+                    ("kicks_confirmed".to_string(), JValue::Num(score.kicksconfirmed as f64)),
+                    ("kicks_attributed".to_string(), JValue::Num(score.kicksattributed as f64)),
+                    ("interceptions".to_string(), JValue::Num(score.interceptions as f64)),
+                    ("balllosses".to_string(), JValue::Num(score.balllosses as f64)),
+                    ("tackles_won".to_string(), JValue::Num(score.tackleswon as f64)),
+                    ("tackles_failed".to_string(), JValue::Num(score.tacklesfailed as f64)),
+                    ("possession".to_string(), JValue::Num(self.possession.player(side, pid) as f64)),
+                    ("periods".to_string(), JValue::Arr((0..self.periods.periods.len()).map(|i| {
+                        let pstats = self.player_period_stats(side, pid, i);
+                        JValue::Obj(vec![
+                            ("period".to_string(), JValue::Str(self.periods.periods[i].kind.to_string())),
+                            ("score".to_string(), JValue::Num(pstats.0 as f64)),
+                            ("kicks".to_string(), JValue::Num(pstats.1 as f64)),
+                            ("tackles".to_string(), JValue::Num(pstats.2 as f64)),
+                            ("catchs".to_string(), JValue::Num(pstats.3 as f64)),
+                        ])
+                    }).collect())),
+                ]));
+            }
+            jteams.push(JValue::Obj(vec![
+                ("side".to_string(), JValue::Str(side.to_string())),
+                ("balltime".to_string(), JValue::Num(teamposs.time as f64)),
+                ("shots".to_string(), JValue::Num(self.shots.stats(side).0 as f64)),
+                ("shots_ontarget".to_string(), JValue::Num(self.shots.stats(side).1 as f64)),
+                ("xg".to_string(), JValue::Num(self.shots.stats(side).3 as f64)),
+                ("possession".to_string(), JValue::Obj(vec![
+                    ("periods".to_string(), JValue::Arr(teamposs.periods.iter().map(|t| JValue::Num(*t as f64)).collect())),
+                    ("thirds".to_string(), JValue::Obj(possession::THIRD_NAMES.iter().zip(teamposs.thirds.iter()).map(|(n, t)| (n.to_lowercase(), JValue::Num(*t as f64))).collect())),
+                ])),
+                ("periods".to_string(), JValue::Arr((0..self.periods.periods.len()).map(|i| {
+                    let tstats = self.team_period_stats(side, i);
+                    JValue::Obj(vec![
+                        ("period".to_string(), JValue::Str(self.periods.periods[i].kind.to_string())),
+                        ("score".to_string(), JValue::Num(tstats.0 as f64)),
+                        ("kicks".to_string(), JValue::Num(tstats.1 as f64)),
+                        ("tackles".to_string(), JValue::Num(tstats.2 as f64)),
+                        ("catchs".to_string(), JValue::Num(tstats.3 as f64)),
+                        ("shots".to_string(), JValue::Num(tstats.4 as f64)),
+                        ("shots_ontarget".to_string(), JValue::Num(tstats.5 as f64)),
+                        ("goals".to_string(), JValue::Num(tstats.6 as f64)),
+                        ("xg".to_string(), JValue::Num(tstats.7 as f64)),
+                    ])
+                }).collect())),
+                ("penalties".to_string(), JValue::Num(self.periods.penalty_score(side).1 as f64)),
+                ("goalie".to_string(), goalie_json(self.goalies.get(side), self.goalie_saves(side))),
+                ("setpieces".to_string(), {
+                    let (cnt, goals, shots, retained, lost) = self.setpieces.stats(side, None);
+                    JValue::Obj(vec![
+                        ("count".to_string(), JValue::Num(cnt as f64)),
+                        ("goals".to_string(), JValue::Num(goals as f64)),
+                        ("shots".to_string(), JValue::Num(shots as f64)),
+                        ("retained".to_string(), JValue::Num(retained as f64)),
+                        ("lost".to_string(), JValue::Num(lost as f64)),
+                    ])
+                }),
+                ("players".to_string(), JValue::Arr(jplayers)),
+            ]));
+        }
+        JValue::Obj(vec![
+            ("inc_cardscore".to_string(), JValue::Bool(inc_cardscore)),
+            ("deadballtime".to_string(), JValue::Num(self.possession.deadtime as f64)),
+            ("contestedtime".to_string(), JValue::Num(self.possession.contestedtime as f64)),
+            ("periods".to_string(), JValue::Arr(self.periods.periods.iter().map(|period| JValue::Obj(vec![
+                ("period".to_string(), JValue::Str(period.kind.to_string())),
+                ("start".to_string(), JValue::Num(period.start as f64)),
+                ("end".to_string(), period.end.map_or(JValue::Null, |end| JValue::Num(end as f64))),
+            ])).collect())),
+            ("teams".to_string(), JValue::Arr(jteams)),
+            ("sequences".to_string(), JValue::Arr(self.possession.sequences.iter().map(|seq| seq_json(seq, &self.periods)).collect())),
+            ("shots".to_string(), JValue::Arr(self.shots.shots.iter().map(|shot| shot_json(shot, &self.periods)).collect())),
+            ("offsides".to_string(), JValue::Arr(self.offside.calls.iter().map(|call| offside_json(call, &self.periods)).collect())),
+            ("setpieces".to_string(), JValue::Arr(self.setpieces.setpieces.iter().map(|sp| setpiece_json(sp, &self.periods)).collect())),
+        ])
     }
 
 }
@@ -617,6 +1055,16 @@ pub enum HAReturn {
 
 impl ActionsInfo {
 
+    /// Score the kick which restarts play, ie a kick off (including after a goal)
+    /// or a penalty kick. This is like a no effort kick potentially, so low score,
+    /// and the actions before it are not looked at, so it is never penalised.
+    fn handle_kickoff(&mut self, curactd: &mut ActionData) -> HAReturn {
+        let score = curactd.action.scoring();
+        let pscore = score.0 * score.2 * SCORE_SELF_PASS_RATIO;
+        self.teams.pscore_update(curactd.time, curactd.side, &curactd.playerid, pscore);
+        return HAReturn::Done(true);
+    }
+
     /// Score wrt good and bad passes (ie btw members of same team or not)
     /// Lower score wrt self pass (ie same player keeping the ball going)
     /// Lower scoring for kick after a goal
@@ -639,26 +1087,29 @@ impl ActionsInfo {
                     }
                     self.teams.pscore_update(curactd.time, prevactd.side, &prevactd.playerid, ppscore);
                     self.teams.pscore_update(curactd.time, curactd.side, &curactd.playerid, cpscore);
+                    if prevactd.action == AIAction::Tackle {
+                        self.teams.ballstat_increment(prevactd.side, &prevactd.playerid, BallStat::TackleWon);
+                    }
                 } else {
                     let pscore = score.0 * score.3;
                     self.teams.pscore_update(curactd.time, prevactd.side, &prevactd.playerid, pscore);
                     let pscore = score.0 * score.4;
                     self.teams.pscore_update(curactd.time, curactd.side, &curactd.playerid, pscore);
+                    if prevactd.action == AIAction::Tackle {
+                        self.teams.ballstat_increment(prevactd.side, &prevactd.playerid, BallStat::TackleFailed);
+                    } else {
+                        self.teams.ballstat_turnover(prevactd.side, &prevactd.playerid, curactd.side, &curactd.playerid);
+                    }
                 }
                 return HAReturn::Done(true);
             },
             AIAction::Goal => {
                 if prevactd.side == curactd.side {
-                    // After a side gets a goal, the otherside should kick
-                    // The person who has kicked currently has taken ball from the other side immidiately itself!
-                    // This shouldnt occur normally???
-                    panic!("DBUG:{}:HandleKick:Goal{}->Kick{}, wrt same side???", MTAG, prevactd, curactd);
-                } else {
-                    // This is like a no effort kick potentially, ie after a goal, so low score
-                    let pscore = score.0 * score.2 * SCORE_SELF_PASS_RATIO;
-                    self.teams.pscore_update(curactd.time, curactd.side, &curactd.playerid, pscore);
-                    return HAReturn::Done(true);
+                    // After a side gets a goal, the otherside should kick off, however if the
+                    // kick off wasnt seen (or a half got over in between), the same side could kick.
+                    eprintln!("WARN:{}:HandleKick:Goal{}->Kick{}, wrt same side, treating as kick off", MTAG, prevactd, curactd);
                 }
+                return self.handle_kickoff(curactd);
             },
         }
     }
@@ -754,6 +1205,7 @@ impl ActionsInfo {
                 } else {
                     ppscore = score.0 * score.3;
                     cpscore = score.0 * score.4;
+                    self.teams.ballstat_turnover(prevactd.side, &prevactd.playerid, curactd.side, &curactd.playerid);
                 }
                 self.teams.pscore_update(curactd.time, prevactd.side, &prevactd.playerid, ppscore);
                 self.teams.pscore_update(curactd.time, curactd.side, &curactd.playerid, cpscore);
@@ -775,6 +1227,7 @@ impl ActionsInfo {
                 } else {
                     ppscore = score.0 * score.3;
                     cpscore = score.0 * score.4;
+                    self.teams.ballstat_increment(prevactd.side, &prevactd.playerid, BallStat::TackleFailed);
                 }
                 self.teams.pscore_update(curactd.time, prevactd.side, &prevactd.playerid, ppscore);
                 self.teams.pscore_update(curactd.time, curactd.side, &curactd.playerid, cpscore);
@@ -804,9 +1257,18 @@ impl ActionsInfo {
                 if prevactd.side == curactd.side {
                     ppscore = score.0 * score.1;
                     cpscore = score.0 * score.2;
+                    if prevactd.action == AIAction::Tackle {
+                        self.teams.ballstat_increment(prevactd.side, &prevactd.playerid, BallStat::TackleWon);
+                    }
                 } else {
                     ppscore = score.0 * score.3;
                     cpscore = score.0 * score.4;
+                    if prevactd.action == AIAction::Tackle {
+                        self.teams.ballstat_increment(prevactd.side, &prevactd.playerid, BallStat::TackleFailed);
+                    } else if !self.shots.catch_saves(curactd.side) {
+                        // A save of a shot on target is neither a loss nor a interception
+                        self.teams.ballstat_turnover(prevactd.side, &prevactd.playerid, curactd.side, &curactd.playerid);
+                    }
                 }
                 self.teams.pscore_update(curactd.time, prevactd.side, &prevactd.playerid, ppscore);
                 self.teams.pscore_update(curactd.time, curactd.side, &curactd.playerid, cpscore);
@@ -820,13 +1282,6 @@ impl ActionsInfo {
         }
     }
 
-    fn balltime_update(&mut self, prevactd: &ActionData, curactd: &ActionData, lookbackcnt: usize) {
-        if lookbackcnt != 1 {
-            return;
-        }
-        self.teams.balltime_update(&prevactd, &curactd);
-    }
-
     /// Handle the passed action by
     /// * looking at the sequence leading to it and scoring players accordingly.
     ///   * normally 1 step back,
@@ -839,11 +1294,28 @@ impl ActionsInfo {
             self.handle_deferedseek = false;
         }
         curactd.print(false);
+        self.teams.period = self.periods.period_of(curactd.time);
+        self.teams.cmds_update(curactd.side, &curactd.playerid, &curactd.cmds);
+        // The kick command (if known) confirms a kick seen in the state, or else attributes
+        // a kick, if the state neither indicated a kick nor a failed kick.
+        if curactd.kick.is_some() {
+            match curactd.action {
+                AIAction::Kick => self.teams.kickcmd_update(curactd.side, &curactd.playerid, false),
+                AIAction::None if !curactd.kickfault => {
+                    curactd.action = AIAction::Kick;
+                    self.teams.kickcmd_update(curactd.side, &curactd.playerid, true);
+                    ldebug!(&format!("DBUG:{}:KickAttributed:{}", MTAG, curactd));
+                },
+                _ => (),
+            }
+        }
         let mut bupdate_actions = false;
         let mut bupdate_rawactions = true;
         let mut bupdate_dist = true;
         let mut lookbackcnt = 0;
-        for i in (0..self.actions.len()).rev() {
+        // Dont look beyond the latest restart of play
+        let chainstart = self.restarts.last().map_or(0, |restart| restart.1);
+        for i in (chainstart..self.actions.len()).rev() {
             lookbackcnt += 1;
             if lookbackcnt > HA_LOOKBACK_MAX {
                 break;
@@ -855,28 +1327,24 @@ impl ActionsInfo {
                     break;
                 },
                 AIAction::Kick => {
-                    self.balltime_update(&prevactd, &curactd, lookbackcnt);
                     if let HAReturn::Done(save) = self.handle_kick(&mut curactd, &prevactd) {
                         bupdate_actions = save;
                         break;
                     }
                 },
                 AIAction::Tackle => {
-                    self.balltime_update(&prevactd, &curactd, lookbackcnt);
                     if let HAReturn::Done(save) = self.handle_tackle(&mut curactd, &prevactd) {
                         bupdate_actions = save;
                         break;
                     }
                 },
                 AIAction::Catch => {
-                    self.balltime_update(&prevactd, &curactd, lookbackcnt);
                     if let HAReturn::Done(save) = self.handle_catch(&mut curactd, &prevactd) {
                         bupdate_actions = save;
                         break;
                     }
                 },
                 AIAction::Goal => {
-                    self.balltime_update(&prevactd, &curactd, lookbackcnt);
                     // Allow 1st lookback check to filter out this goal if required.
                     // Else If one goes beyond 1st lookback, then the goal is always saved into actions.
                     bupdate_dist = false;
@@ -892,9 +1360,12 @@ impl ActionsInfo {
                 },
             }
         }
-        // Handle the special case of 1st action
-        if self.actions.len() == 0 {
+        // Handle the special case of 1st action, wrt the game or a restart of play
+        if self.actions.len() == chainstart {
             if let AIAction::Kick = curactd.action {
+                if chainstart > 0 {
+                    self.handle_kickoff(&mut curactd);
+                }
                 bupdate_actions = true;
             } else if let AIAction::None = curactd.action {
                 bupdate_rawactions = false;
@@ -910,6 +1381,21 @@ impl ActionsInfo {
         if bupdate_rawactions {
             self.teams.count_increment(curactd.side, &curactd.playerid, curactd.action.clone());
             ldebug!(&format!("DBUG:{}:RawActions:{}", MTAG, curactd));
+            // The penalty shoot-out kicks are neither shots in play nor passes
+            if !self.periods.in_shootout() {
+                let players = self.teams.positions();
+                let opponents = players.iter().filter(|p| p.0 != curactd.side).map(|p| p.2).collect();
+                let bsave = (curactd.action == AIAction::Catch) && self.shots.catch_saves(curactd.side);
+                self.shots.handle_action(curactd.time, curactd.side, &curactd.playerid, &curactd.action, curactd.pos, &opponents);
+                if curactd.action == AIAction::Kick {
+                    self.offside.handle_kick(curactd.time, curactd.side, &curactd.playerid, &players, curactd.pos);
+                }
+                self.setpieces.handle_action(curactd.side, &curactd.playerid, &curactd.action);
+                self.goalies.handle_action(curactd.time, curactd.side, &curactd.playerid, &curactd.action, curactd.pos);
+                if bsave {
+                    self.goalies.handle_save(curactd.time, curactd.side, &curactd.playerid);
+                }
+            }
             self.rawactions.push(curactd);
         }
     }
@@ -919,6 +1405,7 @@ impl ActionsInfo {
 impl ActionsInfo {
 
     pub fn handle_card(&mut self, time: usize, side: char, playerid: &str, card: playdata::Card) {
+        self.teams.period = self.periods.period_of(time);
         self.teams.card_issued(time, side, playerid, card);
     }
 
@@ -947,6 +1434,14 @@ impl ActionsInfo {
             }
             self.rawactions.pop();
         }
+        self.shots.seek_to(timecounter);
+        self.offside.seek_to(timecounter);
+        self.periods.seek_to(timecounter);
+        self.teams.period_counts_recount(&self.rawactions, &self.periods);
+        self.setpieces.seek_to(timecounter);
+        self.goalies.seek_to(timecounter);
+        self.restarts.retain(|restart| restart.0 < timecounter);
+        self.playmode = None;
         let eacnt = self.actions.len();
         let eracnt = self.rawactions.len();
         eprintln!("DBUG:{}:SkipAfterInc:A:{}->{}:RA:{}->{}", MTAG, sacnt, eacnt, sracnt, eracnt);
@@ -968,6 +1463,32 @@ impl ActionsInfo {
 
 }
 
+#[derive(Debug, Clone, PartialEq)]
+/// Select a possession sequence to highlight
+pub enum SeqSelect {
+    None,
+    Index(usize),
+    /// Relative to the currently highlighted one, or the latest if none.
+    Step(isize),
+}
+
+impl SeqSelect {
+
+    /// Parse none, +N/-N (relative step) or N (index)
+    pub fn parse(ssel: &str) -> Result<SeqSelect, String> {
+        if ssel == "none" {
+            return Ok(SeqSelect::None);
+        }
+        if ssel.starts_with('+') || ssel.starts_with('-') {
+            let delta = ssel.trim_start_matches('+').parse::<isize>().map_err(|_| format!("Bad sequence step [{}]", ssel))?;
+            return Ok(SeqSelect::Step(delta));
+        }
+        let i = ssel.parse::<usize>().map_err(|_| format!("Bad sequence index [{}]", ssel))?;
+        Ok(SeqSelect::Index(i))
+    }
+
+}
+
 #[derive(Debug, PartialEq)]
 pub enum SummaryPlayerType {
     ScoreDeltas,
@@ -1063,13 +1584,407 @@ impl ActionsInfo {
 
 impl ActionsInfo {
 
+    /// Update the ball possession wrt the current cycle, using the
+    /// latest known positions of the players.
+    pub fn handle_cycle(&mut self, time: usize, state: &Option<rcss::PlayMode>, ball: Pos) {
+        self.handle_playmode(time, state);
+        let players = self.teams.positions();
+        let mut catchside = None;
+        if let Some(actd) = self.rawactions.last() {
+            if (actd.time == time) && (actd.action == AIAction::Catch) {
+                catchside = Some(actd.side);
+            }
+        }
+        let period = self.periods.period_of(time);
+        self.possession.update(time, state, ball, &players, catchside, period);
+        self.shots.handle_cycle(time, state, ball);
+        self.offside.handle_cycle(time, state, ball);
+        self.setpieces.handle_cycle(time, state, ball, &self.shots.shots, &self.periods);
+        self.goalies.handle_cycle(time, state, &players);
+    }
+
+    /// Keep track of the current play mode, noting changes which matter wrt the
+    /// game flow, ie set pieces, end of a period and penalty shoot-outs.
+    /// Inturn keep track of the periods and the restarts of play.
+    fn handle_playmode(&mut self, time: usize, state: &Option<rcss::PlayMode>) {
+        let mode = match state {
+            Some(mode) => mode,
+            None => return,
+        };
+        if self.playmode.as_ref() == Some(mode) {
+            return;
+        }
+        let kind = if mode.set_piece().is_some() {
+            "SetPiece"
+        } else if mode.is_period_end() {
+            "PeriodEnd"
+        } else if mode.is_penalty_shootout() {
+            "PenaltyShootout"
+        } else {
+            "Mode"
+        };
+        ldebug!(&format!("DBUG:{}:PlayMode:{}:{}:{}:{:?}", MTAG, time, kind, mode, mode.side()));
+        if self.periods.handle_mode(time, mode) {
+            self.restarts.push((time, self.actions.len()));
+        }
+        self.playmode = Some(mode.clone());
+    }
+
+    /// The offside lines (x in rcss space) wrt the left and right teams, if known
+    pub fn offside_lines(&self) -> (Option<f32>, Option<f32>) {
+        let players = self.teams.positions();
+        return (Offside::line(entities::SIDE_L, &players), Offside::line(entities::SIDE_R, &players));
+    }
+
+    /// List the offside calls, along with the count of calls wrt each player
+    pub fn summary_offsides(&self) -> String {
+        let mut ssummary = format!("Offsides:{}", self.offside.calls.len());
+        for side in [entities::SIDE_L, entities::SIDE_R] {
+            ssummary.push_str(&format!("\n{}:", side.to_uppercase()));
+            for (pid, cnt) in self.offside.counts(side) {
+                ssummary.push_str(&format!(" {}:{}", pid, cnt));
+            }
+        }
+        for call in &self.offside.calls {
+            ssummary.push_str(&format!("\n{}:{}:Pass:{}:{}->{}", call.time, call.side.to_uppercase(),
+                call.passtime.map_or("?".to_string(), |t| t.to_string()),
+                call.passer.as_deref().unwrap_or("?"), call.playerid.as_deref().unwrap_or("?")));
+        }
+        return ssummary;
+    }
+
     pub fn show_ballpossession(&self, sx: &mut SdlX) {
-        let lposs = ((self.teams.lballtime/(self.teams.lballtime + self.teams.rballtime))*100.0).round();
-        let rposs = 100.0-lposs;
+        let (lposs, rposs) = Possession::percentages(self.possession.lteam.time, self.possession.rteam.time);
         let lpos = entities::MSG_LBALLPOSS_POS;
         let rpos = entities::MSG_RBALLPOSS_POS;
         sx.n_string(lpos.0, lpos.1, &lposs.to_string(), Color::WHITE);
         sx.n_string(rpos.0, rpos.1, &rposs.to_string(), Color::WHITE);
     }
 
+    /// List the last cnt possession sequences, along with their index
+    pub fn summary_sequences(&self, cnt: usize) -> String {
+        let seqs = &self.possession.sequences;
+        let mut ssummary = format!("Sequences:{}", seqs.len());
+        for i in seqs.len().saturating_sub(cnt)..seqs.len() {
+            ssummary.push_str(&format!("\n{}:{}", i, seqs[i]));
+        }
+        return ssummary;
+    }
+
+    /// Highlight the specified possession sequence, or
+    /// step thro them relative to the currently highlighted one.
+    /// Returns the sequence highlighted if any.
+    pub fn highlight_sequence(&mut self, seqsel: SeqSelect) -> Option<&Sequence> {
+        let seqcnt = self.possession.sequences.len() as isize;
+        self.seqhighlight = match seqsel {
+            SeqSelect::None => None,
+            SeqSelect::Index(i) => Some(i),
+            SeqSelect::Step(delta) => {
+                let cur = self.seqhighlight.map_or(seqcnt, |i| i as isize);
+                Some((cur + delta).max(0) as usize)
+            },
+        };
+        if let Some(i) = self.seqhighlight {
+            if (i as isize) >= seqcnt {
+                self.seqhighlight = None;
+            }
+        }
+        return self.seqhighlight.map(|i| &self.possession.sequences[i]);
+    }
+
+    /// Draw the path of the ball wrt the highlighted possession sequence, if any.
+    pub fn draw_sequence(&self, sx: &mut SdlX) {
+        let seq = match self.seqhighlight.and_then(|i| self.possession.sequences.get(i)) {
+            Some(seq) => seq,
+            None => return,
+        };
+        let color = if seq.side == entities::SIDE_L { HL_LCOLOR } else { HL_RCOLOR };
+        for i in 1..seq.path.len() {
+            let (p1, p2) = (seq.path[i-1], seq.path[i]);
+            sx.nn_thick_line(p1.0, p1.1, p2.0, p2.1, SEQ_LINE_WIDTH, color);
+        }
+        for npos in &seq.path {
+            sx.n_arc(npos.0, npos.1, SEQ_TOUCH_RADIUS, 0.0, 1.0, 2, color);
+        }
+        let spos = seq.path[0];
+        sx.n_string(spos.0, spos.1 - 2.0*SEQ_TOUCH_RADIUS, &format!("{}:{}-{}:{}", seq.side.to_uppercase(), seq.start_time, seq.end_time, seq.outcome), color);
+    }
+
+    /// A single line summary of the shots and xG of both the teams
+    pub fn summary_shots(&self) -> String {
+        let mut ssummary = String::from("Shots");
+        for side in [entities::SIDE_L, entities::SIDE_R] {
+            let (cnt, ontarget, goals, xg) = self.shots.stats(side);
+            ssummary.push_str(&format!(":{}:{}/{}:Goals:{}:xG:{:.2}", side.to_uppercase(), ontarget, cnt, goals, xg));
+        }
+        return ssummary;
+    }
+
+    /// Draw the shot map, ie the shots at the positions they were taken from.
+    /// The size of the marker corresponds to the xG, goals are filled in
+    /// and shots on target have a thicker marker.
+    pub fn draw_shotmap(&self, sx: &mut SdlX) {
+        for shot in &self.shots.shots {
+            let color = if shot.side == entities::SIDE_L { HL_LCOLOR } else { HL_RCOLOR };
+            let nrad = SHOT_RADIUS*shot.xg.sqrt();
+            let width = if shot.ontarget { 3 } else { 1 };
+            sx.n_arc(shot.npos.0, shot.npos.1, nrad, 0.0, 1.0, width, color);
+            if shot.outcome == ShotOutcome::Goal {
+                sx.wc.set_draw_color(color);
+                let sside = (sx.n2s.d2o_dx(nrad).round() as u32).max(2);
+                sx.ns_fill_rect_mid(shot.npos.0, shot.npos.1, sside, sside);
+            }
+        }
+    }
+
+    /// Plot the cumulative xG of both the teams over time, in the given window ((left,bottom),(width,height))
+    pub fn summary_xg_sdl(&self, sx: &mut SdlX, maxtime: usize, win: XRect) {
+        use crate::sdlx::PlotType;
+        let ((wx, wy), (ww, wh)) = win;
+        let ymax = self.shots.stats(entities::SIDE_L).3.max(self.shots.stats(entities::SIDE_R).3).max(1.0);
+        for side in [entities::SIDE_L, entities::SIDE_R] {
+            let mut vxg = self.shots.xg_cumul(side);
+            let xg = vxg.last().unwrap().1;
+            vxg.push((maxtime, xg));
+            let stag = format!("{}xG:{:.2}", side.to_uppercase(), xg);
+            sx.n_plot_uf(wx, wy, ww, wh, &vxg, 0.0, maxtime as f32, 0.0, ymax, None, &stag, PlotType::Lines);
+        }
+    }
+
+    /// List the set pieces of both the teams, along with the outcomes and the
+    /// success rate wrt each type of set piece and each taker
+    pub fn summary_setpieces(&self) -> String {
+        let mut ssummary = format!("SetPieces:{}", self.setpieces.setpieces.len());
+        for side in [entities::SIDE_L, entities::SIDE_R] {
+            for line in setpieces_lines(&self.setpieces, side) {
+                ssummary.push_str(&format!("\n{}", line));
+            }
+        }
+        return ssummary;
+    }
+
+    /// Mark the set pieces on the pitch at the location they were taken from,
+    /// filled in if successful
+    pub fn draw_setpieces(&self, sx: &mut SdlX) {
+        for sp in &self.setpieces.setpieces {
+            let color = if sp.side == entities::SIDE_L { HL_LCOLOR } else { HL_RCOLOR };
+            sx.n_arc(sp.npos.0, sp.npos.1, SETPIECE_RADIUS, 0.0, 1.0, 2, color);
+            if sp.outcome.success() {
+                sx.wc.set_draw_color(color);
+                let sside = (sx.n2s.d2o_dx(SETPIECE_RADIUS).round() as u32).max(2);
+                sx.ns_fill_rect_mid(sp.npos.0, sp.npos.1, sside, sside);
+            }
+        }
+    }
+
+    /// Show a panel with the latest set pieces and the success rates of both the teams
+    pub fn summary_setpieces_sdl(&self, sx: &mut SdlX) {
+        let mut vlines = vec![format!("SetPieces:{}", self.setpieces.setpieces.len())];
+        for sp in self.setpieces.setpieces.iter().rev().take(SETPIECES_PANEL_LATEST) {
+            vlines.push(format!("  {}", sp));
+        }
+        for side in [entities::SIDE_L, entities::SIDE_R] {
+            vlines.extend(setpieces_lines(&self.setpieces, side));
+        }
+        let vlines: Vec<&str> = vlines.iter().map(|s| s.as_str()).collect();
+        sx.n_msgbox(SETPIECES_RECT, vlines, Color::BLACK);
+    }
+
+    /// The number of shots on target faced by the goalie of the given side and
+    /// the number of them which were saved, ie caught by the goalie
+    fn goalie_saves(&self, side: char) -> (usize, usize) {
+        let ontarget = self.shots.shots.iter().filter(|shot| (shot.side != side) && shot.ontarget).count();
+        return (ontarget, self.goalies.get(side).saves);
+    }
+
+    /// The stats of the goalies of both the teams, a few lines per goalie
+    pub fn summary_goalies(&self) -> String {
+        let mut ssummary = String::from("Goalies");
+        for side in [entities::SIDE_L, entities::SIDE_R] {
+            for line in goalie_lines(self.goalies.get(side), side, self.goalie_saves(side)) {
+                ssummary.push_str(&format!("\n{}", line));
+            }
+        }
+        return ssummary;
+    }
+
+    /// Show a panel with the stats of the goalies of both the teams
+    pub fn summary_goalies_sdl(&self, sx: &mut SdlX) {
+        let mut vlines = vec!["Goalies".to_string()];
+        for side in [entities::SIDE_L, entities::SIDE_R] {
+            vlines.extend(goalie_lines(self.goalies.get(side), side, self.goalie_saves(side)));
+        }
+        let vlines: Vec<&str> = vlines.iter().map(|s| s.as_str()).collect();
+        sx.n_msgbox(GOALIES_RECT, vlines, Color::BLACK);
+    }
+
+    /// Show a panel with the possession breakdown wrt periods, pitch thirds and players
+    pub fn summary_possession_sdl(&self, sx: &mut SdlX) {
+        let poss = &self.possession;
+        let mut vlines = Vec::new();
+        let (lposs, rposs) = Possession::percentages(poss.lteam.time, poss.rteam.time);
+        vlines.push(format!("Possession  L:{}%  R:{}%", lposs, rposs));
+        vlines.push(format!("  DeadBall:{}  Contested:{}", poss.deadtime, poss.contestedtime));
+        let nperiods = poss.lteam.periods.len().max(poss.rteam.periods.len()).min(self.periods.periods.len());
+        for period in 0..nperiods {
+            let (lposs, rposs) = Possession::percentages(poss.lteam.period(period), poss.rteam.period(period));
+            vlines.push(format!("{}  L:{}%  R:{}%", self.periods.periods[period].kind, lposs, rposs));
+        }
+        for (side, teamposs) in [(entities::SIDE_L, &poss.lteam), (entities::SIDE_R, &poss.rteam)] {
+            vlines.push(format!("{} Thirds {}", side.to_uppercase(), thirds_string(teamposs)));
+            let mut splayers = format!("{} Top", side.to_uppercase());
+            for (pid, ptime) in poss.players_sorted(side).iter().take(5) {
+                splayers.push_str(&format!(" {}:{}%", pid, pct(*ptime, teamposs.time)));
+            }
+            vlines.push(splayers);
+        }
+        let vlines: Vec<&str> = vlines.iter().map(|s| s.as_str()).collect();
+        sx.n_msgbox(POSSESSION_RECT, vlines, Color::BLACK);
+    }
+
+}
+
+/// Percentage of part wrt total, 0 if no total
+fn pct(part: f32, total: f32) -> f32 {
+    if total <= 0.0 {
+        return 0.0;
+    }
+    return ((part/total)*100.0).round();
+}
+
+/// The team's possession split across the pitch thirds, in percentage
+fn thirds_string(teamposs: &TeamPossession) -> String {
+    let mut sthirds = String::new();
+    for i in 0..possession::THIRD_NAMES.len() {
+        sthirds.push_str(&format!(" {}:{}%", possession::THIRD_NAMES[i], pct(teamposs.thirds[i], teamposs.time)));
+    }
+    return sthirds.trim().to_string();
+}
+
+/// The success rates wrt the set pieces of the given side, ie overall, wrt each
+/// type of set piece and wrt each taker
+fn setpieces_lines(setpieces: &SetPieces, side: char) -> Vec<String> {
+    let mut vlines = Vec::new();
+    let (cnt, goals, shots, retained, lost) = setpieces.stats(side, None);
+    let success = (goals+shots+retained) as f32;
+    vlines.push(format!("{}:{}:Goals:{}:Shots:{}:Retained:{}:Lost:{}:Success:{}%", side.to_uppercase(), cnt, goals, shots, retained, lost, pct(success, cnt as f32)));
+    for kind in setpieces.kinds(side) {
+        let (cnt, goals, shots, retained, _lost) = setpieces.stats(side, Some(&kind));
+        let success = (goals+shots+retained) as f32;
+        vlines.push(format!("  {}:{}:Success:{}%", kind, cnt, pct(success, cnt as f32)));
+    }
+    let mut stakers = String::from("  Takers");
+    for (pid, cnt, success) in setpieces.takers(side) {
+        stakers.push_str(&format!(" {}:{}/{}", pid, success, cnt));
+    }
+    vlines.push(stakers);
+    return vlines;
+}
+
+/// The stats of the given goalie, ie saves, catches, goals conceded, position,
+/// sweeping and distribution
+fn goalie_lines(goalie: &Goalie, side: char, saves: (usize, usize)) -> Vec<String> {
+    let spid = goalie.playerid.as_deref().unwrap_or("?");
+    let mut vlines = Vec::new();
+    vlines.push(format!("{}{}:Saves:{}/{}:Catches:{}:Failed:{}:Conceded:{}",
+        side.to_uppercase(), spid, saves.1, saves.0, goalie.catches, goalie.failedcatches, goalie.goalsconceded));
+    vlines.push(format!("  GoalLineDist:Avg:{:.1}:Max:{:.1}:Sweeps:{}", goalie.dist_avg(), goalie.distmax, goalie.sweeps));
+    vlines.push(format!("  Distribution:{}:Retained:{}:Lost:{}", goalie.distkicks, goalie.distretained, goalie.distlost));
+    return vlines;
+}
+
+/// The json representation of the given goalie
+fn goalie_json(goalie: &Goalie, saves: (usize, usize)) -> JValue {
+    JValue::Obj(vec![
+        ("id".to_string(), goalie.playerid.as_ref().map_or(JValue::Null, |pid| JValue::Str(pid.to_string()))),
+        ("shots_ontarget_faced".to_string(), JValue::Num(saves.0 as f64)),
+        ("saves".to_string(), JValue::Num(saves.1 as f64)),
+        ("catches".to_string(), JValue::Num(goalie.catches as f64)),
+        ("failed_catches".to_string(), JValue::Num(goalie.failedcatches as f64)),
+        ("goals_conceded".to_string(), JValue::Num(goalie.goalsconceded as f64)),
+        ("goalline_dist_avg".to_string(), JValue::Num(goalie.dist_avg() as f64)),
+        ("goalline_dist_max".to_string(), JValue::Num(goalie.distmax as f64)),
+        ("sweeps".to_string(), JValue::Num(goalie.sweeps as f64)),
+        ("distribution".to_string(), JValue::Obj(vec![
+            ("kicks".to_string(), JValue::Num(goalie.distkicks as f64)),
+            ("retained".to_string(), JValue::Num(goalie.distretained as f64)),
+            ("lost".to_string(), JValue::Num(goalie.distlost as f64)),
+        ])),
+    ])
+}
+
+/// The json representation of the given set piece
+fn setpiece_json(sp: &SetPiece, periods: &Periods) -> JValue {
+    let pos = rcss::npos_to_rcss(sp.npos);
+    JValue::Obj(vec![
+        ("time".to_string(), JValue::Num(sp.time as f64)),
+        ("period".to_string(), JValue::Str(periods.name_of(sp.time))),
+        ("type".to_string(), JValue::Str(sp.kind.to_string())),
+        ("side".to_string(), JValue::Str(sp.side.to_string())),
+        ("taker".to_string(), sp.taker.as_ref().map_or(JValue::Null, |pid| JValue::Str(pid.to_string()))),
+        ("pos".to_string(), JValue::Arr(vec![JValue::Num(pos.0 as f64), JValue::Num(pos.1 as f64)])),
+        ("outcome".to_string(), JValue::Str(sp.outcome.to_string())),
+    ])
+}
+
+/// The json representation of the given possession sequence
+fn seq_json(seq: &Sequence, periods: &Periods) -> JValue {
+    let path = seq.path.iter().map(|npos| {
+        let pos = rcss::npos_to_rcss(*npos);
+        JValue::Arr(vec![JValue::Num(pos.0 as f64), JValue::Num(pos.1 as f64)])
+    }).collect();
+    JValue::Obj(vec![
+        ("side".to_string(), JValue::Str(seq.side.to_string())),
+        ("period".to_string(), JValue::Str(periods.name_of(seq.start_time))),
+        ("start".to_string(), JValue::Num(seq.start_time as f64)),
+        ("end".to_string(), JValue::Num(seq.end_time as f64)),
+        ("duration".to_string(), JValue::Num(seq.duration() as f64)),
+        ("start_event".to_string(), JValue::Str(seq.start_event.to_string())),
+        ("end_event".to_string(), JValue::Str(seq.end_event.to_string())),
+        ("players".to_string(), JValue::Arr(seq.players.iter().map(|pid| JValue::Str(pid.to_string())).collect())),
+        ("passes".to_string(), JValue::Num(seq.passes as f64)),
+        ("start_zone".to_string(), JValue::Str(possession::THIRD_NAMES[seq.start_zone].to_lowercase())),
+        ("end_zone".to_string(), JValue::Str(possession::THIRD_NAMES[seq.end_zone].to_lowercase())),
+        ("outcome".to_string(), JValue::Str(seq.outcome.to_string())),
+        ("path".to_string(), JValue::Arr(path)),
+    ])
+}
+
+/// The json representation of the given shot
+fn shot_json(shot: &Shot, periods: &Periods) -> JValue {
+    let pos = rcss::npos_to_rcss(shot.npos);
+    JValue::Obj(vec![
+        ("time".to_string(), JValue::Num(shot.time as f64)),
+        ("period".to_string(), JValue::Str(periods.name_of(shot.time))),
+        ("side".to_string(), JValue::Str(shot.side.to_string())),
+        ("player".to_string(), JValue::Str(shot.playerid.to_string())),
+        ("pos".to_string(), JValue::Arr(vec![JValue::Num(pos.0 as f64), JValue::Num(pos.1 as f64)])),
+        ("dist".to_string(), JValue::Num(shot.dist as f64)),
+        ("angle".to_string(), JValue::Num(shot.angle as f64)),
+        ("defenders".to_string(), JValue::Num(shot.defenders as f64)),
+        ("xg".to_string(), JValue::Num(shot.xg as f64)),
+        ("ontarget".to_string(), JValue::Bool(shot.ontarget)),
+        ("outcome".to_string(), JValue::Str(shot.outcome.to_string())),
+    ])
+}
+
+/// The json representation of the given offside call
+fn offside_json(call: &OffsideCall, periods: &Periods) -> JValue {
+    let ostr = |o: &Option<String>| o.as_ref().map_or(JValue::Null, |s| JValue::Str(s.to_string()));
+    let pos = match call.npos {
+        Some(npos) => {
+            let pos = rcss::npos_to_rcss(npos);
+            JValue::Arr(vec![JValue::Num(pos.0 as f64), JValue::Num(pos.1 as f64)])
+        },
+        None => JValue::Null,
+    };
+    JValue::Obj(vec![
+        ("time".to_string(), JValue::Num(call.time as f64)),
+        ("period".to_string(), JValue::Str(periods.name_of(call.time))),
+        ("side".to_string(), JValue::Str(call.side.to_string())),
+        ("passtime".to_string(), call.passtime.map_or(JValue::Null, |t| JValue::Num(t as f64))),
+        ("passer".to_string(), ostr(&call.passer)),
+        ("player".to_string(), ostr(&call.playerid)),
+        ("pos".to_string(), pos),
+    ])
 }
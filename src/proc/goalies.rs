@@ -0,0 +1,333 @@
+//!
+//! Goalkeeper specific analytics
+//! HanishKVC, 2022
+//!
+//! The goalie of each team is either set using the config, or else got from
+//! the goalie flag in the player state, or failing that the 1st player of the
+//! team to catch the ball (as only the goalie can catch).
+//!
+//! The following are tracked wrt the goalie of each team
+//! * catches and failed catches
+//! * goals conceded (by the team)
+//! * average and max distance from the own goal line, during play on
+//! * sweeping, ie kicks/tackles outside the own penalty area
+//! * distribution, ie kicks after a catch, and whether the ball is retained
+//!   by the team (or lost to the other team) after it.
+//!
+//! The saves, ie shots on target which were caught by the goalie, are noted
+//! based on the shots.
+//!
+//! The time of each of these events is kept, so that they are rewound wrt
+//! seeking back in time.
+//!
+
+use crate::entities;
+use crate::playdata::rcss::{self, FieldGeom, PlayMode};
+use crate::proc::actions::AIAction;
+
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// The events counted wrt a goalie
+enum GoalieEvent {
+    Catch,
+    FailedCatch,
+    Save,
+    GoalConceded,
+    Sweep,
+    DistKick,
+    DistRetained,
+    DistLost,
+}
+
+#[derive(Debug)]
+pub struct Goalie {
+    pub playerid: Option<String>,
+    /// The goalie was set using the config, so the player state is ignored
+    pub bconfigured: bool,
+    pub catches: usize,
+    pub failedcatches: usize,
+    /// The shots on target caught by the goalie
+    pub saves: usize,
+    pub goalsconceded: usize,
+    /// The sum of the distances from the own goal line, and the number of cycles summed
+    distsum: f32,
+    distcnt: usize,
+    pub distmax: f32,
+    pub sweeps: usize,
+    /// The number of kicks after a catch, and how many of them were retained or lost
+    pub distkicks: usize,
+    pub distretained: usize,
+    pub distlost: usize,
+    /// The ball has been caught, and not yet kicked
+    bholding: bool,
+    /// The outcome of the latest distribution kick is pending
+    bdistpending: bool,
+    /// The time of the latest failed catch
+    lastfault: Option<usize>,
+    /// The events counted, along with when they occured, oldest first
+    vevents: Vec<(usize, GoalieEvent)>,
+    /// The distances from the own goal line, along with when, during play on
+    vdists: Vec<(usize, f32)>,
+}
+
+impl Goalie {
+
+    fn new() -> Goalie {
+        Goalie {
+            playerid: None,
+            bconfigured: false,
+            catches: 0,
+            failedcatches: 0,
+            saves: 0,
+            goalsconceded: 0,
+            distsum: 0.0,
+            distcnt: 0,
+            distmax: 0.0,
+            sweeps: 0,
+            distkicks: 0,
+            distretained: 0,
+            distlost: 0,
+            bholding: false,
+            bdistpending: false,
+            lastfault: None,
+            vevents: Vec::new(),
+            vdists: Vec::new(),
+        }
+    }
+
+    /// The counter corresponding to the given event
+    fn counter_mut(&mut self, event: GoalieEvent) -> &mut usize {
+        match event {
+            GoalieEvent::Catch => &mut self.catches,
+            GoalieEvent::FailedCatch => &mut self.failedcatches,
+            GoalieEvent::Save => &mut self.saves,
+            GoalieEvent::GoalConceded => &mut self.goalsconceded,
+            GoalieEvent::Sweep => &mut self.sweeps,
+            GoalieEvent::DistKick => &mut self.distkicks,
+            GoalieEvent::DistRetained => &mut self.distretained,
+            GoalieEvent::DistLost => &mut self.distlost,
+        }
+    }
+
+    /// Count the given event, noting when it occured
+    fn count(&mut self, time: usize, event: GoalieEvent) {
+        *self.counter_mut(event) += 1;
+        self.vevents.push((time, event));
+    }
+
+    /// Note the distance from the own goal line
+    fn dist_update(&mut self, time: usize, dist: f32) {
+        self.distsum += dist;
+        self.distcnt += 1;
+        self.distmax = self.distmax.max(dist);
+        self.vdists.push((time, dist));
+    }
+
+    /// Forget the events and distances at or after the given time
+    fn seek_to(&mut self, time: usize) {
+        while let Some((etime, event)) = self.vevents.last().cloned() {
+            if etime < time {
+                break;
+            }
+            *self.counter_mut(event) -= 1;
+            self.vevents.pop();
+        }
+        self.vdists.retain(|d| d.0 < time);
+        self.distsum = self.vdists.iter().fold(0.0, |acc, d| acc + d.1);
+        self.distcnt = self.vdists.len();
+        self.distmax = self.vdists.iter().fold(0.0, |acc, d| acc.max(d.1));
+        self.bholding = false;
+        self.bdistpending = false;
+        self.lastfault = None;
+    }
+
+    fn is(&self, playerid: &str) -> bool {
+        return self.playerid.as_deref() == Some(playerid);
+    }
+
+    /// The average distance from the own goal line, during play on
+    pub fn dist_avg(&self) -> f32 {
+        if self.distcnt == 0 {
+            return 0.0;
+        }
+        return self.distsum/self.distcnt as f32;
+    }
+
+}
+
+#[derive(Debug)]
+pub struct Goalies {
+    pub lgoalie: Goalie,
+    pub rgoalie: Goalie,
+    /// The field geometry, wrt the goal line and penalty area
+    pub field: FieldGeom,
+    /// The play mode changes seen, along with when, oldest first
+    vmodes: Vec<(usize, PlayMode)>,
+}
+
+impl Goalies {
+
+    pub fn new() -> Goalies {
+        Goalies {
+            lgoalie: Goalie::new(),
+            rgoalie: Goalie::new(),
+            field: FieldGeom::new(),
+            vmodes: Vec::new(),
+        }
+    }
+
+    pub fn get(&self, side: char) -> &Goalie {
+        if side == entities::SIDE_L {
+            return &self.lgoalie;
+        }
+        return &self.rgoalie;
+    }
+
+    fn get_mut(&mut self, side: char) -> &mut Goalie {
+        if side == entities::SIDE_L {
+            return &mut self.lgoalie;
+        }
+        return &mut self.rgoalie;
+    }
+
+    /// Set the goalie of the given side, as specified in the config
+    pub fn set_goalie(&mut self, side: char, playerid: &str) {
+        let goalie = self.get_mut(side);
+        goalie.playerid = Some(playerid.to_string());
+        goalie.bconfigured = true;
+    }
+
+    /// Note the goalie of the given side, based on the player state or a catch,
+    /// unless set using the config
+    fn identify(&mut self, side: char, playerid: &str, how: &str) {
+        let goalie = self.get_mut(side);
+        if goalie.bconfigured || goalie.is(playerid) {
+            return;
+        }
+        eprintln!("INFO:GPPGND:Goalies:{}{:02}:Identified:{}", side.to_uppercase(), playerid, how);
+        goalie.playerid = Some(playerid.to_string());
+    }
+
+    /// The player state indicates the given player is the goalie
+    pub fn handle_goalie_flag(&mut self, side: char, playerid: &str) {
+        self.identify(side, playerid, "State");
+    }
+
+    /// A catch by the given player failed. Consecutive failed catches are counted once.
+    pub fn handle_catch_fault(&mut self, time: usize, side: char, playerid: &str) {
+        if self.get(side).playerid.is_none() {
+            self.identify(side, playerid, "CatchFault");
+        }
+        let goalie = self.get_mut(side);
+        if !goalie.is(playerid) {
+            return;
+        }
+        if goalie.lastfault.map_or(true, |lasttime| time > lasttime+1) {
+            goalie.count(time, GoalieEvent::FailedCatch);
+        }
+        goalie.lastfault = Some(time);
+    }
+
+    /// Whether the given position (normal space) is within the penalty area of the given side
+    fn in_penalty_area(&self, side: char, npos: (f32, f32)) -> bool {
+        let pos = rcss::npos_to_rcss(npos);
+        let goalx = if side == entities::SIDE_L { -self.field.pitch_length/2.0 } else { self.field.pitch_length/2.0 };
+        return ((pos.0-goalx).abs() <= self.field.penalty_area_length) && (pos.1.abs() <= self.field.penalty_area_width/2.0);
+    }
+
+    /// Update the goalie stats wrt the given action
+    pub fn handle_action(&mut self, time: usize, side: char, playerid: &str, action: &AIAction, npos: (f32, f32)) {
+        match action {
+            AIAction::Kick | AIAction::Tackle | AIAction::Catch => (),
+            _ => return,
+        }
+        // Resolve the pending distribution kicks, wrt a action by someone else
+        for gside in [entities::SIDE_L, entities::SIDE_R] {
+            let goalie = self.get_mut(gside);
+            if !goalie.bdistpending || ((gside == side) && goalie.is(playerid)) {
+                continue;
+            }
+            goalie.bdistpending = false;
+            if gside == side {
+                goalie.count(time, GoalieEvent::DistRetained);
+            } else {
+                goalie.count(time, GoalieEvent::DistLost);
+            }
+        }
+        if (*action == AIAction::Catch) && self.get(side).playerid.is_none() {
+            self.identify(side, playerid, "Catch");
+        }
+        if !self.get(side).is(playerid) {
+            return;
+        }
+        let binarea = self.in_penalty_area(side, npos);
+        let goalie = self.get_mut(side);
+        match action {
+            AIAction::Catch => {
+                goalie.count(time, GoalieEvent::Catch);
+                goalie.bholding = true;
+            },
+            AIAction::Kick => {
+                if goalie.bholding {
+                    goalie.count(time, GoalieEvent::DistKick);
+                    goalie.bdistpending = true;
+                    goalie.bholding = false;
+                }
+            },
+            _ => (),
+        }
+        if !binarea {
+            goalie.count(time, GoalieEvent::Sweep);
+        }
+    }
+
+    /// The given player caught a shot on target, which is a save if they are the goalie
+    pub fn handle_save(&mut self, time: usize, side: char, playerid: &str) {
+        let goalie = self.get_mut(side);
+        if goalie.is(playerid) {
+            goalie.count(time, GoalieEvent::Save);
+        }
+    }
+
+    /// The latest play mode seen, if any
+    fn lastmode(&self) -> Option<&PlayMode> {
+        return self.vmodes.last().map(|m| &m.1);
+    }
+
+    /// Update the distance from the goal line of the goalies, during play on,
+    /// and the goals conceded.
+    /// players: (side, playerid, pos in normal space) wrt all players.
+    pub fn handle_cycle(&mut self, time: usize, state: &Option<PlayMode>, players: &Vec<(char, String, (f32, f32))>) {
+        if let Some(mode) = state {
+            if self.lastmode() != Some(mode) {
+                self.vmodes.push((time, mode.clone()));
+                if let PlayMode::Goal(side) = mode {
+                    let concside = if *side == entities::SIDE_L { entities::SIDE_R } else { entities::SIDE_L };
+                    self.get_mut(concside).count(time, GoalieEvent::GoalConceded);
+                }
+            }
+        }
+        if self.lastmode() != Some(&PlayMode::PlayOn) {
+            return;
+        }
+        let halflength = self.field.pitch_length/2.0;
+        for (side, pid, npos) in players {
+            let goalie = self.get_mut(*side);
+            if !goalie.is(pid) {
+                continue;
+            }
+            let x = rcss::npos_to_rcss(*npos).0;
+            let dist = if *side == entities::SIDE_L { x + halflength } else { halflength - x };
+            goalie.dist_update(time, dist);
+        }
+    }
+
+    /// Forget the stats and play modes at or after the given time, wrt a seek back in time.
+    /// The play mode in effect at the given time is retained.
+    pub fn seek_to(&mut self, time: usize) {
+        self.lgoalie.seek_to(time);
+        self.rgoalie.seek_to(time);
+        self.vmodes.retain(|m| m.0 < time);
+    }
+
+}
@@ -4,3 +4,9 @@
 //!
 
 pub mod actions;
+pub mod possession;
+pub mod shots;
+pub mod offside;
+pub mod periods;
+pub mod setpieces;
+pub mod goalies;
@@ -0,0 +1,181 @@
+//!
+//! Track offside lines and offside calls
+//! HanishKVC, 2022
+//!
+//! The offside line wrt a team is the position of its second-last
+//! defender (including the goalie), along the length of the pitch.
+//!
+//! Whenever a player kicks the ball, the team mates who are in an
+//! offside position at that moment are noted. When the referee calls
+//! offside (playmode offside_l/offside_r, ie offside by the left/right
+//! team), the offside call is recorded along with the passer and the
+//! attacker (among those in offside position at the time of the pass)
+//! nearest to the ball when the call was made.
+//!
+
+use std::collections::HashMap;
+
+use crate::entities;
+use crate::playdata::rcss::{self, PlayMode};
+
+
+#[derive(Debug, Clone)]
+pub struct OffsideCall {
+    /// When the offside was called
+    pub time: usize,
+    /// The side which was offside
+    pub side: char,
+    /// The time of the pass which led to the offside, if known
+    pub passtime: Option<usize>,
+    /// The player who made the pass, if known
+    pub passer: Option<String>,
+    /// The attacker who was offside, if known
+    pub playerid: Option<String>,
+    /// The position (normal space) of the attacker at the time of the pass, if known
+    pub npos: Option<(f32, f32)>,
+}
+
+#[derive(Debug, Clone)]
+/// The players in offside position, at the time of the pass (kick)
+struct PassSnapshot {
+    time: usize,
+    passer: String,
+    /// (playerid, pos in normal space)
+    offside: Vec<(String, (f32, f32))>,
+}
+
+#[derive(Debug)]
+pub struct Offside {
+    pub calls: Vec<OffsideCall>,
+    /// The latest pass wrt the left and right teams
+    lpass: Option<PassSnapshot>,
+    rpass: Option<PassSnapshot>,
+    /// The last playmode seen, so that a call is recorded only once
+    lastmode: Option<PlayMode>,
+}
+
+impl Offside {
+
+    pub fn new() -> Offside {
+        Offside {
+            calls: Vec::new(),
+            lpass: None,
+            rpass: None,
+            lastmode: None,
+        }
+    }
+
+    /// The offside line (x in rcss space) wrt the given defending side, ie the
+    /// position of its second-last player towards its own goal.
+    /// players: (side, playerid, pos in normal space) wrt all players.
+    pub fn line(defside: char, players: &Vec<(char, String, (f32, f32))>) -> Option<f32> {
+        let mut vx: Vec<f32> = players.iter().filter(|p| p.0 == defside).map(|p| rcss::npos_to_rcss(p.2).0).collect();
+        if vx.len() < 2 {
+            return None;
+        }
+        vx.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        if defside == entities::SIDE_L {
+            return Some(vx[1]);
+        }
+        return Some(vx[vx.len()-2]);
+    }
+
+    /// The players of the attacking side in offside position, ie in the half of
+    /// the other team and beyond both the ball and its second-last defender.
+    pub fn players_offside(attside: char, players: &Vec<(char, String, (f32, f32))>, bnpos: (f32, f32)) -> Vec<(String, (f32, f32))> {
+        let defside = if attside == entities::SIDE_L { entities::SIDE_R } else { entities::SIDE_L };
+        let line = match Offside::line(defside, players) {
+            Some(line) => line,
+            None => return Vec::new(),
+        };
+        let bx = rcss::npos_to_rcss(bnpos).0;
+        // Flip the r side, so that the attack is always towards +ve x
+        let dir = if attside == entities::SIDE_L { 1.0 } else { -1.0 };
+        let limit = (line*dir).max(bx*dir).max(0.0);
+        let mut voffside = Vec::new();
+        for (side, pid, npos) in players {
+            if *side != attside {
+                continue;
+            }
+            if rcss::npos_to_rcss(*npos).0*dir > limit {
+                voffside.push((pid.to_string(), *npos));
+            }
+        }
+        return voffside;
+    }
+
+    /// Note the players in offside position, wrt a kick by the given player
+    pub fn handle_kick(&mut self, time: usize, side: char, playerid: &str, players: &Vec<(char, String, (f32, f32))>, bnpos: (f32, f32)) {
+        let snapshot = PassSnapshot {
+            time: time,
+            passer: playerid.to_string(),
+            offside: Offside::players_offside(side, players, bnpos).into_iter().filter(|p| p.0 != playerid).collect(),
+        };
+        if side == entities::SIDE_L {
+            self.lpass = Some(snapshot);
+        } else {
+            self.rpass = Some(snapshot);
+        }
+    }
+
+    /// Record a offside call, if the play mode indicates one.
+    pub fn handle_cycle(&mut self, time: usize, state: &Option<PlayMode>, bnpos: (f32, f32)) {
+        if state.is_none() || (*state == self.lastmode) {
+            return;
+        }
+        self.lastmode = state.clone();
+        let side = match state {
+            Some(PlayMode::Offside(side)) => *side,
+            _ => return,
+        };
+        let pass = if side == entities::SIDE_L { self.lpass.take() } else { self.rpass.take() };
+        let mut call = OffsideCall {
+            time: time,
+            side: side,
+            passtime: None,
+            passer: None,
+            playerid: None,
+            npos: None,
+        };
+        if let Some(pass) = pass {
+            call.passtime = Some(pass.time);
+            call.passer = Some(pass.passer);
+            let bpos = rcss::npos_to_rcss(bnpos);
+            let mut mindist = f32::MAX;
+            for (pid, npos) in pass.offside {
+                let ppos = rcss::npos_to_rcss(npos);
+                let d = ((ppos.0-bpos.0).powi(2) + (ppos.1-bpos.1).powi(2)).sqrt();
+                if d < mindist {
+                    mindist = d;
+                    call.playerid = Some(pid);
+                    call.npos = Some(npos);
+                }
+            }
+        }
+        eprintln!("INFO:GPPGND:Offside:Call:{:?}", call);
+        self.calls.push(call);
+    }
+
+    /// Forget the calls at or after the given time, wrt a seek back in time
+    pub fn seek_to(&mut self, time: usize) {
+        self.calls.retain(|call| call.time < time);
+        self.lpass = None;
+        self.rpass = None;
+        self.lastmode = None;
+    }
+
+    /// The number of offside calls wrt each of the players of the given side,
+    /// largest first. Calls where the player isnt known are not counted.
+    pub fn counts(&self, side: char) -> Vec<(String, usize)> {
+        let mut hm: HashMap<String, usize> = HashMap::new();
+        for call in self.calls.iter().filter(|call| call.side == side) {
+            if let Some(pid) = &call.playerid {
+                *hm.entry(pid.to_string()).or_insert(0) += 1;
+            }
+        }
+        let mut vcounts: Vec<(String, usize)> = hm.into_iter().collect();
+        vcounts.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        return vcounts;
+    }
+
+}
@@ -0,0 +1,166 @@
+//!
+//! Track the periods of a game
+//! HanishKVC, 2022
+//!
+//! A game consists of two halfs, optionally followed by extra time halfs
+//! and or a penalty shoot-out. The periods are identified based on the
+//! play modes, ie a kick off after the end of a half (first_half_over,
+//! time_over or their referee variants like half_time, time_up) starts the
+//! next half (or extra time half, once the normal halfs are over), while
+//! the 1st penalty_setup starts the shoot-out.
+//!
+//! The stats are tagged to a period based on time, ie a period covers the
+//! time from its start till the start of the next period.
+//!
+
+use crate::playdata::rcss::PlayMode;
+
+
+/// The number of normal halfs in a game
+const NORMAL_HALFS: usize = 2;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PeriodKind {
+    Half(usize),
+    ExtraHalf(usize),
+    PenaltyShootout,
+}
+
+impl std::fmt::Display for PeriodKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PeriodKind::Half(n) => f.write_str(&format!("H{}", n)),
+            PeriodKind::ExtraHalf(n) => f.write_str(&format!("E{}", n)),
+            PeriodKind::PenaltyShootout => f.write_str("PSO"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Period {
+    pub kind: PeriodKind,
+    pub start: usize,
+    /// When the period was over, if it is over
+    pub end: Option<usize>,
+}
+
+#[derive(Debug)]
+pub struct Periods {
+    pub periods: Vec<Period>,
+    /// The penalty shoot-out kicks, as (time, side, scored)
+    pub penalties: Vec<(usize, char, bool)>,
+}
+
+impl Periods {
+
+    pub fn new() -> Periods {
+        Periods {
+            periods: vec![Period { kind: PeriodKind::Half(1), start: 0, end: None }],
+            penalties: Vec::new(),
+        }
+    }
+
+    /// The current period
+    pub fn current(&self) -> &Period {
+        return self.periods.last().unwrap();
+    }
+
+    /// Whether the penalty shoot-out is in progress
+    pub fn in_shootout(&self) -> bool {
+        return self.current().kind == PeriodKind::PenaltyShootout;
+    }
+
+    /// The kind of period which follows the current period
+    fn next_kind(&self) -> PeriodKind {
+        let halfs = self.periods.iter().filter(|p| matches!(p.kind, PeriodKind::Half(_))).count();
+        if halfs < NORMAL_HALFS {
+            return PeriodKind::Half(halfs+1);
+        }
+        let extrahalfs = self.periods.iter().filter(|p| matches!(p.kind, PeriodKind::ExtraHalf(_))).count();
+        return PeriodKind::ExtraHalf(extrahalfs+1);
+    }
+
+    /// Start a new period of the given kind, ending the current one if not yet done.
+    fn start(&mut self, time: usize, kind: PeriodKind) {
+        let cur = self.periods.last_mut().unwrap();
+        if cur.end.is_none() {
+            cur.end = Some(time);
+        }
+        eprintln!("INFO:GPPGND:Periods:Start:{}:{}", kind, time);
+        self.periods.push(Period { kind: kind, start: time, end: None });
+    }
+
+    /// Update the periods wrt a change in the play mode.
+    /// Returns true, if play is being restarted afresh, ie a kick off or
+    /// a penalty setup, in which case the chaining of actions should restart.
+    pub fn handle_mode(&mut self, time: usize, mode: &PlayMode) -> bool {
+        if mode.is_period_end() {
+            let cur = self.periods.last_mut().unwrap();
+            if cur.end.is_none() {
+                cur.end = Some(time);
+                eprintln!("INFO:GPPGND:Periods:End:{}:{}", cur.kind, time);
+            }
+            return false;
+        }
+        match mode {
+            PlayMode::KickOff(_) => {
+                if self.current().end.is_some() && !self.in_shootout() {
+                    self.start(time, self.next_kind());
+                }
+                return true;
+            },
+            PlayMode::PenaltySetup(_) => {
+                if !self.in_shootout() {
+                    self.start(time, PeriodKind::PenaltyShootout);
+                }
+                return true;
+            },
+            PlayMode::PenaltyScore(side) => self.penalties.push((time, *side, true)),
+            PlayMode::PenaltyMiss(side) => self.penalties.push((time, *side, false)),
+            _ => (),
+        }
+        return false;
+    }
+
+    /// The index of the period to which the given time belongs
+    pub fn period_of(&self, time: usize) -> usize {
+        for i in (0..self.periods.len()).rev() {
+            if self.periods[i].start <= time {
+                return i;
+            }
+        }
+        return 0;
+    }
+
+    /// The time range [start, end) covered by the given period
+    pub fn range(&self, index: usize) -> (usize, usize) {
+        let end = match self.periods.get(index+1) {
+            Some(next) => next.start,
+            None => usize::MAX,
+        };
+        return (self.periods[index].start, end);
+    }
+
+    /// The name of the period to which the given time belongs
+    pub fn name_of(&self, time: usize) -> String {
+        return self.periods[self.period_of(time)].kind.to_string();
+    }
+
+    /// The penalties taken and scored wrt the given side, during the shoot-out
+    pub fn penalty_score(&self, side: char) -> (usize, usize) {
+        let taken = self.penalties.iter().filter(|p| p.1 == side).count();
+        let scored = self.penalties.iter().filter(|p| (p.1 == side) && p.2).count();
+        return (taken, scored);
+    }
+
+    /// Forget the periods and penalties at or after the given time, wrt a seek back in time
+    pub fn seek_to(&mut self, time: usize) {
+        self.periods.retain(|p| (p.start == 0) || (p.start < time));
+        let cur = self.periods.last_mut().unwrap();
+        if cur.end.map_or(false, |end| end >= time) {
+            cur.end = None;
+        }
+        self.penalties.retain(|p| p.0 < time);
+    }
+
+}
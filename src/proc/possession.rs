@@ -0,0 +1,425 @@
+//!
+//! Track ball possession, based on the ball's proximity to the players
+//! HanishKVC, 2022
+//!
+//! Every cycle of play, the player nearest to the ball, if within the
+//! kickable distance, is taken to be in control of the ball. The team
+//! of the player last in control is credited with the possession time,
+//! till someone else from the other team gets control of the ball.
+//!
+//! Time during dead ball situations (any playmode other than play_on)
+//! and time when players from both teams are within reach of the ball
+//! (contested) is not credited to either team.
+//!
+//! What the time wrt each cycle was credited to is remembered, so that
+//! the times can be recomputed, wrt a seek back in time.
+//!
+//! The match is also segmented into possession sequences, ie the time
+//! a team keeps control of the ball, from when they get control of it,
+//! till they either lose it to the other team or play stops.
+//!
+
+use std::collections::HashMap;
+use std::fmt::Display;
+
+use crate::entities;
+use crate::playdata::Params;
+use crate::playdata::rcss::{self, PlayMode};
+
+
+/// The default rcss kickable distance (kickable_margin + player_size + ball_size)
+pub const KICKABLE_DIST_DEFAULT: f32 = 0.7 + 0.3 + 0.085;
+/// If the time jumps by more than this, it is treated as a seek and not credited
+const CYCLE_DELTA_MAX: usize = 10;
+
+/// The pitch thirds, relative to the attacking direction of a team
+pub const THIRD_DEFENSIVE: usize = 0;
+pub const THIRD_MIDDLE: usize = 1;
+pub const THIRD_ATTACKING: usize = 2;
+pub const THIRD_NAMES: [&str; 3] = ["Def", "Mid", "Att"];
+
+/// The start event wrt a sequence, when the ball is won from the other team
+pub const SEQ_EVENT_REGAIN: &str = "regain";
+
+
+#[derive(Debug, Clone, PartialEq, Default)]
+/// The possession time wrt a team.
+pub struct TeamPossession {
+    /// Total possession time, in cycles
+    pub time: f32,
+    /// Possession time wrt each period of the game, see proc::periods
+    pub periods: Vec<f32>,
+    /// Possession time based on the third of the pitch where the ball is,
+    /// relative to the attacking direction of the team.
+    pub thirds: [f32; 3],
+}
+
+impl TeamPossession {
+
+    fn credit(&mut self, dtime: f32, period: usize, third: usize) {
+        self.time += dtime;
+        if self.periods.len() <= period {
+            self.periods.resize(period+1, 0.0);
+        }
+        self.periods[period] += dtime;
+        self.thirds[third] += dtime;
+    }
+
+    /// Possession time wrt the given period, 0 if none
+    pub fn period(&self, period: usize) -> f32 {
+        return *self.periods.get(period).unwrap_or(&0.0);
+    }
+
+}
+
+
+#[derive(Debug, Clone, PartialEq)]
+/// How a possession sequence ended
+pub enum SeqOutcome {
+    Ongoing,
+    /// Play stopped due to a goal by the team in possession
+    Goal,
+    /// The ball was caught by the goalie of the other team
+    Shot,
+    /// The other team got control of the ball
+    Loss,
+    /// Play stopped for any other reason, like ball going out, foul, offside, ...
+    Out,
+}
+
+impl Display for SeqOutcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let soutcome = match self {
+            SeqOutcome::Ongoing => "ongoing",
+            SeqOutcome::Goal => "goal",
+            SeqOutcome::Shot => "shot",
+            SeqOutcome::Loss => "loss",
+            SeqOutcome::Out => "out",
+        };
+        f.write_str(soutcome)
+    }
+}
+
+#[derive(Debug, Clone)]
+/// A possession sequence, ie a period of continuous control of the ball by a team.
+pub struct Sequence {
+    pub side: char,
+    pub start_time: usize,
+    pub end_time: usize,
+    /// What started the sequence, the restart playmode or regain
+    pub start_event: String,
+    /// What ended the sequence, the playmode or the player who got the ball
+    pub end_event: String,
+    /// The players who had control of the ball, in the order in which they got it first
+    pub players: Vec<String>,
+    /// The number of times the ball moved between players of the team
+    pub passes: usize,
+    /// The pitch third where the sequence started and ended, wrt the team
+    pub start_zone: usize,
+    pub end_zone: usize,
+    pub outcome: SeqOutcome,
+    /// The ball positions (normal space) when a player got control of the ball,
+    /// followed by the ball position at the end of the sequence.
+    pub path: Vec<(f32, f32)>,
+}
+
+impl Sequence {
+
+    fn new(time: usize, side: char, playerid: &str, start_event: &str, bnpos: (f32, f32), zone: usize) -> Sequence {
+        Sequence {
+            side: side,
+            start_time: time,
+            end_time: time,
+            start_event: start_event.to_string(),
+            end_event: String::new(),
+            players: vec![playerid.to_string()],
+            passes: 0,
+            start_zone: zone,
+            end_zone: zone,
+            outcome: SeqOutcome::Ongoing,
+            path: vec![bnpos],
+        }
+    }
+
+    /// The duration of the sequence, in cycles
+    pub fn duration(&self) -> usize {
+        return self.end_time - self.start_time;
+    }
+
+}
+
+impl Display for Sequence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&format!("{}:{}-{}:{}:{}->{}:Passes:{}:Players:{}:Zone:{}->{}:{}",
+            self.side.to_uppercase(), self.start_time, self.end_time, self.duration(),
+            self.start_event, self.end_event, self.passes, self.players.join(","),
+            THIRD_NAMES[self.start_zone], THIRD_NAMES[self.end_zone], self.outcome))
+    }
+}
+
+
+#[derive(Debug, Clone)]
+/// What the time wrt a cycle was credited to
+enum Credit {
+    /// The player (side, playerid) in control, the period and the pitch third
+    Player(char, String, usize, usize),
+    Dead,
+    Contested,
+}
+
+
+#[derive(Debug)]
+pub struct Possession {
+    /// Ball within this distance (in meters) of a player, is taken to be under their control
+    pub kickable_dist: f32,
+    /// The player (side, playerid) currently in control of the ball, if any
+    holder: Option<(char, String)>,
+    /// Whether play is on, as per the last known playmode.
+    /// Assumed to be on, till a playmode is got, for sources without playmodes.
+    bplayon: bool,
+    /// The last cycle time seen
+    lasttime: Option<usize>,
+    pub lteam: TeamPossession,
+    pub rteam: TeamPossession,
+    /// Possession time wrt each player, keyed by (side, playerid)
+    pub players: HashMap<(char, String), f32>,
+    /// Time during dead ball situations
+    pub deadtime: f32,
+    /// Time when players of both teams could reach the ball
+    pub contestedtime: f32,
+    /// The possession sequences, the last one could be ongoing
+    pub sequences: Vec<Sequence>,
+    /// Whether the last sequence is ongoing
+    bseqopen: bool,
+    /// The start event wrt the next sequence
+    nextstart: String,
+    /// The time credited wrt each cycle, as (time, dtime, credit)
+    credits: Vec<(usize, f32, Credit)>,
+}
+
+impl Possession {
+
+    pub fn new() -> Possession {
+        Possession {
+            kickable_dist: KICKABLE_DIST_DEFAULT,
+            holder: None,
+            bplayon: true,
+            lasttime: None,
+            lteam: TeamPossession::default(),
+            rteam: TeamPossession::default(),
+            players: HashMap::new(),
+            deadtime: 0.0,
+            contestedtime: 0.0,
+            sequences: Vec::new(),
+            bseqopen: false,
+            nextstart: "start".to_string(),
+            credits: Vec::new(),
+        }
+    }
+
+    /// Update the kickable distance from the params if any.
+    pub fn update_from_params(&mut self, params: &Params) {
+        let getf = |key: &str, def: f32| -> f32 {
+            match params.get(key) {
+                Some(sval) => sval.parse::<f32>().unwrap_or(def),
+                None => def,
+            }
+        };
+        if params.contains_key("server_param.kickable_margin") {
+            self.kickable_dist = getf("server_param.kickable_margin", 0.7) + getf("server_param.player_size", 0.3) + getf("server_param.ball_size", 0.085);
+        }
+    }
+
+    /// The third of the pitch, wrt the attacking direction of the side,
+    /// where the ball (rcss space) is.
+    pub fn third(side: char, bx: f32) -> usize {
+        let edge = rcss::FieldGeom::new().pitch_length/6.0;
+        let third = if bx < -edge {
+            THIRD_DEFENSIVE
+        } else if bx > edge {
+            THIRD_ATTACKING
+        } else {
+            THIRD_MIDDLE
+        };
+        if side == entities::SIDE_L {
+            return third;
+        }
+        return THIRD_ATTACKING - third;
+    }
+
+    /// End the ongoing sequence if any
+    fn seq_end(&mut self, time: usize, bnpos: (f32, f32), outcome: SeqOutcome, end_event: &str) {
+        if !self.bseqopen {
+            return;
+        }
+        self.bseqopen = false;
+        let seq = self.sequences.last_mut().unwrap();
+        seq.end_time = time;
+        seq.end_event = end_event.to_string();
+        seq.end_zone = Possession::third(seq.side, rcss::npos_to_rcss(bnpos).0);
+        seq.outcome = outcome;
+        seq.path.push(bnpos);
+    }
+
+    /// Add the given time to the totals wrt the given credit
+    fn apply(&mut self, dtime: f32, credit: &Credit) {
+        match credit {
+            Credit::Player(side, pid, period, third) => {
+                if *side == entities::SIDE_L {
+                    self.lteam.credit(dtime, *period, *third);
+                } else {
+                    self.rteam.credit(dtime, *period, *third);
+                }
+                *self.players.entry((*side, pid.to_string())).or_insert(0.0) += dtime;
+            },
+            Credit::Dead => self.deadtime += dtime,
+            Credit::Contested => self.contestedtime += dtime,
+        }
+    }
+
+    /// Credit the time wrt the given cycle, and remember it
+    fn credit(&mut self, time: usize, dtime: f32, credit: Credit) {
+        if dtime <= 0.0 {
+            return;
+        }
+        self.apply(dtime, &credit);
+        self.credits.push((time, dtime, credit));
+    }
+
+    /// Forget the sequences which werent fully over before the given time,
+    /// and recompute the times from the credits before the given time,
+    /// wrt a seek back in time.
+    fn seek_to(&mut self, time: usize) {
+        self.sequences.retain(|seq| (seq.outcome != SeqOutcome::Ongoing) && (seq.end_time < time));
+        self.bseqopen = false;
+        self.holder = None;
+        self.nextstart = "seek".to_string();
+        let credits = std::mem::take(&mut self.credits);
+        self.lteam = TeamPossession::default();
+        self.rteam = TeamPossession::default();
+        self.players.clear();
+        self.deadtime = 0.0;
+        self.contestedtime = 0.0;
+        for (ctime, dtime, credit) in credits.into_iter().filter(|c| c.0 < time) {
+            self.credit(ctime, dtime, credit);
+        }
+    }
+
+    /// Update the possession wrt a cycle.
+    ///
+    /// state: the play mode wrt this cycle, None means no change.
+    /// ball: the position of the ball in normal space.
+    /// players: (side, playerid, pos in normal space) wrt all players.
+    /// catchside: the side whose goalie caught the ball in this cycle, if any.
+    /// period: the period of the game (index) to which this cycle belongs.
+    pub fn update(&mut self, time: usize, state: &Option<PlayMode>, ball: (f32, f32), players: &Vec<(char, String, (f32, f32))>, catchside: Option<char>, period: usize) {
+        let mut dtime = 0.0;
+        if let Some(lasttime) = self.lasttime {
+            if (time > lasttime) && ((time - lasttime) <= CYCLE_DELTA_MAX) {
+                dtime = (time - lasttime) as f32;
+            }
+            if time < lasttime {
+                self.seek_to(time);
+            }
+        }
+        self.lasttime = Some(time);
+        match state {
+            None => (),
+            Some(PlayMode::PlayOn) => self.bplayon = true,
+            Some(mode) => {
+                self.bplayon = false;
+                self.nextstart = mode.to_string();
+            },
+        }
+        if !self.bplayon {
+            if self.bseqopen {
+                let seqside = self.sequences.last().unwrap().side;
+                let outcome = match state {
+                    Some(PlayMode::Goal(side)) if *side == seqside => SeqOutcome::Goal,
+                    _ if catchside.is_some() && (catchside != Some(seqside)) => SeqOutcome::Shot,
+                    _ => SeqOutcome::Out,
+                };
+                let end_event = self.nextstart.clone();
+                self.seq_end(time, ball, outcome, &end_event);
+            }
+            self.holder = None;
+            self.credit(time, dtime, Credit::Dead);
+            return;
+        }
+        let bpos = rcss::npos_to_rcss(ball);
+        let mut nearest: Option<(f32, char, &String)> = None;
+        let mut bl = false;
+        let mut br = false;
+        for (side, pid, npos) in players {
+            let ppos = rcss::npos_to_rcss(*npos);
+            let dx = ppos.0 - bpos.0;
+            let dy = ppos.1 - bpos.1;
+            let d = (dx*dx + dy*dy).sqrt();
+            if d > self.kickable_dist {
+                continue;
+            }
+            if *side == entities::SIDE_L { bl = true } else { br = true }
+            if nearest.is_none() || nearest.unwrap().0 > d {
+                nearest = Some((d, *side, pid));
+            }
+        }
+        if bl && br {
+            self.credit(time, dtime, Credit::Contested);
+            return;
+        }
+        if let Some((_, side, pid)) = nearest {
+            let pid = pid.to_string();
+            let third = Possession::third(side, bpos.0);
+            let bsameside = self.bseqopen && (self.sequences.last().unwrap().side == side);
+            if bsameside {
+                let seq = self.sequences.last_mut().unwrap();
+                if self.holder != Some((side, pid.clone())) {
+                    seq.passes += 1;
+                    seq.path.push(ball);
+                    if !seq.players.contains(&pid) {
+                        seq.players.push(pid.clone());
+                    }
+                }
+            } else {
+                if self.bseqopen {
+                    let outcome = if catchside == Some(side) { SeqOutcome::Shot } else { SeqOutcome::Loss };
+                    self.seq_end(time, ball, outcome, &format!("{}{}", side, pid));
+                    self.nextstart = SEQ_EVENT_REGAIN.to_string();
+                }
+                self.sequences.push(Sequence::new(time, side, &pid, &self.nextstart, ball, third));
+                self.bseqopen = true;
+            }
+            self.holder = Some((side, pid));
+        }
+        if self.bseqopen {
+            self.sequences.last_mut().unwrap().end_time = time;
+        }
+        if let Some((side, pid)) = self.holder.clone() {
+            let third = Possession::third(side, bpos.0);
+            self.credit(time, dtime, Credit::Player(side, pid, period, third));
+        }
+    }
+
+    /// Possession percentage of the left and right teams, wrt the given team times
+    pub fn percentages(ltime: f32, rtime: f32) -> (f32, f32) {
+        let total = ltime + rtime;
+        if total <= 0.0 {
+            return (50.0, 50.0);
+        }
+        let lposs = ((ltime/total)*100.0).round();
+        return (lposs, 100.0-lposs);
+    }
+
+    /// The possession time wrt the specified player
+    pub fn player(&self, side: char, playerid: &str) -> f32 {
+        return *self.players.get(&(side, playerid.to_string())).unwrap_or(&0.0);
+    }
+
+    /// The players of the given side, sorted on their possession time, largest first
+    pub fn players_sorted(&self, side: char) -> Vec<(String, f32)> {
+        let mut vplayers: Vec<(String, f32)> = self.players.iter().filter(|(k, _)| k.0 == side).map(|(k, v)| (k.1.to_string(), *v)).collect();
+        vplayers.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
+        return vplayers;
+    }
+
+}
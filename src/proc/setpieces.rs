@@ -0,0 +1,246 @@
+//!
+//! Track set pieces and their outcome
+//! HanishKVC, 2022
+//!
+//! Whenever the play mode changes to a set piece (kick in, free kick, corner
+//! kick, goal kick, indirect free kick or penalty kick), the set piece is
+//! recorded along with the side taking it and the location of the ball.
+//! The 1st player of that side to kick the ball is taken to be the taker.
+//!
+//! What happens in the window of cycles following the set piece decides its
+//! outcome, ie
+//!
+//! * goal, if the side taking the set piece scores
+//! * shot, if the side taking the set piece takes a shot
+//! * lost, if the other side scores, or was the last to touch the ball
+//! * retained, if the side taking the set piece was the last to touch the ball
+//!
+//! A new set piece (or end of the period) before the window is over, resolves
+//! the current set piece based on what has happened till then.
+//!
+
+use std::collections::HashMap;
+
+use crate::playdata::rcss::PlayMode;
+use crate::proc::actions::AIAction;
+use crate::proc::periods::Periods;
+use crate::proc::shots::Shot;
+
+
+/// The number of cycles after a set piece, which decide its outcome
+pub const WINDOW_DEFAULT: usize = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SetPieceOutcome {
+    Pending,
+    Goal,
+    Shot,
+    Retained,
+    Lost,
+}
+
+impl std::fmt::Display for SetPieceOutcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let soutcome = match self {
+            SetPieceOutcome::Pending => "pending",
+            SetPieceOutcome::Goal => "goal",
+            SetPieceOutcome::Shot => "shot",
+            SetPieceOutcome::Retained => "retained",
+            SetPieceOutcome::Lost => "lost",
+        };
+        f.write_str(soutcome)
+    }
+}
+
+impl SetPieceOutcome {
+
+    /// Whether the set piece was successful, ie it didnt lead to a loss of the ball
+    pub fn success(&self) -> bool {
+        match self {
+            SetPieceOutcome::Goal | SetPieceOutcome::Shot | SetPieceOutcome::Retained => true,
+            _ => false,
+        }
+    }
+
+}
+
+#[derive(Debug, Clone)]
+pub struct SetPiece {
+    pub time: usize,
+    /// The type of the set piece, ie the play mode name like corner_kick
+    pub kind: String,
+    /// The side taking the set piece
+    pub side: char,
+    /// The player who took the set piece, if known
+    pub taker: Option<String>,
+    /// The location (normal space) of the ball, when the set piece was awarded
+    pub npos: (f32, f32),
+    pub outcome: SetPieceOutcome,
+}
+
+impl std::fmt::Display for SetPiece {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let staker = self.taker.as_deref().unwrap_or("?");
+        f.write_str(&format!("{}:{}:{}{}:{}", self.time, self.kind, self.side.to_uppercase(), staker, self.outcome))
+    }
+}
+
+#[derive(Debug)]
+pub struct SetPieces {
+    /// The number of cycles after a set piece, which decide its outcome
+    pub window: usize,
+    pub setpieces: Vec<SetPiece>,
+    /// The side which last touched the ball, after the current set piece
+    lasttouch: Option<char>,
+    /// The last playmode seen, so that a set piece is recorded only once
+    lastmode: Option<PlayMode>,
+}
+
+impl SetPieces {
+
+    pub fn new() -> SetPieces {
+        SetPieces {
+            window: WINDOW_DEFAULT,
+            setpieces: Vec::new(),
+            lasttouch: None,
+            lastmode: None,
+        }
+    }
+
+    /// The set piece whose outcome is still pending, if any
+    fn pending(&mut self) -> Option<&mut SetPiece> {
+        match self.setpieces.last_mut() {
+            Some(sp) if sp.outcome == SetPieceOutcome::Pending => Some(sp),
+            _ => None,
+        }
+    }
+
+    /// Resolve the pending set piece (if any), based on the shots taken and
+    /// the side which last touched the ball, after it.
+    fn resolve(&mut self, shots: &Vec<Shot>) {
+        let lasttouch = self.lasttouch;
+        let sp = match self.pending() {
+            Some(sp) => sp,
+            None => return,
+        };
+        if shots.iter().any(|shot| (shot.side == sp.side) && (shot.time >= sp.time)) {
+            sp.outcome = SetPieceOutcome::Shot;
+        } else if lasttouch == Some(sp.side) {
+            sp.outcome = SetPieceOutcome::Retained;
+        } else {
+            sp.outcome = SetPieceOutcome::Lost;
+        }
+        eprintln!("INFO:GPPGND:SetPieces:{}", sp);
+    }
+
+    /// Note the taker and the side touching the ball, wrt the pending set piece
+    pub fn handle_action(&mut self, side: char, playerid: &str, action: &AIAction) {
+        match action {
+            AIAction::Kick | AIAction::Tackle | AIAction::Catch => (),
+            _ => return,
+        }
+        self.lasttouch = Some(side);
+        if let Some(sp) = self.pending() {
+            if (sp.side == side) && (*action == AIAction::Kick) && sp.taker.is_none() {
+                sp.taker = Some(playerid.to_string());
+            }
+        }
+    }
+
+    /// Record a new set piece, if the play mode indicates one, and resolve the
+    /// pending set piece, if its window is over, the period is over or play has
+    /// moved on.
+    pub fn handle_cycle(&mut self, time: usize, state: &Option<PlayMode>, bnpos: (f32, f32), shots: &Vec<Shot>, periods: &Periods) {
+        if let Some(sp) = self.pending() {
+            if (time >= sp.time + self.window) || periods.current().end.is_some() {
+                self.resolve(shots);
+            }
+        }
+        if state.is_none() || (*state == self.lastmode) {
+            return;
+        }
+        self.lastmode = state.clone();
+        let mode = state.as_ref().unwrap();
+        if let PlayMode::Goal(side) = mode {
+            if let Some(sp) = self.pending() {
+                sp.outcome = if *side == sp.side { SetPieceOutcome::Goal } else { SetPieceOutcome::Lost };
+                eprintln!("INFO:GPPGND:SetPieces:{}", sp);
+            }
+            return;
+        }
+        let side = match mode.set_piece() {
+            Some(side) => side,
+            None => return,
+        };
+        self.resolve(shots);
+        self.lasttouch = None;
+        self.setpieces.push(SetPiece {
+            time: time,
+            kind: mode.name().to_string(),
+            side: side,
+            taker: None,
+            npos: bnpos,
+            outcome: SetPieceOutcome::Pending,
+        });
+    }
+
+    /// Forget the set pieces at or after the given time, wrt a seek back in time
+    pub fn seek_to(&mut self, time: usize) {
+        self.setpieces.retain(|sp| sp.time < time);
+        // The outcome of the last set piece may depend on what is forgotten
+        if let Some(sp) = self.setpieces.last_mut() {
+            if time < sp.time + self.window {
+                sp.outcome = SetPieceOutcome::Pending;
+            }
+        }
+        self.lasttouch = None;
+        self.lastmode = None;
+    }
+
+    /// The number of set pieces, goals, shots, retained and lost wrt the given side,
+    /// optionally only wrt the given type of set piece. Pending ones are not counted.
+    pub fn stats(&self, side: char, kind: Option<&str>) -> (usize, usize, usize, usize, usize) {
+        let (mut cnt, mut goals, mut shots, mut retained, mut lost) = (0, 0, 0, 0, 0);
+        for sp in self.setpieces.iter().filter(|sp| (sp.side == side) && kind.map_or(true, |k| k == sp.kind)) {
+            match sp.outcome {
+                SetPieceOutcome::Pending => continue,
+                SetPieceOutcome::Goal => goals += 1,
+                SetPieceOutcome::Shot => shots += 1,
+                SetPieceOutcome::Retained => retained += 1,
+                SetPieceOutcome::Lost => lost += 1,
+            }
+            cnt += 1;
+        }
+        return (cnt, goals, shots, retained, lost);
+    }
+
+    /// The types of set pieces seen wrt the given side
+    pub fn kinds(&self, side: char) -> Vec<String> {
+        let mut vkinds: Vec<String> = Vec::new();
+        for sp in self.setpieces.iter().filter(|sp| sp.side == side) {
+            if !vkinds.contains(&sp.kind) {
+                vkinds.push(sp.kind.clone());
+            }
+        }
+        return vkinds;
+    }
+
+    /// The number of set pieces taken and the number of successful ones, wrt each
+    /// of the takers of the given side, most taken first. Pending ones are not counted.
+    pub fn takers(&self, side: char) -> Vec<(String, usize, usize)> {
+        let mut hm: HashMap<String, (usize, usize)> = HashMap::new();
+        for sp in self.setpieces.iter().filter(|sp| (sp.side == side) && (sp.outcome != SetPieceOutcome::Pending)) {
+            if let Some(pid) = &sp.taker {
+                let entry = hm.entry(pid.to_string()).or_insert((0, 0));
+                entry.0 += 1;
+                if sp.outcome.success() {
+                    entry.1 += 1;
+                }
+            }
+        }
+        let mut vtakers: Vec<(String, usize, usize)> = hm.into_iter().map(|(pid, (cnt, success))| (pid, cnt, success)).collect();
+        vtakers.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        return vtakers;
+    }
+
+}
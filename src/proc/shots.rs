@@ -0,0 +1,360 @@
+//!
+//! Identify shots and their expected goals (xG)
+//! HanishKVC, 2022
+//!
+//! A kick is taken to be a candidate shot. Once the ball moves a bit
+//! after the kick, if it is heading towards the goal of the other team,
+//! from within the shooting range, it is taken to be a shot and inturn
+//! on target if its line of travel is between the goal posts.
+//!
+//! The shot is resolved based on what happens next
+//! * Goal - if the team of the shooter scores.
+//! * Saved - if the goalie of the other team catches the ball.
+//! * Blocked - if a player of the other team kicks/tackles the ball,
+//!   or play stops (other than a goal), when the shot was on target.
+//! * OnTarget/OffTarget - otherwise
+//!
+
+use std::fmt::Display;
+
+use crate::entities;
+use crate::playdata::rcss::{self, FieldGeom, PlayMode};
+use crate::proc::actions::AIAction;
+
+
+/// Shots are taken only from within this distance (meters) to the goal
+const SHOT_DIST_MAX: f32 = 35.0;
+/// Ball should move atleast this much (meters) after the kick, to decide its direction
+const SHOT_DIR_MINDIST: f32 = 1.0;
+/// If the ball doesnt move enough within these many cycles, the kick is not a shot
+const SHOT_DIR_MAXCYCLES: usize = 5;
+/// Ball heading towards the goal line within this distance (meters) beyond
+/// the goal posts, is taken to be a shot which is off target.
+const SHOT_WIDE_MARGIN: f32 = 6.0;
+/// If nothing decisive happens within these many cycles, the shot is resolved
+/// based on whether it was on target or not.
+const SHOT_RESOLVE_MAXCYCLES: usize = 50;
+
+
+#[derive(Debug, Clone, PartialEq)]
+/// A simple logistic expected goals model.
+///
+/// xg = 1/(1+e^-(intercept + dist*coef_dist + angle*coef_angle + defenders*coef_defenders))
+pub struct XGModel {
+    pub intercept: f32,
+    /// wrt distance to the center of the goal, in meters
+    pub coef_dist: f32,
+    /// wrt angle (radians) subtended by the goal posts at the shot position
+    pub coef_angle: f32,
+    /// wrt the number of players of the other team in the cone between the shot position and the goal posts
+    pub coef_defenders: f32,
+}
+
+impl XGModel {
+
+    pub fn new(intercept: f32, coef_dist: f32, coef_angle: f32, coef_defenders: f32) -> XGModel {
+        XGModel {
+            intercept: intercept,
+            coef_dist: coef_dist,
+            coef_angle: coef_angle,
+            coef_defenders: coef_defenders,
+        }
+    }
+
+    pub fn xg(&self, dist: f32, angle: f32, defenders: usize) -> f32 {
+        let z = self.intercept + dist*self.coef_dist + angle*self.coef_angle + (defenders as f32)*self.coef_defenders;
+        return 1.0/(1.0 + (-z).exp());
+    }
+
+}
+
+impl Default for XGModel {
+    fn default() -> Self {
+        XGModel::new(3.2, -0.3, 1.5, -0.5)
+    }
+}
+
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ShotOutcome {
+    Pending,
+    Goal,
+    Saved,
+    Blocked,
+    OnTarget,
+    OffTarget,
+}
+
+impl Display for ShotOutcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let soutcome = match self {
+            ShotOutcome::Pending => "pending",
+            ShotOutcome::Goal => "goal",
+            ShotOutcome::Saved => "saved",
+            ShotOutcome::Blocked => "blocked",
+            ShotOutcome::OnTarget => "ontarget",
+            ShotOutcome::OffTarget => "offtarget",
+        };
+        f.write_str(soutcome)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Shot {
+    pub time: usize,
+    pub side: char,
+    pub playerid: String,
+    /// The position of the shot, in normal space
+    pub npos: (f32, f32),
+    /// Distance (meters) to the center of the goal
+    pub dist: f32,
+    /// The angle (radians) subtended by the goal posts
+    pub angle: f32,
+    /// The number of players of the other team in the shot cone
+    pub defenders: usize,
+    pub xg: f32,
+    pub ontarget: bool,
+    pub outcome: ShotOutcome,
+}
+
+impl Display for Shot {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&format!("{}{:02}:{}:Dist:{:.1}:Angle:{:.2}:Defenders:{}:xG:{:.2}:{}",
+            self.side.to_uppercase(), self.playerid, self.time, self.dist, self.angle, self.defenders, self.xg, self.outcome))
+    }
+}
+
+
+#[derive(Debug)]
+pub struct Shots {
+    pub xgmodel: XGModel,
+    /// The field geometry, wrt the goal position and width
+    pub field: FieldGeom,
+    pub shots: Vec<Shot>,
+    /// The latest kick, which could turn out to be a shot
+    candidate: Option<Shot>,
+    /// The shot which is yet to be resolved, if any
+    pending: Option<usize>,
+}
+
+impl Shots {
+
+    pub fn new() -> Shots {
+        Shots {
+            xgmodel: XGModel::default(),
+            field: FieldGeom::new(),
+            shots: Vec::new(),
+            candidate: None,
+            pending: None,
+        }
+    }
+
+    /// The x coordinate (rcss space) of the goal line attacked by the given side
+    fn goal_x(&self, side: char) -> f32 {
+        let x = self.field.pitch_length/2.0;
+        if side == entities::SIDE_L { x } else { -x }
+    }
+
+    /// Get the distance, angle and defenders wrt a shot by the side, from the given position (rcss space).
+    /// opponents: the positions (normal space) of the players of the other team.
+    pub fn shot_geometry(&self, side: char, pos: (f32, f32), opponents: &Vec<(f32, f32)>) -> (f32, f32, usize) {
+        let gx = self.goal_x(side);
+        let hw = self.field.goal_width/2.0;
+        let (p1, p2) = ((gx, -hw), (gx, hw));
+        let dist = ((gx-pos.0).powi(2) + pos.1.powi(2)).sqrt();
+        let a1 = (p1.1-pos.1).atan2(p1.0-pos.0);
+        let a2 = (p2.1-pos.1).atan2(p2.0-pos.0);
+        let mut angle = (a2-a1).abs();
+        if angle > std::f32::consts::PI {
+            angle = 2.0*std::f32::consts::PI - angle;
+        }
+        let mut defenders = 0;
+        for opos in opponents {
+            if in_triangle(rcss::npos_to_rcss(*opos), pos, p1, p2) {
+                defenders += 1;
+            }
+        }
+        return (dist, angle, defenders);
+    }
+
+    /// Resolve the pending shot if any, with the given outcome
+    fn resolve(&mut self, outcome: ShotOutcome) {
+        if let Some(i) = self.pending.take() {
+            self.shots[i].outcome = outcome;
+        }
+    }
+
+    /// Resolve the pending shot if any, based on it being on target or not
+    fn resolve_default(&mut self) {
+        if let Some(i) = self.pending {
+            let outcome = if self.shots[i].ontarget { ShotOutcome::OnTarget } else { ShotOutcome::OffTarget };
+            self.resolve(outcome);
+        }
+    }
+
+    /// Whether a catch by the given side would save the pending shot, if any
+    pub fn catch_saves(&self, side: char) -> bool {
+        match self.pending {
+            Some(i) => (self.shots[i].side != side) && self.shots[i].ontarget,
+            None => false,
+        }
+    }
+
+    /// Handle a ball related action by a player.
+    /// opponents: the positions (normal space) of the players of the other team wrt the player.
+    pub fn handle_action(&mut self, time: usize, side: char, playerid: &str, action: &AIAction, bnpos: (f32, f32), opponents: &Vec<(f32, f32)>) {
+        if let Some(i) = self.pending {
+            if self.shots[i].side != side {
+                match action {
+                    // A catch of a shot which wasnt on target, is not a save
+                    AIAction::Catch if self.shots[i].ontarget => self.resolve(ShotOutcome::Saved),
+                    AIAction::Catch => self.resolve_default(),
+                    AIAction::Kick | AIAction::Tackle => self.resolve(ShotOutcome::Blocked),
+                    _ => (),
+                }
+            } else if *action == AIAction::Kick {
+                self.resolve_default();
+            }
+        }
+        if *action != AIAction::Kick {
+            return;
+        }
+        let pos = rcss::npos_to_rcss(bnpos);
+        let (dist, angle, defenders) = self.shot_geometry(side, pos, opponents);
+        if dist > SHOT_DIST_MAX {
+            self.candidate = None;
+            return;
+        }
+        self.candidate = Some(Shot {
+            time: time,
+            side: side,
+            playerid: playerid.to_string(),
+            npos: bnpos,
+            dist: dist,
+            angle: angle,
+            defenders: defenders,
+            xg: self.xgmodel.xg(dist, angle, defenders),
+            ontarget: false,
+            outcome: ShotOutcome::Pending,
+        });
+    }
+
+    /// Decide whether the candidate kick is a shot, based on the movement of the ball after the kick.
+    fn check_candidate(&mut self, time: usize, bnpos: (f32, f32)) {
+        let cand = match &self.candidate {
+            Some(cand) => cand,
+            None => return,
+        };
+        if time <= cand.time {
+            return;
+        }
+        let spos = rcss::npos_to_rcss(cand.npos);
+        let bpos = rcss::npos_to_rcss(bnpos);
+        let (dx, dy) = (bpos.0-spos.0, bpos.1-spos.1);
+        if (dx*dx + dy*dy).sqrt() < SHOT_DIR_MINDIST {
+            if (time - cand.time) > SHOT_DIR_MAXCYCLES {
+                self.candidate = None;
+            }
+            return;
+        }
+        let mut cand = self.candidate.take().unwrap();
+        let gx = self.goal_x(cand.side);
+        if (gx-spos.0)*dx <= 0.0 {
+            return;
+        }
+        // Where the ball would cross the goal line, if it continues in the same direction
+        let gy = spos.1 + dy*(gx-spos.0)/dx;
+        let hw = self.field.goal_width/2.0;
+        if gy.abs() > (hw + SHOT_WIDE_MARGIN) {
+            return;
+        }
+        cand.ontarget = gy.abs() <= hw;
+        self.resolve_default();
+        self.shots.push(cand);
+        self.pending = Some(self.shots.len()-1);
+    }
+
+    /// Handle a cycle, wrt the play mode (None means no change) and the ball position (normal space)
+    pub fn handle_cycle(&mut self, time: usize, state: &Option<PlayMode>, bnpos: (f32, f32)) {
+        self.check_candidate(time, bnpos);
+        if let Some(i) = self.pending {
+            let shot = &self.shots[i];
+            match state {
+                None | Some(PlayMode::PlayOn) => {
+                    if time > (shot.time + SHOT_RESOLVE_MAXCYCLES) {
+                        self.resolve_default();
+                    }
+                },
+                Some(PlayMode::Goal(side)) if *side == shot.side => self.resolve(ShotOutcome::Goal),
+                _ => {
+                    if shot.ontarget {
+                        self.resolve(ShotOutcome::Blocked);
+                    } else {
+                        self.resolve(ShotOutcome::OffTarget);
+                    }
+                },
+            }
+        }
+        match state {
+            None | Some(PlayMode::PlayOn) => (),
+            _ => self.candidate = None,
+        }
+    }
+
+    /// Forget the shots at or after the given time, wrt a seek back in time
+    pub fn seek_to(&mut self, time: usize) {
+        self.shots.retain(|shot| shot.time < time);
+        self.candidate = None;
+        if let Some(i) = self.pending {
+            if i >= self.shots.len() {
+                self.pending = None;
+            }
+        }
+    }
+
+    /// The cumulative xG over time wrt the given side
+    pub fn xg_cumul(&self, side: char) -> Vec<(usize, f32)> {
+        let mut vxg = vec![(0, 0.0)];
+        let mut xg = 0.0;
+        for shot in &self.shots {
+            if shot.side != side {
+                continue;
+            }
+            vxg.push((shot.time, xg));
+            xg += shot.xg;
+            vxg.push((shot.time, xg));
+        }
+        return vxg;
+    }
+
+    /// The number of shots, shots on target, goals and total xG wrt the given side
+    pub fn stats(&self, side: char) -> (usize, usize, usize, f32) {
+        let (mut cnt, mut ontarget, mut goals, mut xg) = (0, 0, 0, 0.0);
+        for shot in self.shots.iter().filter(|shot| shot.side == side) {
+            cnt += 1;
+            if shot.ontarget {
+                ontarget += 1;
+            }
+            if shot.outcome == ShotOutcome::Goal {
+                goals += 1;
+            }
+            xg += shot.xg;
+        }
+        return (cnt, ontarget, goals, xg);
+    }
+
+}
+
+
+/// Check if the point p is within the triangle a,b,c
+fn in_triangle(p: (f32, f32), a: (f32, f32), b: (f32, f32), c: (f32, f32)) -> bool {
+    let sign = |p1: (f32, f32), p2: (f32, f32), p3: (f32, f32)| -> f32 {
+        (p1.0-p3.0)*(p2.1-p3.1) - (p2.0-p3.0)*(p1.1-p3.1)
+    };
+    let d1 = sign(p, a, b);
+    let d2 = sign(p, b, c);
+    let d3 = sign(p, c, a);
+    let bneg = (d1 < 0.0) || (d2 < 0.0) || (d3 < 0.0);
+    let bpos = (d1 > 0.0) || (d2 > 0.0) || (d3 > 0.0);
+    return !(bneg && bpos);
+}
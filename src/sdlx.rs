@@ -7,7 +7,7 @@ use sdl2::gfx::primitives::DrawRenderer;
 use sdl2::rect::Rect;
 use sdl2::{self, VideoSubsystem, Sdl, EventPump, ttf::Font, surface::Surface};
 use sdl2::render::{WindowCanvas, TextureCreator, Texture, BlendMode};
-use sdl2::video::WindowContext;
+use sdl2::video::{WindowContext, FullscreenType};
 pub use sdl2::pixels::Color;
 
 use datautilsk::sigpro;
@@ -19,8 +19,23 @@ pub const COLOR_MSG_HEAD_BACKGROUND: Color = Color::RGBA(80, 80, 80, 180);
 pub const COLOR_MSG_HEAD_TEXT: Color = Color::WHITE;
 const STRING_CHAR_PIXEL_WIDTH: f32 = 8.0;
 const STRING_CHAR_PIXEL_HEIGHT: f32 = 8.0;
+/// The builtin pixel font is not scaled down below this
+const STRING_SCALE_MIN: f32 = 0.5;
 pub const TTF_FONT: &str = "/usr/share/fonts/truetype/freefont/FreeMonoBold.ttf";
 pub const TTF_FONT_SIZE: u16 = 16;
+/// The monospace ttf fonts to look for, in the order of preference
+const TTF_FONT_NAMES: [&str; 10] = [
+    "FreeMonoBold.ttf", "DejaVuSansMono-Bold.ttf", "LiberationMono-Bold.ttf",
+    "NotoSansMono-Bold.ttf", "UbuntuMono-B.ttf", "FreeMono.ttf",
+    "DejaVuSansMono.ttf", "LiberationMono-Regular.ttf", "NotoSansMono-Regular.ttf",
+    "UbuntuMono-R.ttf",
+];
+/// The dirs to search for fonts, relative ones are wrt the users home dir
+const TTF_FONT_DIRS: [&str; 6] = [
+    "/usr/share/fonts", "/usr/local/share/fonts", ".local/share/fonts", ".fonts",
+    "/Library/Fonts", "/System/Library/Fonts",
+];
+const TTF_FONT_DIRS_MAXDEPTH: usize = 4;
 
 
 pub static mut PRG_WIDTH: u32 = 1024;
@@ -51,6 +66,13 @@ pub struct SdlX {
     pub ep: EventPump,
     pub wctc: TextureCreator<WindowContext>,
     pub n2s: XSpaces,
+    /// The view (zoom/pan) applied to the normal space, when enabled
+    /// using view_apply, wrt drawing the playground.
+    pub view: XView,
+    /// The window height at init, wrt which the text is scaled
+    baseheight: u32,
+    /// The scale applied to the builtin pixel font, based on the window height
+    pub fontscale: f32,
 }
 
 impl SdlX {
@@ -87,16 +109,23 @@ impl SdlX {
     /// If required pass hint to WM during window creation, that
     /// we dont want to participate in _NET_WM_PING mechanism
     ///
-    pub fn init_plus(title: &str, width: u32, height: u32, ignore_wmping: bool) -> SdlX {
+    /// If bfitdisplay, the window size is decided based on the display,
+    /// else (or if display info is not available) width x height is used.
+    ///
+    pub fn init_plus(title: &str, width: u32, height: u32, bfitdisplay: bool, ignore_wmping: bool) -> SdlX {
         let ctxt = sdl2::init().unwrap();
         // Setup window
         let vs = ctxt.video().unwrap();
-        let (width, height) = Self::find_prg_resolution(&vs, width, height);
+        let (width, height) = if bfitdisplay {
+            Self::find_prg_resolution(&vs, width, height)
+        } else {
+            (width, height)
+        };
         update_prg_resolution(width, height);
         if ignore_wmping {
             sdl2::hint::set(&String::from_utf8(sdl2::sys::SDL_HINT_VIDEO_X11_NET_WM_PING.to_vec()).unwrap(), "0");
         }
-        let win = vs.window(title, width, height).build().unwrap();
+        let win = vs.window(title, width, height).resizable().build().unwrap();
         let wc = win.into_canvas().build().unwrap();
         let wctc = wc.texture_creator();
         // Setup events
@@ -113,7 +142,41 @@ impl SdlX {
             ep: ep,
             wctc: wctc,
             n2s: XSpaces::new(drect, orect),
+            view: XView::new(),
+            baseheight: height,
+            fontscale: 1.0,
+        }
+    }
+
+    /// Update the screen size, when the window is resized.
+    /// The normal space is mapped to the new size, so that everything drawn
+    /// in normal space, scales to the new size. The text is scaled wrt the
+    /// new height, so that it remains in proportion to the rest.
+    pub fn resize(&mut self, width: u32, height: u32) {
+        update_prg_resolution(width, height);
+        self.n2s = XSpaces::new(((0.0,0.0), (1.0,1.0)), ((0.0,0.0), (width as f32, height as f32)));
+        self.fontscale = (height as f32/self.baseheight as f32).max(STRING_SCALE_MIN);
+    }
+
+    /// Toggle between the window and fullscreen (desktop resolution) mode.
+    /// Returns true if now in fullscreen mode.
+    pub fn toggle_fullscreen(&mut self) -> bool {
+        let win = self.wc.window_mut();
+        let ft = if win.fullscreen_state() == FullscreenType::Off { FullscreenType::Desktop } else { FullscreenType::Off };
+        if let Err(err) = win.set_fullscreen(ft) {
+            eprintln!("WARN:SdlX:Fullscreen:{:?}:{}", ft, err);
         }
+        let (width, height) = self.wc.window().size();
+        self.resize(width, height);
+        return self.wc.window().fullscreen_state() != FullscreenType::Off;
+    }
+
+    /// Apply (or remove) the view transform wrt the normal space.
+    /// When applied, the normal space region currently in view is mapped
+    /// to the full screen, so all the n* drawing calls get zoomed/panned.
+    pub fn view_apply(&mut self, bapply: bool) {
+        let drect = if bapply { self.view.drect() } else { ((0.0,0.0), (1.0,1.0)) };
+        self.n2s = XSpaces::new(drect, self.n2s.orect);
     }
 
 }
@@ -129,6 +192,66 @@ impl SdlX {
 
 }
 
+/// Search the given dir and its sub dirs (upto maxdepth), for files with
+/// any of the given names. The found files are added into vfound at the
+/// index corresponding to their name.
+fn find_files(dir: &std::path::Path, vnames: &[&str], vfound: &mut Vec<Vec<String>>, maxdepth: usize) {
+    let rd = match std::fs::read_dir(dir) {
+        Ok(rd) => rd,
+        Err(_) => return,
+    };
+    for de in rd.flatten() {
+        let path = de.path();
+        if path.is_dir() {
+            if maxdepth > 0 {
+                find_files(&path, vnames, vfound, maxdepth-1);
+            }
+            continue;
+        }
+        let fname = de.file_name();
+        if let Some(i) = vnames.iter().position(|name| fname.to_str() == Some(name)) {
+            vfound[i].push(path.to_string_lossy().to_string());
+        }
+    }
+}
+
+/// Find the ttf fonts which could be used by the program, in the order of preference
+/// * the preferred font specified by the user/program
+/// * the monospace font suggested by fontconfig, if available
+/// * the known monospace fonts found in the common font dirs
+pub fn find_ttf_fonts(preferred: &str) -> Vec<String> {
+    let mut vfonts = Vec::new();
+    if preferred.len() > 0 {
+        vfonts.push(preferred.to_string());
+    }
+    let fcm = std::process::Command::new("fc-match").args(["-f", "%{file}", "monospace:bold"]).output();
+    if let Ok(fcm) = fcm {
+        let sfile = String::from_utf8_lossy(&fcm.stdout).trim().to_string();
+        if fcm.status.success() && sfile.to_lowercase().ends_with(".ttf") {
+            vfonts.push(sfile);
+        }
+    }
+    let home = std::env::var("HOME").unwrap_or_default();
+    let mut vfound = vec![Vec::new(); TTF_FONT_NAMES.len()];
+    for dir in TTF_FONT_DIRS {
+        let dir = if dir.starts_with('/') {
+            std::path::PathBuf::from(dir)
+        } else {
+            if home.len() == 0 {
+                continue;
+            }
+            std::path::Path::new(&home).join(dir)
+        };
+        find_files(&dir, &TTF_FONT_NAMES, &mut vfound, TTF_FONT_DIRS_MAXDEPTH);
+    }
+    for sfont in vfound.into_iter().flatten() {
+        if !vfonts.contains(&sfont) {
+            vfonts.push(sfont);
+        }
+    }
+    vfonts
+}
+
 /// Create a surface with a image of the passed text
 pub fn text_surface<'a>(font: &'a Font, text: &str, color: Color) -> Surface<'a> {
     return font.render(text).blended(color).unwrap();
@@ -196,6 +319,16 @@ impl XSpaces {
         return (self.d2ox(d.0), self.d2oy(d.1));
     }
 
+    /// Convert a length along x-axis, from Data to Other space
+    pub fn d2o_dx(&self, ddx: f32) -> f32 {
+        return ddx * self.d2o.0;
+    }
+
+    /// Convert a length along y-axis, from Data to Other space
+    pub fn d2o_dy(&self, ddy: f32) -> f32 {
+        return ddy * self.d2o.1;
+    }
+
 }
 
 #[allow(dead_code)]
@@ -220,6 +353,85 @@ impl XSpaces {
         return (self.o2dx(o.0), self.o2dy(o.1));
     }
 
+    /// Convert a length along x-axis, from Other to Data space
+    pub fn o2d_dx(&self, odx: f32) -> f32 {
+        return odx * self.o2d.0;
+    }
+
+    /// Convert a length along y-axis, from Other to Data space
+    pub fn o2d_dy(&self, ody: f32) -> f32 {
+        return ody * self.o2d.1;
+    }
+
+}
+
+
+/// A view (camera) wrt the normal 0.0-1.0 space, which allows
+/// zooming in and panning around, while remaining within it.
+#[derive(Debug, Clone)]
+pub struct XView {
+    /// The zoom level, 1.0 means whole normal space is in view
+    pub zoom: f32,
+    /// The center of the view, in normal space
+    pub center: XPoint,
+}
+
+pub const XVIEW_ZOOM_MAX: f32 = 8.0;
+
+impl XView {
+
+    pub fn new() -> XView {
+        XView {
+            zoom: 1.0,
+            center: (0.5, 0.5),
+        }
+    }
+
+    /// The region of the normal space currently in view
+    pub fn drect(&self) -> XRect {
+        let half = 0.5/self.zoom;
+        ((self.center.0-half, self.center.1-half), (self.center.0+half, self.center.1+half))
+    }
+
+    /// Keep the view within the normal space
+    fn clamp(&mut self) {
+        self.zoom = self.zoom.max(1.0).min(XVIEW_ZOOM_MAX);
+        let half = 0.5/self.zoom;
+        self.center.0 = self.center.0.max(half).min(1.0-half);
+        self.center.1 = self.center.1.max(half).min(1.0-half);
+    }
+
+    /// Map a screen position (in normal space) to the position in view (in normal space)
+    pub fn s2v(&self, spos: XPoint) -> XPoint {
+        let ((x1,y1),(x2,y2)) = self.drect();
+        (x1 + spos.0*(x2-x1), y1 + spos.1*(y2-y1))
+    }
+
+    /// Zoom in (ratio > 1) or out (ratio < 1), keeping the given
+    /// position (in normal space) fixed on the screen.
+    pub fn zoom_by(&mut self, ratio: f32, about: XPoint) {
+        let oldzoom = self.zoom;
+        self.zoom = (self.zoom*ratio).max(1.0).min(XVIEW_ZOOM_MAX);
+        let adj = oldzoom/self.zoom;
+        self.center.0 = about.0 + (self.center.0 - about.0)*adj;
+        self.center.1 = about.1 + (self.center.1 - about.1)*adj;
+        self.clamp();
+    }
+
+    /// Pan the view, by the given screen distance (in normal space),
+    /// ie the contents move along with a mouse drag.
+    pub fn pan(&mut self, sdx: f32, sdy: f32) {
+        self.center.0 -= sdx/self.zoom;
+        self.center.1 -= sdy/self.zoom;
+        self.clamp();
+    }
+
+    /// Center the view on the given position (in normal space), as far as possible.
+    pub fn center_on(&mut self, npos: XPoint) {
+        self.center = npos;
+        self.clamp();
+    }
+
 }
 
 
@@ -272,9 +484,9 @@ impl SdlX {
         let y2 = self.n2s.d2oy(ny2).round() as i16;
         let mut sw;
         if (x2-x1).abs() > (y2-y1).abs() {
-            sw = self.n2s.d2ox(nw).round() as u8;
+            sw = self.n2s.d2o_dx(nw).round() as u8;
         } else {
-            sw = self.n2s.d2oy(nw).round() as u8;
+            sw = self.n2s.d2o_dy(nw).round() as u8;
         }
         if sw < 1 {
             sw = 1;
@@ -285,16 +497,31 @@ impl SdlX {
 
     /// Draw a string.
     /// Takes the starting point (horiz_left-vert_mid) for drawing in normal space.
-    pub fn n_string(&self, nx: f32, ny: f32, s: &str, color: Color) {
-        let sx = self.n2s.d2ox(nx).round() as i16;
-        let sy = self.n2s.d2oy(ny).round() as i16;
+    /// The builtin pixel font is scaled as per fontscale.
+    pub fn n_string(&mut self, nx: f32, ny: f32, s: &str, color: Color) {
+        let fs = self.fontscale;
+        let sx = (self.n2s.d2ox(nx)/fs).round() as i16;
+        let sy = (self.n2s.d2oy(ny)/fs).round() as i16;
+        if fs != 1.0 {
+            self.wc.set_scale(fs, fs).unwrap();
+        }
         self.wc.string(sx, sy, s, color).unwrap();
+        if fs != 1.0 {
+            self.wc.set_scale(1.0, 1.0).unwrap();
+        }
+    }
+
+    /// The width and height of a char of the (scaled) builtin pixel font, in normal space
+    pub fn n_char_wh(&self) -> (f32, f32) {
+        let cw = self.n2s.o2d_dx(STRING_CHAR_PIXEL_WIDTH*self.fontscale);
+        let ch = self.n2s.o2d_dy(STRING_CHAR_PIXEL_HEIGHT*self.fontscale);
+        return (cw, ch);
     }
 
     /// Draw/Show multiple lines on the screen.
     /// The starting point as well as the gap between lines is given in normal space.
     /// nlh: gives the height to be used wrt each line
-    pub fn n_strings(&self, nx: f32, ny: f32, nlh: f32, ss: Vec<&str>, color: Color) {
+    pub fn n_strings(&mut self, nx: f32, ny: f32, nlh: f32, ss: Vec<&str>, color: Color) {
         for i in 0..ss.len() {
             let y = ny + (i as f32 * nlh);
             self.n_string(nx, y, ss[i], color);
@@ -303,10 +530,11 @@ impl SdlX {
 
     /// Draw a string along with a partially translucent (gray) background box/rect.
     /// Takes the starting point for drawing in normal space.
+    /// The message is moved left if required, so that it doesnt go beyond the screen.
     pub fn n_msg(&mut self, nx: f32, ny: f32, msg: &str, color: Color) {
-        let cw = self.n2s.o2dx(STRING_CHAR_PIXEL_WIDTH);
-        let ch = self.n2s.o2dy(STRING_CHAR_PIXEL_HEIGHT);
+        let (cw, ch) = self.n_char_wh();
         let nw = (msg.len()+2) as f32 * cw;
+        let nx = nx.min(self.n2s.o2dx(self.n2s.orect.1.0) - nw + cw);
         let rx = nx-cw;
         let ry = ny-(ch*0.5);
         self.wc.set_draw_color(COLOR_MSG_BOX_BACKGROUND);
@@ -318,8 +546,7 @@ impl SdlX {
     /// Draw a string along with a partially translucent (gray) background box/rect.
     /// Takes the horiz_mid-vert_mid point for drawing in normal space.
     pub fn n_msg_mid(&mut self, nx: f32, ny: f32, msg: &str, color: Color) {
-        let cw = self.n2s.o2dx(STRING_CHAR_PIXEL_WIDTH);
-        let ch = self.n2s.o2dy(STRING_CHAR_PIXEL_HEIGHT);
+        let (cw, ch) = self.n_char_wh();
         let nw = (msg.len()+2) as f32 * cw;
         let rx = nx - nw/2.0;
         let nx = rx+cw;
@@ -348,7 +575,7 @@ impl SdlX {
         self.wc.set_draw_color(COLOR_MSG_HEAD_BACKGROUND);
         self.nn_fill_rect(nr.0, nr.1, nr.2, nlh*(2 as f32));
         // Heading text
-        let ncw = self.n2s.o2dx(STRING_CHAR_PIXEL_WIDTH);
+        let (ncw, _nch) = self.n_char_wh();
         let hlen = ss[0].len() as f32*ncw;
         let hbefore = (nr.2 - hlen)/2.0;
         //eprintln!("DBUG:SdlX:NMsgBox:[{:?}]:hlen[{}]:hb4[{}]:", nr, hlen, hbefore);
@@ -383,7 +610,7 @@ impl SdlX {
 
     #[allow(dead_code)]
     pub fn n_arc(&self, nx: f32, ny: f32, nrad: f32, nstartangle: f32, nendangle: f32, width: isize, color: Color) {
-        let radius = self.n2s.d2ox(nrad).round() as i16;
+        let radius = self.n2s.d2o_dx(nrad).round() as i16;
         let ssdeg = (nstartangle*360.0).round() as i16;
         let sedeg = (nendangle*360.0).round() as i16;
         self.ns_arc(nx, ny, radius, ssdeg, sedeg, width, color);
@@ -451,8 +678,8 @@ impl SdlX {
     pub fn n_plot_f(&self, nx: f32, ny: f32, nw: f32, nh: f32, vdata: Vec<f32>, ymin: f32, ymax: f32) {
         let sx = self.n2s.d2ox(nx).round() as i16;
         let sy = self.n2s.d2oy(ny).round() as i16;
-        let sw = self.n2s.d2ox(nw);
-        let sh = self.n2s.d2oy(nh);
+        let sw = self.n2s.d2o_dx(nw);
+        let sh = self.n2s.d2o_dy(nh);
         let dw = vdata.len();
         let dh = ymax - ymin;
         let _spdw = sw/dw as f32;
@@ -520,8 +747,8 @@ impl SdlX {
     pub fn n_plot_uf(&self, nx: f32, ny: f32, nw: f32, nh: f32, vindata: &Vec<(usize, f32)>, xmin: f32, xmax: f32, ymin: f32, ymax: f32, weights: Option<Vec<f32>>, stag: &str, plottype: PlotType) {
         let sx = self.n2s.d2ox(nx).round();
         let sy = self.n2s.d2oy(ny).round();
-        let sw = self.n2s.d2ox(nw).round();
-        let sh = self.n2s.d2oy(nh).round();
+        let sw = self.n2s.d2o_dx(nw).round();
+        let sh = self.n2s.d2o_dy(nh).round();
         let crosscorr;
         if weights.is_some() {
             let weights = weights.as_ref().unwrap();
@@ -6,7 +6,7 @@
 use sdl2::pixels::Color;
 use sdl2::ttf::Font;
 
-use crate::playdata::GameState;
+use crate::playdata::rcss::PlayMode;
 use crate::sdlx::{self, SdlX};
 use crate::entities;
 use crate::entities::gentity::GEntity;
@@ -20,14 +20,14 @@ pub fn test_ncolor() {
 }
 
 pub fn test_gentity(font: &Font) {
-    let mut g1 = GEntity::new("test01", (0.5,0.5), (16,16), Color::WHITE, font);
+    let mut g1 = GEntity::new("test01", (0.5,0.5), (16,16), Color::WHITE, Some(font));
     g1.set_fcolor(0.25, 1.0);
     g1.set_nxarc(1.2, 0.98, Color::RED);
 }
 
 #[allow(dead_code)]
 pub fn test_dummy() {
-    eprintln!("{},{:?}", entities::SIDE_R, GameState::PlayPaused);
+    eprintln!("{},{:?}", entities::SIDE_R, PlayMode::Pause);
 }
 
 #[allow(dead_code)]
@@ -153,7 +153,7 @@ fn test_sdlx_xspaces_srinmid() {
 pub fn sdlx_plots() {
     use sdl2::event::Event;
     use sdlx::PlotType;
-    let mut sx = sdlx::SdlX::init_plus("Test SdlX", 640, 480, false);
+    let mut sx = sdlx::SdlX::init_plus("Test SdlX", 640, 480, true, false);
     'mainloop: loop {
         for ev in sx.ep.poll_iter() {
             match ev {
@@ -173,3 +173,853 @@ pub fn sdlx_plots() {
 fn test_sdlx_plots() {
     sdlx_plots();
 }
+
+#[test]
+fn test_rcss_sexp() {
+    use crate::playdata::rcss;
+    let vblocks = rcss::sexp_blocks("(turn 30)(say \"hi (all)\") (kick 100 -20)");
+    assert_eq!(vblocks, vec!["(turn 30)", "(say \"hi (all)\")", "(kick 100 -20)"]);
+    let vtoks = rcss::sexp_tokens("(move (player l 1) 10 -5 \"a b\")");
+    assert_eq!(vtoks, vec!["move", "(player l 1)", "10", "-5", "\"a b\""]);
+    let vtoks = rcss::sexp_tokens("(server_param (goal_width 14.02)(ball_size 0.085))");
+    assert_eq!(vtoks, vec!["server_param", "(goal_width 14.02)", "(ball_size 0.085)"]);
+}
+
+#[test]
+fn test_rcss_rccmd() {
+    use crate::playdata::rcss::{self, RCCmd};
+    use crate::keys::{KeyBindings, KBMatch, ProgramEvent};
+    assert_eq!(RCCmd::parse("move ball 1.5 -2"), Ok(RCCmd::MoveBall(1.5, -2.0)));
+    assert_eq!(RCCmd::parse("(move (ball) 1.5 -2)"), Ok(RCCmd::MoveBall(1.5, -2.0)));
+    assert_eq!(RCCmd::parse("move (player l 3) -10 5 90").unwrap().to_string(), "(move (player l 3) -10 5 90)");
+    assert_eq!(RCCmd::parse("move player r 7 1 2").unwrap(), RCCmd::MovePlayer('r', 7, 1.0, 2.0, None));
+    assert_eq!(RCCmd::parse("change_player_type l 3 2").unwrap().to_string(), "(change_player_type l 3 2)");
+    assert_eq!(RCCmd::parse("dispfoul 10 -5 r").unwrap().to_string(), "(dispfoul 160 -80 -1)");
+    assert_eq!(RCCmd::parse("dispplayer l 3 1 2 45").unwrap().to_string(), "(dispplayer 1 3 16 32 45)");
+    assert_eq!(RCCmd::from_code(rcss::CMDCODE_PAUSE, 5).unwrap().to_string(), "(change_mode pause)");
+    assert_eq!(RCCmd::from_code(rcss::CMDCODE_RESUME, 5).unwrap().to_string(), "(change_mode play_on)");
+    assert_eq!(RCCmd::parse("pause").unwrap().to_string(), "(change_mode pause)");
+    assert_eq!(RCCmd::parse("resume").unwrap().to_string(), "(change_mode play_on)");
+    let kb = KeyBindings::new();
+    assert!(matches!(kb.lookup("c 2"), KBMatch::Exact(ProgramEvent::SendRecordCoded(rcss::CMDCODE_PAUSE))));
+    assert!(matches!(kb.lookup("c 3"), KBMatch::Exact(ProgramEvent::SendRecordCoded(rcss::CMDCODE_RESUME))));
+    assert!(RCCmd::parse("move goal 1 2").is_err());
+    assert!(RCCmd::parse("dispfoul 10 x l").is_err());
+    assert!(RCCmd::parse("").is_err());
+}
+
+#[test]
+fn test_console_parse() {
+    use crate::console;
+    use crate::keys::ProgramEvent;
+    assert!(matches!(console::parse("seek 1200"), Ok(ProgramEvent::SeekTo(1200))));
+    assert!(matches!(console::parse("seek -50"), Ok(ProgramEvent::Seek(-50))));
+    assert!(matches!(console::parse("seek +50"), Ok(ProgramEvent::Seek(50))));
+    assert!(matches!(console::parse("fps 48"), Ok(ProgramEvent::SetFPS(fps)) if fps == 48.0));
+    assert!(matches!(console::parse("select l7"), Ok(ProgramEvent::SelectPlayer(Some((side, pid)))) if (side == 'l') && (pid == "7")));
+    assert!(matches!(console::parse("select none"), Ok(ProgramEvent::SelectPlayer(None))));
+    assert!(matches!(console::parse("export json out.json"), Ok(ProgramEvent::ExportJson(fname)) if fname == "out.json"));
+    assert!(matches!(console::parse("send (change_mode play_on)"), Ok(ProgramEvent::SendCommand(scmd)) if scmd == "(change_mode play_on)"));
+    assert!(matches!(console::parse("move ball 0 0"), Ok(ProgramEvent::SendCommand(_))));
+    assert!(console::parse("select x7").is_err());
+    assert!(console::parse("select l12").is_err());
+    assert!(console::parse("fps fast").is_err());
+    assert!(console::parse("export xml out.xml").is_err());
+    assert!(console::parse("send (move goal)").is_err());
+    assert!(console::parse("dance").is_err());
+}
+
+#[test]
+fn test_keybindings() {
+    use crate::keys::{KeyBindings, KBMatch, ProgramEvent};
+    let mut kb = KeyBindings::new();
+    assert!(matches!(kb.lookup("p"), KBMatch::Exact(ProgramEvent::Pause)));
+    assert!(matches!(kb.lookup("d"), KBMatch::Prefix));
+    assert!(matches!(kb.lookup("d A"), KBMatch::Exact(ProgramEvent::DumpAIScoresSummary('A'))));
+    assert!(matches!(kb.lookup("x"), KBMatch::None));
+    let (keyseq, event, desc) = KeyBindings::parse_line(" g  k = SendCommand(change_mode kick_off_l) # kick off").unwrap().unwrap();
+    assert_eq!(keyseq, "g k");
+    assert_eq!(event, ProgramEvent::SendCommand("change_mode kick_off_l".to_string()));
+    assert_eq!(desc, "kick off");
+    assert!(KeyBindings::parse_line("# comment").unwrap().is_none());
+    assert!(KeyBindings::parse_line("q = Dance").is_err());
+    assert!(KeyBindings::parse_line("q = AdjustFPS(fast)").is_err());
+    assert!(KeyBindings::parse_line("q Pause").is_err());
+    kb.load_str("p = None\nq = Quit\nF = AdjustFPS(2.0) # faster", "test");
+    assert!(matches!(kb.lookup("p"), KBMatch::None));
+    assert!(matches!(kb.lookup("q"), KBMatch::Exact(ProgramEvent::Quit)));
+    let vhelp = kb.help_lines();
+    assert!(vhelp.iter().any(|l| l.starts_with("F ") && l.ends_with("faster")));
+    assert!(vhelp.iter().any(|l| l.starts_with("s c ") && l.ends_with("show/hide cards")));
+    assert!(!vhelp.iter().any(|l| l.starts_with("p ")));
+}
+
+#[test]
+fn test_rcl_parse_line() {
+    use crate::playdata::PlayerCmd;
+    use crate::playdata::rcl::Rcl;
+    let ventries = Rcl::parse_line("12,0\tRecv Team_A_7: (kick 80.5 -30)(say \"pass\")");
+    assert_eq!(ventries.len(), 2);
+    assert_eq!(ventries[0].time, 12);
+    assert_eq!(ventries[0].team, "Team_A");
+    assert_eq!(ventries[0].playerid, "7");
+    assert_eq!(ventries[0].cmd, PlayerCmd::Kick(80.5, -30.0));
+    assert_eq!(ventries[1].cmd, PlayerCmd::Say("pass".to_string()));
+    assert_eq!(Rcl::parse_line("12,0\t(referee kick_off_l)").len(), 0);
+}
+
+#[test]
+fn test_json_parse() {
+    use crate::playdata::json::{self, JValue};
+    let smsg = r#"{"type":"show", "time":12,
+        "teams":[{"side":"l","name":"A \"x\"","score":1,"extra":{"a":[1,2]}},{"side":"r","name":"B","score":0}],
+        "ball":{"x":-1.5e1,"y":2.25,"vx":0,"vy":0},
+        "players":[{"side":"l","unum":1,"stamina":{"v":8000,"e":1},"ok":true,"none":null}]}"#;
+    let jmsg = json::parse(&format!("{}\0\0", smsg)).unwrap();
+    eprintln!("Test:Json:Parse:{}", jmsg);
+    assert_eq!(jmsg.get_string("type"), "show");
+    assert_eq!(jmsg.get_f32("time", 0.0), 12.0);
+    let teams = jmsg.get("teams").unwrap().as_arr().unwrap();
+    assert_eq!(teams[0].get_string("name"), "A \"x\"");
+    assert_eq!(jmsg.get("ball").unwrap().get_f32("x", 0.0), -15.0);
+    let player = &jmsg.get("players").unwrap().as_arr().unwrap()[0];
+    assert_eq!(player.get("stamina").unwrap().get_f32("v", 0.0), 8000.0);
+    assert_eq!(player.get("ok"), Some(&JValue::Bool(true)));
+    assert_eq!(player.get("none"), Some(&JValue::Null));
+    assert_eq!(player.get("missing"), None);
+    assert!(json::parse(r#"{"type":"show","#).is_err());
+    assert!(json::parse(r#"{"a":1} x"#).is_err());
+    // Control chars should be escaped as valid json, and round trip
+    let jsay = JValue::Obj(vec![("say\n".to_string(), JValue::Str("a\x1b[1m \"b\"\\\t\u{7f}é".to_string()))]);
+    let ssay = jsay.to_string();
+    assert_eq!(ssay, "{\"say\\n\":\"a\\u001b[1m \\\"b\\\"\\\\\\t\u{7f}é\"}");
+    assert_eq!(json::parse(&ssay).unwrap(), jsay);
+}
+
+#[test]
+fn test_rcgparser_params() {
+    use crate::playdata::PlayUpdate;
+    use crate::playdata::rcg::{RcgParser, RcgRecord};
+    let parser = RcgParser::new();
+    let mut pu = PlayUpdate::new();
+    let rtype = parser.parse_line("(server_param (goal_width 14.02)(ball_size 0.085)(team_l_start \"\"))", &mut pu);
+    assert_eq!(rtype, RcgRecord::Params);
+    let rtype = parser.parse_line("(player_type (id 3)(kickable_margin 0.75))", &mut pu);
+    assert_eq!(rtype, RcgRecord::Params);
+    eprintln!("Test:RcgParser:Params:{:?}", pu.params);
+    assert_eq!(pu.params["server_param.goal_width"], "14.02");
+    assert_eq!(pu.params["player_type.3.kickable_margin"], "0.75");
+    assert_eq!(parser.parse_line("# comment", &mut pu), RcgRecord::Skip);
+    assert!(matches!(parser.parse_line("(show abc)", &mut pu), RcgRecord::Bad(_)));
+    assert!(matches!(parser.parse_line("(show)", &mut pu), RcgRecord::Bad(_)));
+    assert!(matches!(parser.parse_line("(playmode 10)", &mut pu), RcgRecord::Bad(_)));
+    assert!(matches!(parser.parse_line("()", &mut pu), RcgRecord::Bad(_)));
+}
+
+#[test]
+fn test_rcgrec_json() {
+    use crate::playdata::{json, PlayUpdate};
+    use crate::playdata::rcg::{RcgParser, RcgRecord};
+    use crate::playdata::rcgrec::RcgRecorder;
+    let fname = "/tmp/gppgnd_test_rcgrec.rcg";
+    let mut rec = RcgRecorder::new(fname);
+    let smsgs = [
+        r#"{"type":"server_param","params":{"goal_width":14.02,"team_l_start":""}}"#,
+        r#"{"type":"show","time":10,"mode":"play_on","teams":[{"side":"l","name":"A","score":1},{"side":"r","name":"B","score":0}],
+            "ball":{"x":1.5,"y":-2,"vx":0.1,"vy":0},
+            "players":[{"side":"l","unum":2,"type":0,"state":3,"x":-10,"y":5,"vx":0,"vy":0,"body":45,"neck":0,"vq":"h","vw":90,"stamina":{"v":4000,"e":1,"r":1,"c":130600}}]}"#,
+    ];
+    for smsg in smsgs {
+        rec.record_json(&json::parse(smsg).unwrap());
+    }
+    rec.flush();
+    let sdata = std::fs::read_to_string(fname).unwrap();
+    eprintln!("Test:RcgRec:Json:{}", sdata);
+    let parser = RcgParser::new();
+    let mut pu = PlayUpdate::new();
+    let mut vrtypes = Vec::new();
+    for line in sdata.lines() {
+        vrtypes.push(parser.parse_line(line, &mut pu));
+    }
+    assert_eq!(vrtypes, vec![RcgRecord::Skip, RcgRecord::Params, RcgRecord::PlayMode, RcgRecord::Team("A".to_string(), "B".to_string()), RcgRecord::Show]);
+    assert_eq!(pu.timecounter, 10);
+    assert_eq!(pu.params["server_param.goal_width"], "14.02");
+    assert_eq!(pu.lteamcoded.len(), 1);
+    assert_eq!(pu.lteamcoded[0].0, "2");
+}
+
+
+/// A minimal fake rcssserver, to help test RCLive without a real server.
+///
+/// It waits for the initial handshake on its init socket and then streams
+/// the given messages to the monitor from a different data socket (like
+/// rcssserver does from a per client port), so that the server address
+/// update logic of the monitor gets exercised. None entries in the given
+/// messages are dropped, to simulate lost packets.
+///
+/// Once the messages are sent, it collects the commands sent by the monitor
+/// to the data socket, till nothing is got for a while, and returns them
+/// thro finish.
+#[allow(dead_code)]
+pub struct MockRcss {
+    pub addr: String,
+    pub dataaddr: String,
+    handle: std::thread::JoinHandle<Vec<String>>,
+}
+
+#[allow(dead_code)]
+impl MockRcss {
+
+    pub fn start(msgs: Vec<Option<String>>) -> MockRcss {
+        let sktinit = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+        let sktdata = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+        let addr = sktinit.local_addr().unwrap().to_string();
+        let dataaddr = sktdata.local_addr().unwrap().to_string();
+        let timeout = Some(std::time::Duration::from_millis(2000));
+        sktinit.set_read_timeout(timeout).unwrap();
+        sktdata.set_read_timeout(timeout).unwrap();
+        let handle = std::thread::spawn(move || {
+            let mut vcmds = Vec::new();
+            let mut buf = [0u8; 1024];
+            // wait for the handshake
+            let (ilen, monaddr) = match sktinit.recv_from(&mut buf) {
+                Ok(gotr) => gotr,
+                Err(_) => return vcmds,
+            };
+            vcmds.push(String::from_utf8_lossy(&buf[0..ilen]).trim_end_matches(|c: char| c == '\0' || c.is_whitespace()).to_string());
+            // stream the messages
+            for msg in msgs {
+                if let Some(msg) = msg {
+                    sktdata.send_to(msg.as_bytes(), monaddr).unwrap();
+                }
+            }
+            // collect the commands
+            while let Ok((ilen, _)) = sktdata.recv_from(&mut buf) {
+                vcmds.push(String::from_utf8_lossy(&buf[0..ilen]).trim_end_matches(|c: char| c == '\0' || c.is_whitespace()).to_string());
+            }
+            vcmds
+        });
+        MockRcss {
+            addr: addr,
+            dataaddr: dataaddr,
+            handle: handle,
+        }
+    }
+
+    /// Wait for the mock server to finish and return the commands it got.
+    pub fn finish(self) -> Vec<String> {
+        self.handle.join().unwrap()
+    }
+
+    /// Generate a json show message, with a left and right player and the ball.
+    pub fn show(time: usize, mode: &str, ball: (f32, f32), lscore: usize) -> String {
+        format!(concat!(r#"{{"type":"show","time":{},"mode":"{}","#,
+            r#""teams":[{{"side":"l","name":"TeamA","score":{}}},{{"side":"r","name":"TeamB","score":0}}],"#,
+            r#""ball":{{"x":{},"y":{},"vx":0,"vy":0}},"#,
+            r#""players":[{{"side":"l","unum":1,"type":0,"state":1,"x":-55,"y":-37,"body":0,"neck":0,"vw":90,"stamina":8000}},"#,
+            r#"{{"side":"r","unum":11,"type":0,"state":3,"x":55,"y":37,"body":180,"neck":0,"vw":90,"stamina":4000}}]}}"#),
+            time, mode, lscore, ball.0, ball.1)
+    }
+
+}
+
+#[test]
+fn test_rclive_mock_handshake() {
+    use crate::playdata::PlayData;
+    use crate::playdata::rclive::RCLive;
+    let msgs = vec![
+        Some(MockRcss::show(0, "before_kick_off", (0.0, 0.0), 0)),
+        Some(MockRcss::show(1, "play_on", (-55.0, -37.0), 0)),
+        Some(MockRcss::show(2, "goal_l", (55.0, 37.0), 1)),
+    ];
+    let near = |a: (f32, f32), b: (f32, f32)| -> bool {
+        ((a.0 - b.0).abs() < 1e-4) && ((a.1 - b.1).abs() < 1e-4)
+    };
+    let mock = MockRcss::start(msgs);
+    let mut rcl = RCLive::new(&mock.addr, 5, 0);
+    let pu = rcl.next_record();
+    assert_eq!(rcl.srvraddr(), mock.dataaddr);
+    assert_eq!(pu.timecounter, 0);
+    assert_eq!(pu.state, Some(PlayMode::BeforeKickOff));
+    assert!(near(pu.ball, (0.5, 0.5)));
+    assert_eq!(pu.teams, vec![(entities::SIDE_L, "TeamA".to_string(), 0), (entities::SIDE_R, "TeamB".to_string(), 0)]);
+    assert_eq!(pu.lteamcoded.len(), 1);
+    assert_eq!(pu.rteamcoded.len(), 1);
+    assert_eq!(pu.lteamcoded[0].0, "1");
+    assert_eq!(pu.rteamcoded[0].0, "11");
+    assert!(pu.msgs["status"].contains("Connected"));
+    let pu = rcl.next_record();
+    assert_eq!(pu.timecounter, 1);
+    assert_eq!(pu.state, Some(PlayMode::PlayOn));
+    assert!(near(pu.ball, (0.0, 0.0)));
+    let pu = rcl.next_record();
+    assert_eq!(pu.timecounter, 2);
+    assert_eq!(pu.state, Some(PlayMode::Goal(entities::SIDE_L)));
+    assert!(near(pu.ball, (1.0, 1.0)));
+    assert!(pu.msgs["score"].contains("TeamA [1]"));
+    rcl.send_record_coded(1);
+    let vcmds = mock.finish();
+    eprintln!("Test:RCLive:Mock:Cmds:{:?}", vcmds);
+    assert_eq!(vcmds, vec!["(dispinit version 5)", "(dispstart)"]);
+}
+
+#[test]
+fn test_monsrv_rclive_loopback() {
+    use crate::playdata::{PlayData, PlayUpdate, PlayerData};
+    use crate::playdata::monsrv::MonitorServer;
+    use crate::playdata::rclive::RCLive;
+    let near = |a: (f32, f32), b: (f32, f32)| -> bool {
+        ((a.0 - b.0).abs() < 1e-3) && ((a.1 - b.1).abs() < 1e-3)
+    };
+    let mut monsrv = MonitorServer::new(0).unwrap();
+    let mut rcl = RCLive::new(&format!("127.0.0.1:{}", monsrv.port()), 5, 0);
+    for _i in 0..100 {
+        monsrv.poll();
+        if monsrv.clients() > 0 {
+            break;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(10));
+    }
+    assert_eq!(monsrv.clients(), 1);
+    let mut pu = PlayUpdate::new();
+    pu.timecounter = 7;
+    pu.state = Some(PlayMode::PlayOn);
+    pu.teams = vec![(entities::SIDE_L, "TeamA".to_string(), 1), (entities::SIDE_R, "TeamB".to_string(), 0)];
+    pu.ball = (0.25, 0.75);
+    pu.lteamcoded.push(("2".to_string(), vec![PlayerData::Pos(0.1, 0.2)]));
+    pu.rteamcoded.push(("9".to_string(), vec![PlayerData::Pos(0.8, 0.6)]));
+    monsrv.send_playupdate(&pu);
+    let gotpu = rcl.next_record();
+    assert_eq!(gotpu.timecounter, 7);
+    assert_eq!(gotpu.state, Some(PlayMode::PlayOn));
+    assert!(near(gotpu.ball, pu.ball), "{:?}", gotpu.ball);
+    let pos = |teamcoded: &Vec<crate::playdata::PlayerCodedData>| -> (String, (f32, f32)) {
+        let (pid, vpd) = &teamcoded[0];
+        let npos = vpd.iter().find_map(|pd| if let PlayerData::Pos(x, y) = pd { Some((*x, *y)) } else { None }).unwrap();
+        (pid.to_string(), npos)
+    };
+    let (lpid, lpos) = pos(&gotpu.lteamcoded);
+    let (rpid, rpos) = pos(&gotpu.rteamcoded);
+    assert_eq!((lpid.as_str(), rpid.as_str()), ("2", "9"));
+    assert!(near(lpos, (0.1, 0.2)), "{:?}", lpos);
+    assert!(near(rpos, (0.8, 0.6)), "{:?}", rpos);
+}
+
+#[test]
+fn test_rclive_mock_dropped() {
+    use crate::playdata::PlayData;
+    use crate::playdata::rclive::RCLive;
+    let msgs = vec![
+        Some(MockRcss::show(1, "play_on", (0.0, 0.0), 0)),
+        None,
+        Some(MockRcss::show(3, "play_on", (0.0, 0.0), 0)),
+        Some(r#"{"type":"show","time":4,"players":[{"#.to_string()),
+        None,
+        Some(MockRcss::show(6, "play_on", (0.0, 0.0), 0)),
+    ];
+    let mock = MockRcss::start(msgs);
+    let mut rcl = RCLive::new(&mock.addr, 5, 0);
+    let mut vtimes = Vec::new();
+    for _i in 0..5 {
+        let pu = rcl.next_record();
+        vtimes.push(pu.timecounter);
+    }
+    eprintln!("Test:RCLive:Mock:Dropped:Times:{:?}", vtimes);
+    // the partial message and the timeout after the last message, give empty updates
+    assert_eq!(vtimes, vec![1, 3, 0, 6, 0]);
+    let vcmds = mock.finish();
+    assert_eq!(vcmds, vec!["(dispinit version 5)"]);
+}
+
+#[test]
+fn test_cfg_set() {
+    use crate::Cfg;
+    let mut cfg = Cfg::default();
+    cfg.set("fps", "12").unwrap();
+    cfg.set("lteam_color", "10, 20,30").unwrap();
+    cfg.set("pitch_rect", "0.1,0.1,0.9,0.9").unwrap();
+    cfg.set("show_stamina", "no").unwrap();
+    cfg.set("summary_aiscores", "A").unwrap();
+    cfg.set("goalies", "1, 2").unwrap();
+    assert_eq!(cfg.fps, 12.0);
+    assert_eq!(cfg.lteam_color, Color::RGB(10, 20, 30));
+    assert_eq!(cfg.pitch_rect, ((0.1, 0.1), (0.9, 0.9)));
+    assert!(!cfg.show_stamina);
+    assert_eq!(cfg.goalies, ("1".to_string(), "2".to_string()));
+    assert!(cfg.set("goalies", "1").is_err());
+    assert!(cfg.set("lteam_color", "10,20").is_err());
+    assert!(cfg.set("show_cards", "maybe").is_err());
+    assert!(cfg.set("summary_aidistances", "X").is_err());
+    assert!(cfg.set("nosuchkey", "1").is_err());
+    // The printed config should be loadable back
+    let mut cfg2 = Cfg::default();
+    for (key, svalue) in cfg.entries() {
+        cfg2.set(key, &svalue).unwrap();
+    }
+    assert_eq!(cfg2.entries(), cfg.entries());
+}
+
+#[test]
+fn test_find_ttf_fonts() {
+    let vfonts = sdlx::find_ttf_fonts("/tmp/nosuchfont.ttf");
+    eprintln!("Test:FindTtfFonts:{:?}", vfonts);
+    assert_eq!(vfonts[0], "/tmp/nosuchfont.ttf");
+    for sfont in &vfonts[1..] {
+        assert!(sfont.to_lowercase().ends_with(".ttf"));
+    }
+    let vfonts = sdlx::find_ttf_fonts("");
+    assert!(!vfonts.contains(&String::new()));
+}
+
+#[test]
+fn test_rcss_fieldgeom() {
+    use crate::playdata::{rcss, Params};
+    let mut field = rcss::FieldGeom::new();
+    let mut params = Params::new();
+    assert!(!field.update_from_params(&params));
+    params.insert("server_param.goal_width".to_string(), "14.02".to_string());
+    assert!(!field.update_from_params(&params));
+    params.insert("server_param.goal_width".to_string(), "20".to_string());
+    assert!(field.update_from_params(&params));
+    assert_eq!(field.goal_width, 20.0);
+    params.insert("server_param.goal_width".to_string(), "wide".to_string());
+    assert!(!field.update_from_params(&params));
+    assert_eq!(field.goal_width, 20.0);
+}
+
+#[test]
+fn test_sdlx_xview() {
+    let mut view = sdlx::XView::new();
+    assert_eq!(view.drect(), ((0.0, 0.0), (1.0, 1.0)));
+    // Panning at zoom 1 is not possible
+    view.pan(0.1, 0.1);
+    assert_eq!(view.center, (0.5, 0.5));
+    // Zooming about a point keeps it at the same place on the screen
+    let about = (0.25, 0.25);
+    view.zoom_by(2.0, about);
+    assert_eq!(view.zoom, 2.0);
+    assert_eq!(view.s2v(about), about);
+    // The view remains within the normal space
+    view.center_on((0.95, 0.05));
+    assert_eq!(view.drect(), ((0.5, 0.0), (1.0, 0.5)));
+    view.pan(0.2, 0.0);
+    assert_eq!(view.center, (0.65, 0.25));
+    view.zoom_by(100.0, view.center);
+    assert_eq!(view.zoom, sdlx::XVIEW_ZOOM_MAX);
+    view.zoom_by(0.01, view.center);
+    assert_eq!(view.drect(), ((0.0, 0.0), (1.0, 1.0)));
+}
+
+#[test]
+fn test_possession() {
+    use crate::proc::possession::{self, Possession};
+    let mut poss = Possession::new();
+    let ball = (0.5, 0.5);
+    let near = (0.5 + 0.5/110.0, 0.5);
+    let far = (0.5 + 5.0/110.0, 0.5);
+    let lnear = vec![(entities::SIDE_L, "1".to_string(), near), (entities::SIDE_R, "1".to_string(), far)];
+    let both = vec![(entities::SIDE_L, "1".to_string(), near), (entities::SIDE_R, "1".to_string(), near)];
+    let none = vec![(entities::SIDE_L, "1".to_string(), far), (entities::SIDE_R, "1".to_string(), far)];
+    poss.update(1, &Some(PlayMode::PlayOn), ball, &lnear, None, 0);
+    poss.update(2, &None, ball, &lnear, None, 0);
+    // Ball away from everyone, stays with the last holder
+    poss.update(3, &None, ball, &none, None, 0);
+    assert_eq!(poss.lteam.time, 2.0);
+    assert_eq!(poss.player(entities::SIDE_L, "1"), 2.0);
+    // Contested and dead ball time is neutral
+    poss.update(4, &None, ball, &both, None, 0);
+    poss.update(5, &Some(PlayMode::parse("free_kick_r")), ball, &lnear, None, 0);
+    poss.update(6, &None, ball, &lnear, None, 0);
+    assert_eq!(poss.lteam.time, 2.0);
+    assert_eq!(poss.rteam.time, 0.0);
+    assert_eq!(poss.contestedtime, 1.0);
+    assert_eq!(poss.deadtime, 2.0);
+    // A seek is not credited
+    poss.update(3100, &Some(PlayMode::PlayOn), ball, &lnear, None, 1);
+    poss.update(3101, &None, ball, &lnear, None, 1);
+    assert_eq!(poss.lteam.periods, vec![2.0, 1.0]);
+    assert_eq!(poss.lteam.thirds[possession::THIRD_MIDDLE], 3.0);
+    // Seeking back rolls back the times, so replayed cycles are not credited again
+    poss.update(3, &Some(PlayMode::PlayOn), ball, &lnear, None, 0);
+    assert_eq!((poss.lteam.time, poss.deadtime, poss.contestedtime), (1.0, 0.0, 0.0));
+    assert_eq!(poss.player(entities::SIDE_L, "1"), 1.0);
+    poss.update(4, &None, ball, &lnear, None, 0);
+    assert_eq!(poss.lteam.periods, vec![2.0]);
+    assert_eq!(Possession::third(entities::SIDE_L, 30.0), possession::THIRD_ATTACKING);
+    assert_eq!(Possession::third(entities::SIDE_R, 30.0), possession::THIRD_DEFENSIVE);
+    assert_eq!(Possession::percentages(3.0, 1.0), (75.0, 25.0));
+}
+
+#[test]
+fn test_possession_sequences() {
+    use crate::proc::possession::{Possession, SeqOutcome, SEQ_EVENT_REGAIN};
+    let mut poss = Possession::new();
+    let ball = (0.5, 0.5);
+    let near = (0.5 + 0.5/110.0, 0.5);
+    let far = (0.5 + 5.0/110.0, 0.5);
+    let player = |side: char, pid: &str, npos: (f32, f32)| (side, pid.to_string(), npos);
+    poss.update(1, &Some(PlayMode::parse("kick_off_l")), ball, &vec![], None, 0);
+    poss.update(2, &Some(PlayMode::PlayOn), ball, &vec![player('l', "9", near), player('l', "10", far)], None, 0);
+    // Pass to a team mate
+    poss.update(5, &None, ball, &vec![player('l', "9", far), player('l', "10", near)], None, 0);
+    // Lost to the other team
+    poss.update(8, &None, ball, &vec![player('l', "10", far), player('r', "4", near)], None, 0);
+    // Out of play
+    poss.update(12, &Some(PlayMode::parse("kick_in_l")), ball, &vec![player('r', "4", near)], None, 0);
+    assert_eq!(poss.sequences.len(), 2);
+    let seq = &poss.sequences[0];
+    assert_eq!((seq.side, seq.start_time, seq.end_time), ('l', 2, 8));
+    assert_eq!(seq.start_event, "kick_off_l");
+    assert_eq!(seq.end_event, "r4");
+    assert_eq!(seq.players, vec!["9".to_string(), "10".to_string()]);
+    assert_eq!(seq.passes, 1);
+    assert_eq!(seq.outcome, SeqOutcome::Loss);
+    assert_eq!(seq.path.len(), 3);
+    let seq = &poss.sequences[1];
+    assert_eq!(seq.start_event, SEQ_EVENT_REGAIN);
+    assert_eq!((seq.end_event.as_str(), seq.outcome.clone()), ("kick_in_l", SeqOutcome::Out));
+    // A goalie catch ends the sequence as a shot
+    poss.update(13, &Some(PlayMode::PlayOn), ball, &vec![player('l', "2", near)], None, 0);
+    poss.update(15, &Some(PlayMode::parse("free_kick_r")), ball, &vec![player('r', "1", near)], Some('r'), 0);
+    assert_eq!(poss.sequences[2].outcome, SeqOutcome::Shot);
+    // Seeking back drops the sequences not over before the seeked time
+    poss.update(10, &None, ball, &vec![], None, 0);
+    assert_eq!(poss.sequences.len(), 1);
+}
+
+#[test]
+fn test_shots() {
+    use crate::proc::actions::AIAction;
+    use crate::proc::shots::{Shots, ShotOutcome, XGModel};
+    use crate::playdata::rcss::rcss_to_npos;
+    let xgmodel = XGModel::default();
+    assert!(xgmodel.xg(11.0, 1.1, 1) > xgmodel.xg(25.0, 0.5, 1));
+    assert!(xgmodel.xg(11.0, 1.1, 1) > xgmodel.xg(11.0, 1.1, 3));
+    let mut shots = Shots::new();
+    // Penalty spot, with a defender in the cone and one outside
+    let (dist, angle, defenders) = shots.shot_geometry('l', (41.5, 0.0), &vec![rcss_to_npos((50.0, 0.0)), rcss_to_npos((50.0, 20.0))]);
+    assert!((dist - 11.0).abs() < 0.01);
+    assert!((angle - 1.13).abs() < 0.01);
+    assert_eq!(defenders, 1);
+    // A kick away from the goal is not a shot, nor is one from far away
+    shots.handle_action(10, 'l', "9", &AIAction::Kick, rcss_to_npos((40.0, 0.0)), &vec![]);
+    shots.handle_cycle(11, &None, rcss_to_npos((38.0, 0.0)));
+    shots.handle_action(20, 'l', "9", &AIAction::Kick, rcss_to_npos((0.0, 0.0)), &vec![]);
+    shots.handle_cycle(21, &None, rcss_to_npos((2.0, 0.0)));
+    assert_eq!(shots.shots.len(), 0);
+    // On target, goal
+    shots.handle_action(30, 'l', "9", &AIAction::Kick, rcss_to_npos((40.0, 0.0)), &vec![]);
+    shots.handle_cycle(31, &None, rcss_to_npos((42.0, 0.5)));
+    shots.handle_cycle(35, &Some(PlayMode::Goal('l')), rcss_to_npos((53.0, 1.0)));
+    // Off target, caught by the goalie of the other side, isnt a save
+    shots.handle_action(40, 'r', "7", &AIAction::Kick, rcss_to_npos((-35.0, 10.0)), &vec![]);
+    shots.handle_cycle(41, &None, rcss_to_npos((-37.0, 10.2)));
+    shots.handle_action(45, 'l', "1", &AIAction::Catch, rcss_to_npos((-50.0, 13.0)), &vec![]);
+    // On target, saved by the goalie of the other side
+    shots.handle_action(50, 'r', "7", &AIAction::Kick, rcss_to_npos((-35.0, 0.0)), &vec![]);
+    shots.handle_cycle(51, &None, rcss_to_npos((-37.0, 0.2)));
+    shots.handle_action(55, 'l', "1", &AIAction::Catch, rcss_to_npos((-50.0, 2.0)), &vec![]);
+    assert_eq!(shots.shots.len(), 3);
+    assert_eq!((shots.shots[0].ontarget, shots.shots[0].outcome.clone()), (true, ShotOutcome::Goal));
+    assert_eq!((shots.shots[1].ontarget, shots.shots[1].outcome.clone()), (false, ShotOutcome::OffTarget));
+    assert_eq!((shots.shots[2].ontarget, shots.shots[2].outcome.clone()), (true, ShotOutcome::Saved));
+    assert_eq!(shots.stats('l').2, 1);
+    assert_eq!(shots.xg_cumul('r').len(), 5);
+    shots.seek_to(40);
+    assert_eq!(shots.shots.len(), 1);
+}
+
+#[test]
+fn test_offside() {
+    use crate::proc::offside::Offside;
+    use crate::playdata::rcss::rcss_to_npos;
+    let players = vec![
+        ('l', "9".to_string(), rcss_to_npos((10.0, 0.0))),
+        ('l', "10".to_string(), rcss_to_npos((35.0, 5.0))),
+        ('l', "11".to_string(), rcss_to_npos((25.0, -5.0))),
+        ('r', "1".to_string(), rcss_to_npos((50.0, 0.0))),
+        ('r', "2".to_string(), rcss_to_npos((30.0, 0.0))),
+        ('r', "3".to_string(), rcss_to_npos((20.0, 0.0))),
+    ];
+    let line = Offside::line('r', &players).unwrap();
+    assert!((line - 30.0).abs() < 0.01);
+    let offside = Offside::players_offside('l', &players, rcss_to_npos((10.0, 0.0)));
+    assert_eq!(offside.len(), 1);
+    assert_eq!(offside[0].0, "10");
+    // Players behind the ball are not offside
+    assert_eq!(Offside::players_offside('l', &players, rcss_to_npos((40.0, 0.0))).len(), 0);
+    let mut osd = Offside::new();
+    osd.handle_kick(100, 'l', "9", &players, rcss_to_npos((10.0, 0.0)));
+    osd.handle_cycle(105, &Some(PlayMode::parse("offside_l")), rcss_to_npos((36.0, 5.0)));
+    osd.handle_cycle(106, &Some(PlayMode::parse("offside_l")), rcss_to_npos((36.0, 5.0)));
+    assert_eq!(osd.calls.len(), 1);
+    assert_eq!(osd.calls[0].passer.as_deref(), Some("9"));
+    assert_eq!(osd.calls[0].playerid.as_deref(), Some("10"));
+    assert_eq!(osd.calls[0].passtime, Some(100));
+    assert_eq!(osd.counts('l'), vec![("10".to_string(), 1)]);
+}
+
+#[test]
+fn test_rcss_playmode() {
+    let modes = ["before_kick_off", "play_on", "kick_off_l", "kick_in_r", "free_kick_l", "corner_kick_r",
+        "goal_kick_l", "goal_r", "drop_ball", "offside_l", "penalty_kick_r", "first_half_over", "time_over",
+        "foul_charge_r", "back_pass_l", "catch_fault_r", "indirect_free_kick_l", "penalty_setup_r",
+        "penalty_score_l", "goalie_catch_ball_r", "illegal_defense_l", "extend_half", "half_time",
+        "time_up", "time_up_without_a_team", "time_extended"];
+    for smode in modes {
+        let mode = PlayMode::parse(smode);
+        assert!(!matches!(mode, PlayMode::Unknown(_)), "{}", smode);
+        assert_eq!(mode.to_string(), smode);
+    }
+    assert_eq!(PlayMode::parse("corner_kick_l"), PlayMode::CornerKick(entities::SIDE_L));
+    assert!(PlayMode::parse("half_time").is_period_end());
+    assert!(PlayMode::parse("time_up").is_period_end());
+    assert_eq!(PlayMode::parse("offside_r").side(), Some(entities::SIDE_R));
+    assert_eq!(PlayMode::parse("some_new_mode_l"), PlayMode::Unknown("some_new_mode_l".to_string()));
+    assert_eq!(PlayMode::parse("some_new_mode_l").to_string(), "some_new_mode_l");
+}
+
+#[test]
+fn test_periods() {
+    use crate::proc::periods::{Periods, PeriodKind};
+    use crate::proc::actions::{ActionsInfo, ActionData, AIAction};
+    let mut periods = Periods::new();
+    assert!(periods.handle_mode(0, &PlayMode::KickOff('l')));
+    assert!(!periods.handle_mode(3000, &PlayMode::FirstHalfOver));
+    assert!(periods.handle_mode(3000, &PlayMode::KickOff('r')));
+    periods.handle_mode(6000, &PlayMode::TimeOver);
+    periods.handle_mode(6000, &PlayMode::KickOff('l'));
+    periods.handle_mode(7000, &PlayMode::TimeOver);
+    assert!(periods.handle_mode(7000, &PlayMode::PenaltySetup('l')));
+    periods.handle_mode(7000, &PlayMode::PenaltyScore('l'));
+    periods.handle_mode(7000, &PlayMode::PenaltySetup('r'));
+    periods.handle_mode(7000, &PlayMode::PenaltyMiss('r'));
+    let kinds: Vec<PeriodKind> = periods.periods.iter().map(|p| p.kind.clone()).collect();
+    assert_eq!(kinds, vec![PeriodKind::Half(1), PeriodKind::Half(2), PeriodKind::ExtraHalf(1), PeriodKind::PenaltyShootout]);
+    assert_eq!(periods.name_of(2999), "H1");
+    assert_eq!(periods.name_of(3000), "H2");
+    assert_eq!(periods.range(1), (3000, 6000));
+    assert_eq!(periods.penalty_score('l'), (1, 1));
+    assert_eq!(periods.penalty_score('r'), (1, 0));
+    periods.seek_to(4000);
+    assert_eq!(periods.periods.len(), 2);
+    assert!(periods.current().end.is_none());
+    // The kick off of the 2nd half shouldnt penalise the last kicker of the 1st half
+    let mut ainfo = ActionsInfo::new(&vec!["9", "10"], &vec!["9", "10"]);
+    ainfo.handle_cycle(0, &Some(PlayMode::KickOff('l')), (0.5, 0.5));
+    ainfo.handle_action(ActionData::new(1, 'l', "9".to_string(), (0.5, 0.5), AIAction::Kick));
+    ainfo.handle_action(ActionData::new(20, 'l', "10".to_string(), (0.6, 0.5), AIAction::Kick));
+    ainfo.handle_cycle(3000, &Some(PlayMode::FirstHalfOver), (0.5, 0.5));
+    ainfo.handle_cycle(3000, &Some(PlayMode::KickOff('r')), (0.5, 0.5));
+    ainfo.handle_action(ActionData::new(3000, 'r', "9".to_string(), (0.5, 0.5), AIAction::Kick));
+    let ssummary = ainfo.summary_periods();
+    let vlines: Vec<&str> = ssummary.lines().collect();
+    assert!(vlines[1].starts_with("H1:0-3000: L:Score:0.60"), "{}", vlines[1]);
+    assert!(vlines[2].starts_with("H2:3000-: L:Score:0.00"), "{}", vlines[2]);
+    assert!(vlines[2].contains(" R:Score:0.02"), "{}", vlines[2]);
+    let splayer = ainfo.summary_player('l', "10", true);
+    assert!(splayer.ends_with("Periods:H1:0.30,H2:0.00"), "{}", splayer);
+    assert!(vlines[2].contains(" R:Score:0.02:Kicks:1:"), "{}", vlines[2]);
+    // Seeking back into the 1st half should forget the kicks of the 2nd half
+    ainfo.seek(-1);
+    ainfo.handle_action(ActionData::new(2500, 'l', "9".to_string(), (0.7, 0.5), AIAction::Kick));
+    let ssummary = ainfo.summary_periods();
+    let vlines: Vec<&str> = ssummary.lines().collect();
+    assert_eq!(vlines.len(), 2, "{}", ssummary);
+    assert!(vlines[1].contains(" L:Score:1.20:Kicks:3:"), "{}", vlines[1]);
+    assert!(vlines[1].contains(" R:Score:0.00:Kicks:0:"), "{}", vlines[1]);
+}
+
+#[test]
+fn test_setpieces() {
+    use crate::proc::setpieces::{SetPieces, SetPieceOutcome};
+    use crate::proc::actions::AIAction;
+    use crate::proc::periods::Periods;
+    let mut sps = SetPieces::new();
+    sps.window = 50;
+    let shots = Vec::new();
+    let mut periods = Periods::new();
+    // A corner kick which is retained
+    sps.handle_cycle(100, &Some(PlayMode::parse("corner_kick_l")), (1.0, 0.0), &shots, &periods);
+    sps.handle_cycle(101, &Some(PlayMode::parse("corner_kick_l")), (1.0, 0.0), &shots, &periods);
+    sps.handle_action('l', "7", &AIAction::Kick);
+    sps.handle_action('l', "9", &AIAction::Kick);
+    sps.handle_cycle(150, &Some(PlayMode::PlayOn), (0.9, 0.5), &shots, &periods);
+    assert_eq!(sps.setpieces.len(), 1);
+    assert_eq!(sps.setpieces[0].kind, "corner_kick");
+    assert_eq!(sps.setpieces[0].taker.as_deref(), Some("7"));
+    assert_eq!(sps.setpieces[0].outcome, SetPieceOutcome::Retained);
+    // A kick in which is lost, resolved by the next set piece
+    sps.handle_cycle(200, &Some(PlayMode::parse("kick_in_l")), (0.5, 0.0), &shots, &periods);
+    sps.handle_action('l', "7", &AIAction::Kick);
+    sps.handle_action('r', "3", &AIAction::Tackle);
+    sps.handle_cycle(220, &Some(PlayMode::parse("free_kick_r")), (0.5, 0.0), &shots, &periods);
+    assert_eq!(sps.setpieces[1].outcome, SetPieceOutcome::Lost);
+    // A free kick which leads to a goal
+    sps.handle_action('r', "10", &AIAction::Kick);
+    sps.handle_cycle(230, &Some(PlayMode::Goal('r')), (0.0, 0.5), &shots, &periods);
+    assert_eq!(sps.setpieces[2].outcome, SetPieceOutcome::Goal);
+    assert_eq!(sps.stats('l', None), (2, 0, 0, 1, 1));
+    assert_eq!(sps.stats('l', Some("corner_kick")), (1, 0, 0, 1, 0));
+    assert_eq!(sps.takers('l'), vec![("7".to_string(), 2, 1)]);
+    assert_eq!(sps.takers('r'), vec![("10".to_string(), 1, 1)]);
+    sps.seek_to(210);
+    assert_eq!(sps.setpieces.len(), 2);
+    assert_eq!(sps.setpieces[1].outcome, SetPieceOutcome::Pending);
+    // A free kick which is resolved by the end of the half, within its window
+    sps.handle_cycle(2980, &Some(PlayMode::parse("free_kick_l")), (0.3, 0.5), &shots, &periods);
+    sps.handle_action('l', "7", &AIAction::Kick);
+    periods.handle_mode(3000, &PlayMode::HalfTime);
+    sps.handle_cycle(3000, &Some(PlayMode::HalfTime), (0.4, 0.5), &shots, &periods);
+    assert_eq!(sps.setpieces[2].kind, "free_kick");
+    assert_eq!(sps.setpieces[2].outcome, SetPieceOutcome::Retained);
+}
+
+#[test]
+fn test_ballstats() {
+    use crate::proc::actions::{ActionsInfo, ActionData, AIAction};
+    let mut ainfo = ActionsInfo::new(&vec!["2", "9"], &vec!["3", "5"]);
+    let mut act = |time: usize, side: char, pid: &str, action: AIAction| {
+        ainfo.handle_action(ActionData::new(time, side, pid.to_string(), (0.5, 0.5), action));
+    };
+    act(1, 'l', "9", AIAction::Kick);
+    act(20, 'r', "5", AIAction::Kick);
+    act(40, 'r', "3", AIAction::Tackle);
+    act(50, 'l', "2", AIAction::Kick);
+    act(60, 'l', "9", AIAction::Tackle);
+    act(70, 'l', "9", AIAction::Kick);
+    let has = |ainfo: &ActionsInfo, side: char, pid: &str, stats: &str| ainfo.summary_player(side, pid, true).contains(stats);
+    assert!(has(&ainfo, 'l', "9", ":Intercepts:0:Losses:1:TacklesWon:1:TacklesFailed:0:"));
+    assert!(has(&ainfo, 'r', "5", ":Intercepts:1:Losses:0:TacklesWon:0:TacklesFailed:0:"));
+    assert!(has(&ainfo, 'r', "3", ":Intercepts:0:Losses:0:TacklesWon:0:TacklesFailed:1:"));
+    assert!(has(&ainfo, 'l', "2", ":Intercepts:0:Losses:0:TacklesWon:0:TacklesFailed:0:"));
+    // A tackle or catch right after a kick by the other side
+    let act = |ainfo: &mut ActionsInfo, time: usize, side: char, pid: &str, action: AIAction, npos: (f32, f32)| {
+        ainfo.handle_action(ActionData::new(time, side, pid.to_string(), npos, action));
+    };
+    act(&mut ainfo, 80, 'r', "3", AIAction::Tackle, (0.5, 0.5));
+    act(&mut ainfo, 90, 'r', "3", AIAction::Kick, (0.5, 0.5));
+    act(&mut ainfo, 100, 'l', "2", AIAction::Catch, (0.5, 0.5));
+    assert!(has(&ainfo, 'l', "9", ":Intercepts:0:Losses:2:TacklesWon:1:TacklesFailed:0:"));
+    assert!(has(&ainfo, 'r', "3", ":Intercepts:1:Losses:1:TacklesWon:1:TacklesFailed:1:"));
+    assert!(has(&ainfo, 'l', "2", ":Intercepts:1:Losses:0:TacklesWon:0:TacklesFailed:0:"));
+    // A catch which saves a shot on target is neither a interception nor a loss
+    act(&mut ainfo, 110, 'l', "2", AIAction::Kick, (0.9, 0.5));
+    ainfo.handle_cycle(111, &None, (0.95, 0.5));
+    act(&mut ainfo, 112, 'r', "5", AIAction::Catch, (0.98, 0.5));
+    assert_eq!(ainfo.shots.shots.len(), 1);
+    assert_eq!(ainfo.shots.shots[0].outcome, crate::proc::shots::ShotOutcome::Saved);
+    assert!(has(&ainfo, 'l', "2", ":Intercepts:1:Losses:0:TacklesWon:0:TacklesFailed:0:"));
+    assert!(has(&ainfo, 'r', "5", ":Intercepts:1:Losses:0:TacklesWon:0:TacklesFailed:0:"));
+}
+
+#[test]
+fn test_kickcmds() {
+    use crate::playdata::PlayerCmd;
+    use crate::proc::actions::{ActionsInfo, ActionData, AIAction};
+    let mut ainfo = ActionsInfo::new(&vec!["9", "10"], &vec!["9"]);
+    let kick = |power: f32| vec![PlayerCmd::Kick(power, -30.0), PlayerCmd::Dash(50.0, 0.0)];
+    // A kick in the state, confirmed by the command
+    let actd = ActionData::new(1, 'l', "9".to_string(), (0.5, 0.5), AIAction::Kick).with_cmds(kick(80.0));
+    assert_eq!(actd.kick, Some((80.0, -30.0)));
+    ainfo.handle_action(actd);
+    // A kick not seen in the state, attributed based on the command
+    ainfo.handle_action(ActionData::new(20, 'l', "10".to_string(), (0.6, 0.5), AIAction::None).with_cmds(kick(40.0)));
+    // A failed kick isnt attributed
+    ainfo.handle_action(ActionData::new(40, 'r', "9".to_string(), (0.6, 0.5), AIAction::None).with_cmds(kick(100.0)).with_kickfault(true));
+    let s9 = ainfo.summary_player('l', "9", true);
+    assert!(s9.contains(":Kicks:1:") && s9.contains(":KickCmds:1:AvgKickPower:80.0:DashCmds:1:KicksConfirmed:1:KicksAttributed:0:"), "{}", s9);
+    let s10 = ainfo.summary_player('l', "10", true);
+    assert!(s10.contains(":Kicks:1:") && s10.contains(":KicksConfirmed:0:KicksAttributed:1:"), "{}", s10);
+    let sr9 = ainfo.summary_player('r', "9", true);
+    assert!(sr9.contains(":Kicks:0:") && sr9.contains(":KickCmds:1:AvgKickPower:100.0:DashCmds:1:KicksConfirmed:0:KicksAttributed:0:"), "{}", sr9);
+    let jsummary = ainfo.summary_json(true).to_string();
+    assert!(jsummary.contains(r#""kicks_confirmed":1,"kicks_attributed":0"#), "{}", jsummary);
+}
+
+#[test]
+fn test_goalies() {
+    use crate::proc::goalies::Goalies;
+    use crate::proc::actions::AIAction;
+    use crate::playdata::rcss::{self, rcss_to_npos};
+    assert!(rcss::is_goalie(rcss::STATE_ENABLE | rcss::STATE_GOALIE | rcss::STATE_CATCH));
+    assert!(!rcss::is_goalie(rcss::STATE_ENABLE | rcss::STATE_KICK));
+    let mut gks = Goalies::new();
+    gks.set_goalie('r', "1");
+    gks.handle_goalie_flag('r', "5");
+    assert_eq!(gks.get('r').playerid.as_deref(), Some("1"));
+    // The left goalie is identified from the state
+    gks.handle_goalie_flag('l', "1");
+    gks.handle_action(10, 'l', "1", &AIAction::Catch, rcss_to_npos((-50.0, 0.0)));
+    gks.handle_action(12, 'l', "1", &AIAction::Kick, rcss_to_npos((-50.0, 0.0)));
+    gks.handle_action(20, 'l', "7", &AIAction::Kick, rcss_to_npos((-20.0, 0.0)));
+    gks.handle_action(30, 'l', "1", &AIAction::Catch, rcss_to_npos((-50.0, 0.0)));
+    gks.handle_action(32, 'l', "1", &AIAction::Kick, rcss_to_npos((-50.0, 0.0)));
+    gks.handle_action(40, 'r', "9", &AIAction::Kick, rcss_to_npos((-10.0, 0.0)));
+    // Sweeping outside the penalty area
+    gks.handle_action(50, 'l', "1", &AIAction::Kick, rcss_to_npos((-30.0, 0.0)));
+    // Only the goalie makes saves
+    gks.handle_save(60, 'l', "1");
+    gks.handle_save(70, 'l', "7");
+    gks.handle_catch_fault(100, 'l', "1");
+    gks.handle_catch_fault(101, 'l', "1");
+    gks.handle_catch_fault(110, 'l', "1");
+    let players = vec![('l', "1".to_string(), rcss_to_npos((-50.0, 0.0))), ('r', "1".to_string(), rcss_to_npos((45.0, 0.0)))];
+    gks.handle_cycle(120, &Some(PlayMode::PlayOn), &players);
+    gks.handle_cycle(121, &None, &vec![('l', "1".to_string(), rcss_to_npos((-48.0, 0.0)))]);
+    gks.handle_cycle(122, &Some(PlayMode::Goal('r')), &players);
+    let lgk = gks.get('l');
+    assert_eq!(lgk.catches, 2);
+    assert_eq!(lgk.saves, 1);
+    assert_eq!((lgk.distkicks, lgk.distretained, lgk.distlost), (2, 1, 1));
+    assert_eq!(lgk.sweeps, 1);
+    assert_eq!(lgk.failedcatches, 2);
+    assert_eq!(lgk.goalsconceded, 1);
+    assert!((lgk.dist_avg() - 3.5).abs() < 0.01);
+    assert!((lgk.distmax - 4.5).abs() < 0.01);
+    assert!((gks.get('r').distmax - 7.5).abs() < 0.01);
+    // Seeking back rewinds the stats, without counting the goal twice
+    gks.seek_to(122);
+    assert_eq!(gks.get('l').goalsconceded, 0);
+    gks.handle_cycle(122, &Some(PlayMode::Goal('r')), &players);
+    gks.handle_cycle(123, &Some(PlayMode::Goal('r')), &players);
+    assert_eq!(gks.get('l').goalsconceded, 1);
+    // The play on in effect is retained, so the distance is still tracked
+    gks.seek_to(121);
+    gks.handle_cycle(121, &None, &vec![('l', "1".to_string(), rcss_to_npos((-45.0, 0.0)))]);
+    assert_eq!(gks.get('l').goalsconceded, 0);
+    assert!((gks.get('l').distmax - 7.5).abs() < 0.01);
+    gks.seek_to(105);
+    let lgk = gks.get('l');
+    assert_eq!((lgk.catches, lgk.saves, lgk.sweeps, lgk.failedcatches), (2, 1, 1, 1));
+    assert_eq!(lgk.dist_avg(), 0.0);
+}
+
+#[test]
+fn test_rcg_seek_to() {
+    use crate::playdata::PlayData;
+    use crate::playdata::rcg::Rcg;
+    let fname = "/tmp/gppgnd_test_seekto.rcg";
+    let mut sdata = String::from("ULG5\n(playmode 0 before_kick_off)\n");
+    for time in 1..=5 {
+        if time == 3 {
+            sdata.push_str("(playmode 3 play_on)\n");
+        }
+        sdata.push_str(&format!("(show {} ((b) 0 0 0 0))\n", time));
+    }
+    std::fs::write(fname, sdata).unwrap();
+    let mut rcg = Rcg::new(fname, 10.0);
+    assert_eq!(rcg.next_record().timecounter, 1);
+    rcg.seek_to(4);
+    assert_eq!(rcg.next_record().timecounter, 4);
+    rcg.seek_to(3);
+    let pu = rcg.next_record();
+    assert_eq!(pu.timecounter, 3);
+    assert_eq!(pu.state, Some(crate::playdata::rcss::PlayMode::PlayOn));
+    rcg.seek_to(1);
+    assert_eq!(rcg.next_record().timecounter, 1);
+    assert_eq!(rcg.next_record().timecounter, 2);
+    rcg.seek_to(5);
+    assert_eq!(rcg.next_record().timecounter, 5);
+    assert!(!rcg.bdone());
+}